@@ -1,6 +1,6 @@
 use crate::signature_db;
 use crate::types::{AbiDecodeResult, DecodedValue};
-use alloy_dyn_abi::DynSolType;
+use alloy_dyn_abi::{DynSolType, FunctionExt};
 use eyre::{Result, WrapErr};
 
 /// Decode ABI-encoded data.
@@ -125,6 +125,25 @@ fn decode_with_types(data: &[u8], type_spec: &str) -> Result<AbiDecodeResult> {
     })
 }
 
+/// Values `func` returned in `data`, typed by its ABI outputs. `None` when
+/// it declares no outputs or `data` does not decode as them.
+pub(crate) fn decode_outputs(func: &alloy_json_abi::Function, data: &[u8]) -> Option<Vec<DecodedValue>> {
+    if func.outputs.is_empty() {
+        return None;
+    }
+    let values = func.abi_decode_output(data).ok()?;
+    Some(
+        func.outputs
+            .iter()
+            .zip(&values)
+            .map(|(param, value)| DecodedValue {
+                type_name: param.selector_type().into_owned(),
+                value: format_sol_value(value),
+            })
+            .collect(),
+    )
+}
+
 /// Try to decode parameter data using a function signature string like "transfer(address,uint256)".
 fn try_decode_with_signature(
     selector: &str,
@@ -233,7 +252,7 @@ fn total_min_size(types: &[DynSolType]) -> usize {
 }
 
 /// Format a DynSolValue into a human-readable string.
-fn format_sol_value(val: &alloy_dyn_abi::DynSolValue) -> String {
+pub(crate) fn format_sol_value(val: &alloy_dyn_abi::DynSolValue) -> String {
     use alloy_dyn_abi::DynSolValue;
     match val {
         DynSolValue::Bool(b) => b.to_string(),
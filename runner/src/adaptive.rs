@@ -0,0 +1,151 @@
+use crate::calldata::{encode_calldata_with_strategy, CallStrategy};
+use crate::revert;
+use alloy_dyn_abi::DynSolValue;
+use alloy_json_abi::{Function, Param};
+use alloy_primitives::{keccak256, Address, U256};
+
+/// Custom errors recognized by name, OpenZeppelin 5's among them, so their
+/// remedies apply even when the contract's ABI doesn't declare them.
+const KNOWN_ERRORS: &[&str] = &[
+    "ERC20InsufficientAllowance(address,uint256,uint256)",
+    "ERC20InsufficientBalance(address,uint256,uint256)",
+    "InsufficientAllowance()",
+    "InsufficientBalance()",
+    "EnforcedPause()",
+    "ExpectedPause()",
+    "Expired()",
+    "DeadlineExpired()",
+];
+
+/// Amount minted to the caller when a call runs out of balance: far above
+/// anything the strategies send, far below overflow.
+const MINT_AMOUNT: u128 = 10u128.pow(27);
+
+/// A corrective step `--adaptive` takes after a recognized revert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remedy {
+    /// Approve the caller and the contract itself to spend the caller's tokens.
+    Approve,
+    /// Mint tokens to the caller.
+    Mint,
+    /// Unpause the contract.
+    Unpause,
+    /// Pass a far-future timestamp where the function takes a deadline.
+    FutureDeadline,
+}
+
+impl Remedy {
+    pub fn label(self) -> &'static str {
+        match self {
+            Remedy::Approve => "approve",
+            Remedy::Mint => "mint",
+            Remedy::Unpause => "unpause",
+            Remedy::FutureDeadline => "future_deadline",
+        }
+    }
+}
+
+/// Lowercased fragments of revert reasons and error names, mapped to their
+/// remedy; the first match wins, so a reason saying the contract is *not*
+/// paused never asks for `unpause`.
+const REMEDIES: &[(&str, Option<Remedy>)] = &[
+    ("allowance", Some(Remedy::Approve)),
+    ("exceeds balance", Some(Remedy::Mint)),
+    ("insufficient balance", Some(Remedy::Mint)),
+    ("insufficientbalance", Some(Remedy::Mint)),
+    ("not paused", None),
+    ("expectedpause", None),
+    ("paused", Some(Remedy::Unpause)),
+    ("enforcedpause", Some(Remedy::Unpause)),
+    ("expired", Some(Remedy::FutureDeadline)),
+    ("deadline", Some(Remedy::FutureDeadline)),
+    ("too old", Some(Remedy::FutureDeadline)),
+];
+
+/// Reason [`remedy_for`] reads from a call's revert data: a known custom
+/// error's name, else [`revert::decode_revert`]'s text.
+pub fn revert_reason(output: &[u8]) -> Option<String> {
+    let known = output
+        .first_chunk::<4>()
+        .and_then(|selector| KNOWN_ERRORS.iter().find(|sig| keccak256(sig.as_bytes())[..4] == selector[..]));
+    match known {
+        Some(sig) => Some(sig.split('(').next().unwrap_or(sig).to_string()),
+        None => revert::decode_revert(output),
+    }
+}
+
+/// The remedy [`REMEDIES`] prescribes for `reason`, if any.
+pub fn remedy_for(reason: &str) -> Option<Remedy> {
+    let reason = reason.to_lowercase();
+    REMEDIES.iter().find(|(fragment, _)| reason.contains(fragment)).and_then(|(_, remedy)| *remedy)
+}
+
+/// Calls from `caller` to the contract at `addr` that put state right for
+/// `remedy`. Contracts without the function simply reject them.
+pub fn setup_calls(remedy: Remedy, caller: Address, addr: Address) -> Vec<Vec<u8>> {
+    let encode = |sig: &str, values: Vec<DynSolValue>| {
+        let mut calldata = keccak256(sig.as_bytes())[..4].to_vec();
+        calldata.extend_from_slice(&DynSolValue::Tuple(values).abi_encode_params());
+        calldata
+    };
+    match remedy {
+        Remedy::Approve => [caller, addr]
+            .into_iter()
+            .map(|spender| {
+                encode(
+                    "approve(address,uint256)",
+                    vec![DynSolValue::Address(spender), DynSolValue::Uint(U256::MAX, 256)],
+                )
+            })
+            .collect(),
+        Remedy::Mint => vec![encode(
+            "mint(address,uint256)",
+            vec![DynSolValue::Address(caller), DynSolValue::Uint(U256::from(MINT_AMOUNT), 256)],
+        )],
+        Remedy::Unpause => vec![encode("unpause()", Vec::new())],
+        Remedy::FutureDeadline => Vec::new(),
+    }
+}
+
+/// New arguments for `remedy`, when it changes arguments rather than state:
+/// for a deadline, smart defaults with one top-level `uint` at a time treated
+/// as the deadline, for functions whose deadline isn't named like one.
+pub fn retry_calldata(remedy: Remedy, func: &Function, caller: Address) -> Vec<Vec<u8>> {
+    if remedy != Remedy::FutureDeadline {
+        return Vec::new();
+    }
+    (0..func.inputs.len())
+        .filter(|&i| func.inputs[i].ty.starts_with("uint") && !func.inputs[i].ty.ends_with(']'))
+        .filter_map(|i| {
+            let mut renamed = func.clone();
+            renamed.inputs[i] = Param { name: "deadline".into(), ..func.inputs[i].clone() };
+            encode_calldata_with_strategy(&renamed, CallStrategy::SmartDefaults, caller).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_errors_named_without_abi() {
+        let paused = keccak256("EnforcedPause()")[..4].to_vec();
+        assert_eq!(revert_reason(&paused).unwrap(), "EnforcedPause");
+        assert_eq!(remedy_for(&revert_reason(&paused).unwrap()), Some(Remedy::Unpause));
+        assert_eq!(revert_reason(&[0xde, 0xad, 0xbe, 0xef]).unwrap(), "0xdeadbeef");
+        assert_eq!(revert_reason(&[]), None);
+    }
+
+    #[test]
+    fn test_remedy_table() {
+        assert_eq!(remedy_for("revert: ERC20: insufficient allowance"), Some(Remedy::Approve));
+        assert_eq!(remedy_for("ERC20InsufficientBalance"), Some(Remedy::Mint));
+        assert_eq!(remedy_for("ERC20: transfer amount exceeds balance"), Some(Remedy::Mint));
+        assert_eq!(remedy_for("Pausable: paused"), Some(Remedy::Unpause));
+        assert_eq!(remedy_for("Pausable: not paused"), None);
+        assert_eq!(remedy_for("UniswapV2Router: EXPIRED"), Some(Remedy::FutureDeadline));
+        assert_eq!(remedy_for("Transaction too old"), Some(Remedy::FutureDeadline));
+        assert_eq!(remedy_for("Ownable: caller is not the owner"), None);
+    }
+}
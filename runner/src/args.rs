@@ -0,0 +1,178 @@
+use crate::merkle::MerkleSpec;
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_json_abi::Param;
+use eyre::{bail, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Explicit call arguments from an `--args` file:
+///
+/// ```json
+/// {
+///   "transfer(address,uint256)": ["0x00000000000000000000000000000000000000aa", "1000"],
+///   "onCallback(bytes)": { "args": ["0x"], "gas_limit": 2300 },
+///   "constructors": { "Token": ["Name", "TKN", 18], "Vault": ["0x...", [1, 2]] },
+///   "merkle": { "leaves": ["0x...", "0x..."], "prove": 0, "root_slot": "3" }
+/// }
+/// ```
+///
+/// Top-level keys are function signatures; `constructors` is keyed by
+/// contract name, since one source file can hold several contracts. Values
+/// are JSON strings, numbers, booleans or arrays, or a Solidity literal string
+/// for anything else (e.g. `"(1,true)"` for a struct). A function entry may
+/// instead be an object with optional `args` and a `gas_limit` for its
+/// measured transactions. See [`MerkleSpec`] for `merkle` and the
+/// `"$merkle_root"`/`"$merkle_proof"` placeholders.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArgsFile {
+    #[serde(default)]
+    pub constructors: HashMap<String, Vec<Value>>,
+    #[serde(default)]
+    pub merkle: Option<MerkleSpec>,
+    #[serde(flatten)]
+    pub functions: HashMap<String, FunctionEntry>,
+}
+
+/// One function's entry in an `--args` file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FunctionEntry {
+    Args(Vec<Value>),
+    Options {
+        #[serde(default)]
+        args: Option<Vec<Value>>,
+        #[serde(default)]
+        gas_limit: Option<u64>,
+    },
+}
+
+impl ArgsFile {
+    /// ABI-encoded arguments for `signature`, without the selector.
+    pub fn function_args(&self, signature: &str, params: &[Param]) -> Option<Result<Vec<u8>>> {
+        let values = match self.functions.get(signature)? {
+            FunctionEntry::Args(values) => values,
+            FunctionEntry::Options { args, .. } => args.as_ref()?,
+        };
+        Some(encode_args(params, values, self.merkle.as_ref()).wrap_err_with(|| format!("--args entry for {signature}")))
+    }
+
+    /// Transaction gas limit to measure `signature` under, if the file sets one.
+    pub fn gas_limit(&self, signature: &str) -> Option<u64> {
+        match self.functions.get(signature)? {
+            FunctionEntry::Args(_) => None,
+            FunctionEntry::Options { gas_limit, .. } => *gas_limit,
+        }
+    }
+
+    /// ABI-encoded constructor arguments for the contract named `contract`.
+    pub fn constructor_args(&self, contract: &str, params: &[Param]) -> Option<Result<Vec<u8>>> {
+        let values = self.constructors.get(contract)?;
+        Some(
+            encode_args(params, values, self.merkle.as_ref())
+                .wrap_err_with(|| format!("--args constructor for {contract}")),
+        )
+    }
+}
+
+pub fn load_args_file(path: &Path) -> Result<ArgsFile> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading args file {}", path.display()))?;
+    serde_json::from_str(&raw).wrap_err_with(|| format!("parsing args file {}", path.display()))
+}
+
+fn encode_args(params: &[Param], values: &[Value], merkle: Option<&MerkleSpec>) -> Result<Vec<u8>> {
+    if params.len() != values.len() {
+        bail!("expected {} arguments, got {}", params.len(), values.len());
+    }
+    let values = params
+        .iter()
+        .zip(values)
+        .map(|(param, value)| {
+            let ty = param.selector_type().parse::<DynSolType>()?;
+            Ok(ty.coerce_str(&literal(value, merkle)?)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(DynSolValue::Tuple(values).abi_encode_params())
+}
+
+/// Render a JSON argument as the Solidity literal `DynSolType::coerce_str`
+/// parses, expanding the Merkle placeholders from `merkle`.
+fn literal(value: &Value, merkle: Option<&MerkleSpec>) -> Result<String> {
+    Ok(match value {
+        Value::String(s) if s == "$merkle_root" || s == "$merkle_proof" => {
+            let Some(merkle) = merkle else {
+                bail!("{s} needs a `merkle` section");
+            };
+            if s == "$merkle_root" {
+                merkle.root().to_string()
+            } else {
+                let proof: Vec<String> = merkle.proof().iter().map(|h| h.to_string()).collect();
+                format!("[{}]", proof.join(","))
+            }
+        }
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Array(items) => {
+            let items = items.iter().map(|v| literal(v, merkle)).collect::<Result<Vec<_>>>()?;
+            format!("[{}]", items.join(","))
+        }
+        Value::Null | Value::Object(_) => bail!("unsupported argument value {value}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constructors_section_separate_from_functions() {
+        let args: ArgsFile = serde_json::from_str(
+            r#"{"set(uint256)": [5], "constructors": {"Foo": ["0xaa", true], "Bar": []}}"#,
+        )
+        .unwrap();
+        assert_eq!(args.functions.len(), 1);
+        assert_eq!(args.constructors["Foo"].len(), 2);
+        assert!(args.constructors["Bar"].is_empty());
+        assert!(!args.functions.contains_key("constructors"));
+    }
+
+    #[test]
+    fn test_merkle_placeholders() {
+        let args: ArgsFile = serde_json::from_str(&format!(
+            r#"{{"merkle": {{"leaves": ["{}", "{}"], "prove": 1}}, "claim(bytes32[])": ["$merkle_proof"]}}"#,
+            alloy_primitives::B256::repeat_byte(1),
+            alloy_primitives::B256::repeat_byte(2),
+        ))
+        .unwrap();
+        assert!(!args.functions.contains_key("merkle"));
+        let merkle = args.merkle.as_ref();
+        let proof = literal(&Value::String("$merkle_proof".into()), merkle).unwrap();
+        assert_eq!(proof, format!("[{}]", alloy_primitives::B256::repeat_byte(1)));
+        let root = literal(&Value::String("$merkle_root".into()), merkle).unwrap();
+        assert_eq!(root, merkle.unwrap().root().to_string());
+        assert!(literal(&Value::String("$merkle_root".into()), None).is_err());
+    }
+
+    #[test]
+    fn test_function_entry_with_gas_limit() {
+        let args: ArgsFile = serde_json::from_str(
+            r#"{"cb()": {"gas_limit": 2300}, "set(uint256)": {"args": [5], "gas_limit": 50000}, "get()": []}"#,
+        )
+        .unwrap();
+        assert_eq!(args.gas_limit("cb()"), Some(2300));
+        assert_eq!(args.gas_limit("set(uint256)"), Some(50_000));
+        assert_eq!(args.gas_limit("get()"), None);
+        assert!(args.function_args("cb()", &[]).is_none());
+        assert!(matches!(&args.functions["get()"], FunctionEntry::Args(values) if values.is_empty()));
+    }
+
+    #[test]
+    fn test_literal_rendering() {
+        let value: Value = serde_json::from_str(r#"[[1, 2], ["a", false]]"#).unwrap();
+        assert_eq!(literal(&value, None).unwrap(), "[[1,2],[a,false]]");
+        assert!(literal(&Value::Null, None).is_err());
+    }
+}
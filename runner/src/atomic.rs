@@ -0,0 +1,262 @@
+use crate::evm::BlockSettings;
+use crate::fork::Backend;
+use crate::types::ExecutionStatus;
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_json_abi::Function;
+use alloy_primitives::{Address, Bytes, TxKind, U256};
+use eyre::{Result, bail};
+use revm::bytecode::Bytecode;
+use revm::context::TxEnv;
+use revm::database::CacheDB;
+use revm::interpreter::{CallInputs, CallOutcome};
+use revm::state::AccountInfo;
+use revm::{InspectEvm, Inspector, MainBuilder};
+use serde::{Deserialize, Deserializer};
+
+/// Address the generated sequencing contract is installed at. It becomes
+/// `msg.sender` for every step and for the measured call.
+const HELPER: Address = Address::new([
+    0x5e, 0x0a, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+]);
+
+/// One setup call executed inside the same transaction as the measured call,
+/// parsed from `--atomic-step SIG[=ARGS][@WEI]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomicStep {
+    pub signature: String,
+    pub calldata: Vec<u8>,
+    pub value: U256,
+}
+
+impl<'de> Deserialize<'de> for AtomicStep {
+    /// Accepts the same `SIG[=ARGS][@WEI]` string as the CLI flag.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let spec = String::deserialize(deserializer)?;
+        parse_step(&spec).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parse `deposit(uint256)=(100)@1000000000000000000`-style step specs.
+///
+/// Arguments are a Solidity tuple literal (outer parentheses optional); the
+/// value after `@` is wei sent with the step, decimal or 0x-hex.
+pub fn parse_step(spec: &str) -> Result<AtomicStep> {
+    let (rest, value) = match spec.rsplit_once('@') {
+        Some((rest, wei)) => (rest, wei.trim().parse::<U256>()?),
+        None => (spec, U256::ZERO),
+    };
+    let (sig, args) = match rest.split_once('=') {
+        Some((sig, args)) => (sig.trim(), Some(args.trim())),
+        None => (rest.trim(), None),
+    };
+    let func = Function::parse(sig).map_err(|e| eyre::eyre!("invalid step signature '{sig}': {e}"))?;
+
+    let mut calldata = func.selector().to_vec();
+    if !func.inputs.is_empty() {
+        let Some(args) = args else {
+            bail!("step '{sig}' takes arguments; pass them as '{sig}=(...)'");
+        };
+        let types = func
+            .inputs
+            .iter()
+            .map(|p| p.selector_type().parse::<DynSolType>())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let literal = if args.starts_with('(') { args.to_string() } else { format!("({args})") };
+        let values: DynSolValue = DynSolType::Tuple(types).coerce_str(&literal)?;
+        calldata.extend_from_slice(&values.abi_encode_params());
+    }
+    Ok(AtomicStep { signature: func.signature(), calldata, value })
+}
+
+/// Run `steps` followed by `calldata` against `target` as a single transaction
+/// and return the measured call's `(gas, status)`.
+///
+/// The calls are sequenced by a generated helper contract, so the measured call
+/// observes uncommitted mid-transaction state (transient storage, warm slots,
+/// balances moved by earlier steps). A failing step reverts the whole
+/// transaction and is reported as an error. The returned gas is the measured
+/// frame's execution gas plus the intrinsic cost its calldata would carry as a
+/// standalone transaction, so it compares with ordinary reports. `gas_limit`
+/// bounds the whole sequence, steps included.
+pub fn transact_atomic(
+    db: &mut CacheDB<Backend>,
+    caller: Address,
+    target: Address,
+    steps: &[AtomicStep],
+    calldata: &[u8],
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus)> {
+    let mut data = Vec::new();
+    for step in steps {
+        data.extend_from_slice(&step.calldata);
+    }
+    let code = helper_code(target, steps, data.len());
+    data.extend_from_slice(calldata);
+    db.insert_account_info(HELPER, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+
+    let recorder = FrameRecorder::new(HELPER);
+    let nonce = crate::evm::account_nonce(db, caller);
+    let mut evm = block.context(&mut *db).build_mainnet_with_inspector(recorder);
+    let tx = block.priced(TxEnv {
+        caller,
+        gas_limit,
+        kind: TxKind::Call(HELPER),
+        data: Bytes::from(data),
+        value: steps.iter().map(|s| s.value).sum(),
+        nonce,
+        ..Default::default()
+    });
+    evm.inspect_one_tx(tx).map_err(|e| eyre::eyre!("call error: {e:?}"))?;
+
+    let outcomes = &evm.inspector.outcomes;
+    if let Some(i) = outcomes.iter().take(steps.len()).position(|o| o.0 != ExecutionStatus::Success) {
+        bail!("atomic step {} ({}) did not succeed", i + 1, steps[i].signature);
+    }
+    let Some(&(status, spent, refunded)) = outcomes.get(steps.len()) else {
+        bail!("atomic sequence stopped before the measured call");
+    };
+    Ok((frame_gas(calldata, spent, refunded), status))
+}
+
+/// Gas a call frame would have cost as its own transaction: intrinsic cost of
+/// `calldata` plus the frame's execution gas, less the capped refund.
+pub(crate) fn frame_gas(calldata: &[u8], spent: u64, refunded: u64) -> u64 {
+    let total = crate::evm::intrinsic_gas(calldata) + spent;
+    total - refunded.min(total / 5)
+}
+
+/// Records `(status, spent, refunded)` for each call made by `from`, in order.
+pub(crate) struct FrameRecorder {
+    from: Address,
+    pub outcomes: Vec<(ExecutionStatus, u64, u64)>,
+}
+
+impl FrameRecorder {
+    pub(crate) fn new(from: Address) -> Self {
+        FrameRecorder { from, outcomes: Vec::new() }
+    }
+}
+
+impl<CTX> Inspector<CTX> for FrameRecorder {
+    fn call_end(&mut self, _context: &mut CTX, inputs: &CallInputs, outcome: &mut CallOutcome) {
+        if inputs.caller != self.from {
+            return;
+        }
+        let result = *outcome.instruction_result();
+        let status = if result.is_ok() {
+            ExecutionStatus::Success
+        } else if result.is_revert() {
+            ExecutionStatus::Revert
+        } else {
+            ExecutionStatus::Halt
+        };
+        let gas = outcome.gas();
+        self.outcomes.push((status, gas.spent(), gas.refunded().max(0) as u64));
+    }
+}
+
+/// Runtime code that replays each step from the transaction's calldata, reverting
+/// if any fails, then forwards the remaining calldata (from `measured_offset`)
+/// as the measured call and stops regardless of its outcome.
+fn helper_code(target: Address, steps: &[AtomicStep], measured_offset: usize) -> Vec<u8> {
+    const PUSH1: u8 = 0x60;
+    const PUSH2: u8 = 0x61;
+    const PUSH20: u8 = 0x73;
+    const PUSH32: u8 = 0x7f;
+    let push32 = |code: &mut Vec<u8>, v: U256| {
+        code.push(PUSH32);
+        code.extend_from_slice(&v.to_be_bytes::<32>());
+    };
+    let push_target = |code: &mut Vec<u8>| {
+        code.push(PUSH20);
+        code.extend_from_slice(target.as_slice());
+    };
+
+    let mut code = Vec::new();
+    let mut revert_jumps = Vec::new();
+    let mut offset = 0usize;
+    for step in steps {
+        let len = U256::from(step.calldata.len());
+        // calldatacopy(0, offset, len)
+        push32(&mut code, len);
+        push32(&mut code, U256::from(offset));
+        code.extend_from_slice(&[PUSH1, 0, 0x37]);
+        // call(gas, target, value, 0, len, 0, 0)
+        code.extend_from_slice(&[PUSH1, 0, PUSH1, 0]);
+        push32(&mut code, len);
+        code.extend_from_slice(&[PUSH1, 0]);
+        push32(&mut code, step.value);
+        push_target(&mut code);
+        code.extend_from_slice(&[0x5a, 0xf1]);
+        // if iszero(success) { jump(revert) }
+        code.push(0x15);
+        code.push(PUSH2);
+        revert_jumps.push(code.len());
+        code.extend_from_slice(&[0, 0, 0x57]);
+        offset += step.calldata.len();
+    }
+    debug_assert_eq!(offset, measured_offset);
+
+    // len = calldatasize - measured_offset; calldatacopy(0, measured_offset, len)
+    push32(&mut code, U256::from(measured_offset));
+    code.extend_from_slice(&[0x36, 0x03, 0x80]);
+    push32(&mut code, U256::from(measured_offset));
+    code.extend_from_slice(&[PUSH1, 0, 0x37]);
+    // call(gas, target, 0, 0, len, 0, 0); stop
+    code.extend_from_slice(&[PUSH1, 0, PUSH1, 0, 0x82, PUSH1, 0, PUSH1, 0]);
+    push_target(&mut code);
+    code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+
+    // revert: jumpdest; revert(0, 0)
+    let revert_pc = (code.len() as u16).to_be_bytes();
+    for at in revert_jumps {
+        code[at..at + 2].copy_from_slice(&revert_pc);
+    }
+    code.extend_from_slice(&[0x5b, PUSH1, 0, 0x80, 0xfd]);
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::GAS_LIMIT;
+
+    /// Runtime that `tstore(0, 1)`s when sent value and otherwise reverts unless
+    /// slot 0 of transient storage is set, i.e. only succeeds mid-transaction.
+    const TRANSIENT_GATE: &str = "3415600b57600160005d005b60005c15601457005b600080fd";
+
+    fn setup() -> (CacheDB<Backend>, Address, Address) {
+        let caller = Address::repeat_byte(0x10);
+        let target = Address::repeat_byte(0x20);
+        let mut db = CacheDB::new(Backend::Empty);
+        db.insert_account_info(
+            caller,
+            AccountInfo { balance: U256::from(10u64).pow(U256::from(18u64)), nonce: 1, ..Default::default() },
+        );
+        let code = hex::decode(TRANSIENT_GATE).unwrap();
+        db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+        (db, caller, target)
+    }
+
+    #[test]
+    fn test_measured_call_sees_transient_state_from_steps() {
+        let (mut db, caller, target) = setup();
+        let (_, alone) = transact_atomic(&mut db, caller, target, &[], &[], GAS_LIMIT, &BlockSettings::default()).unwrap();
+        assert_eq!(alone, ExecutionStatus::Revert);
+
+        let step = AtomicStep { signature: "deposit()".into(), calldata: Vec::new(), value: U256::from(1u64) };
+        let (gas, status) = transact_atomic(&mut db, caller, target, &[step], &[], GAS_LIMIT, &BlockSettings::default()).unwrap();
+        assert_eq!(status, ExecutionStatus::Success);
+        assert!(gas > 21_000);
+    }
+
+    #[test]
+    fn test_parse_step_without_args() {
+        let step = parse_step("deposit() @ 0x10").unwrap();
+        assert_eq!(step.signature, "deposit()");
+        assert_eq!(step.calldata, Function::parse("deposit()").unwrap().selector().to_vec());
+        assert_eq!(step.value, U256::from(16u64));
+        assert!(parse_step("deposit(uint256)").is_err());
+    }
+}
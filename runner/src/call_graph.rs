@@ -1,4 +1,5 @@
 use crate::types::{CallEdge, CallGraphReport, CompiledContract};
+use alloy_primitives::Address;
 
 // Call-type opcodes
 const OP_CALL: u8 = 0xf1;
@@ -31,6 +32,18 @@ pub fn build_call_graph(contracts: &[CompiledContract]) -> Vec<CallGraphReport>
         .collect()
 }
 
+/// Selectors the bytecode's dispatch table compares calldata against, in
+/// order of first appearance.
+pub fn dispatched_selectors(bytecode: &[u8]) -> Vec<String> {
+    let mut selectors: Vec<String> = Vec::new();
+    for region in extract_function_selectors(bytecode) {
+        if !selectors.contains(&region.selector) {
+            selectors.push(region.selector);
+        }
+    }
+    selectors
+}
+
 /// A detected function region in bytecode (selector -> offset range).
 struct FunctionRegion {
     selector: String,
@@ -126,6 +139,38 @@ fn analyze_calls(bytecode: &[u8], selectors: &[FunctionRegion]) -> Vec<CallEdge>
     edges
 }
 
+/// Length of the CBOR metadata solc appends to runtime code (the map plus
+/// its 2-byte big-endian length), or 0 when the code doesn't end in one.
+pub fn metadata_len(bytecode: &[u8]) -> usize {
+    let Some(&[hi, lo]) = bytecode.len().checked_sub(2).map(|at| &bytecode[at..]) else {
+        return 0;
+    };
+    let len = u16::from_be_bytes([hi, lo]) as usize + 2;
+    // CBOR maps of 1-5 entries start with 0xa1..=0xa5; solc writes 1 to 4.
+    match bytecode.len().checked_sub(len).map(|start| bytecode[start]) {
+        Some(0xa1..=0xa5) => len,
+        _ => 0,
+    }
+}
+
+/// Distinct addresses pushed as PUSH20 constants, in order of appearance.
+/// Zero, precompile-range and all-ones (`type(uint160).max` mask) values are
+/// skipped.
+pub fn pushed_addresses(bytecode: &[u8]) -> Vec<Address> {
+    let mut addresses: Vec<Address> = Vec::new();
+    for (off, op) in parse_ops(bytecode) {
+        if op != OP_PUSH20 || off + 21 > bytecode.len() {
+            continue;
+        }
+        let addr = Address::from_slice(&bytecode[off + 1..off + 21]);
+        let is_low = addr.as_slice()[..18].iter().all(|&b| b == 0);
+        if !is_low && addr != Address::repeat_byte(0xff) && !addresses.contains(&addr) {
+            addresses.push(addr);
+        }
+    }
+    addresses
+}
+
 /// Scan backwards from a CALL opcode looking for a PUSH20 that could be the target address.
 fn find_address_before(ops: &[(usize, u8)], call_idx: usize, bytecode: &[u8]) -> Option<String> {
     let search_start = call_idx.saturating_sub(20);
@@ -197,3 +242,17 @@ fn extract_push_value(offset: usize, opcode: u8, bytecode: &[u8]) -> Option<u64>
     }
     Some(val)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_len() {
+        // runtime ++ {"solc": 0x00081a} ++ 0x000a
+        let code = hex::decode("6080604052a164736f6c634300081a000a").unwrap();
+        assert_eq!(metadata_len(&code), 12);
+        assert_eq!(metadata_len(&code[..5]), 0);
+        assert_eq!(metadata_len(&[0x00]), 0);
+    }
+}
@@ -1,7 +1,27 @@
 use alloy_dyn_abi::{DynSolType, DynSolValue};
 use alloy_json_abi::{Function, JsonAbi, Param};
 use alloy_primitives::{Address, I256, U256};
-use eyre::{Result, WrapErr};
+use eyre::{bail, Result, WrapErr};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// 2100-01-01T00:00:00Z. Fills deadline-style parameters so
+/// `require(deadline >= block.timestamp)` passes at any realistic block time.
+const FAR_FUTURE_TIMESTAMP: u64 = 4_102_444_800;
+const DEADLINE_NAMES: [&str; 6] = ["deadline", "expiry", "expiration", "expires", "validuntil", "validbefore"];
+/// Address parameters (leading underscores stripped) that usually name the
+/// party acting or receiving, so the caller is the value least likely to revert.
+const RECIPIENT_NAMES: [&str; 8] = ["to", "recipient", "receiver", "owner", "from", "account", "beneficiary", "user"];
+const AMOUNT_NAMES: [&str; 6] = ["amount", "value", "wad", "shares", "assets", "quantity"];
+/// Small positive amount: enough that fee and share math doesn't round to
+/// zero, small enough to fit any prefunded balance.
+const AMOUNT_HINT: u64 = 1_000;
+/// Elements in each dynamic array and bytes in each `bytes`/`string` filled
+/// by max defaults.
+const MAX_DEFAULTS_LEN: usize = 3;
+/// Most elements in a fuzzed dynamic array unless `--fuzz-max-len` says
+/// otherwise, as the `fuzz` command's default.
+pub const FUZZ_MAX_LEN: usize = 3;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CallStrategy {
@@ -9,6 +29,10 @@ pub enum CallStrategy {
     IncrementingArgs,
     CallerAddress,
     ZeroDefaults,
+    /// Type maxima, to reach branches guarded by large inputs.
+    MaxDefaults,
+    /// Random inputs from a seeded RNG (`--fuzz`), see [`encode_fuzz_calldata`].
+    Fuzz,
 }
 
 /// Encode `selector ++ abi_encode(strategy_values)` for a function call.
@@ -29,14 +53,14 @@ pub fn encode_calldata_with_strategy(
             .iter()
             .map(|p| {
                 let ty = param_to_dyn_sol_type(p)?;
-                let val = incrementing_value(&ty, caller, &mut counter);
+                let val = incrementing_value(p, &ty, caller, &mut counter);
                 Ok(val)
             })
             .collect::<Result<Vec<_>>>()?
     } else {
         func.inputs
             .iter()
-            .map(|p| Ok(strategy_value(&param_to_dyn_sol_type(p)?, strategy, caller)))
+            .map(|p| Ok(strategy_value(p, &param_to_dyn_sol_type(p)?, strategy, caller)))
             .collect::<Result<Vec<_>>>()?
     };
     let encoded = DynSolValue::Tuple(values).abi_encode_params();
@@ -46,11 +70,41 @@ pub fn encode_calldata_with_strategy(
     Ok(calldata)
 }
 
+/// `iterations` random calldatas for `func`, dynamic arrays holding at most
+/// `max_len` elements. The RNG is seeded from `seed` and the selector, so a
+/// seed reproduces each function's inputs whichever other functions are
+/// measured.
+pub fn encode_fuzz_calldata(
+    func: &Function,
+    caller: Address,
+    seed: u64,
+    iterations: u32,
+    max_len: usize,
+) -> Vec<Vec<u8>> {
+    let selector = u32::from_be_bytes(func.selector().0);
+    let mut rng = StdRng::seed_from_u64(seed ^ u64::from(selector));
+    (0..iterations)
+        .filter_map(|_| crate::fuzzer::generate_random_calldata(func, caller, max_len, &mut rng).ok())
+        .collect()
+}
+
 /// Encode constructor arguments (no selector). Empty if no constructor.
 pub fn encode_constructor_args_with_strategy(
     abi: &JsonAbi,
     strategy: CallStrategy,
     caller: Address,
+) -> Result<Vec<u8>> {
+    encode_constructor_args_with_dependencies(abi, strategy, caller, &[])
+}
+
+/// [`encode_constructor_args_with_strategy`], with `address` parameters
+/// taking the addresses of deployed `dependencies` (name, address); see
+/// [`dependency_arguments`].
+pub fn encode_constructor_args_with_dependencies(
+    abi: &JsonAbi,
+    strategy: CallStrategy,
+    caller: Address,
+    dependencies: &[(String, Address)],
 ) -> Result<Vec<u8>> {
     let ctor = match &abi.constructor {
         Some(c) if !c.inputs.is_empty() => c,
@@ -59,7 +113,171 @@ pub fn encode_constructor_args_with_strategy(
     let values: Vec<DynSolValue> = ctor
         .inputs
         .iter()
-        .map(|p| Ok(strategy_value(&param_to_dyn_sol_type(p)?, strategy, caller)))
+        .zip(dependency_arguments(&ctor.inputs, dependencies))
+        .map(|(p, dependency)| match dependency {
+            Some(address) => Ok(DynSolValue::Address(address)),
+            None => Ok(strategy_value(p, &param_to_dyn_sol_type(p)?, strategy, caller)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(DynSolValue::Tuple(values).abi_encode_params())
+}
+
+/// The dependency address each of `params` takes, if any. An `address`
+/// parameter named like a dependency (ignoring case and leading
+/// underscores) takes that one; the remaining `address` parameters take the
+/// remaining dependencies in order.
+pub fn dependency_arguments(params: &[Param], dependencies: &[(String, Address)]) -> Vec<Option<Address>> {
+    let named = |param: &Param, name: &str| param.name.trim_start_matches('_').eq_ignore_ascii_case(name);
+    let is_address = |param: &Param| param.ty == "address";
+    let mut unclaimed = dependencies
+        .iter()
+        .filter(|(name, _)| !params.iter().any(|p| is_address(p) && named(p, name)))
+        .map(|&(_, address)| address);
+    params
+        .iter()
+        .map(|param| {
+            if !is_address(param) {
+                return None;
+            }
+            match dependencies.iter().find(|(name, _)| named(param, name)) {
+                Some(&(_, address)) => Some(address),
+                None => unclaimed.next(),
+            }
+        })
+        .collect()
+}
+
+/// Smart default for a single parameter, for providers that fill most
+/// arguments themselves and only need a sensible value for the rest.
+pub fn smart_param_value(param: &Param, caller: Address) -> Result<DynSolValue> {
+    Ok(smart_value(param, &param_to_dyn_sol_type(param)?, caller))
+}
+
+/// Smart-default calldata with every dynamic array, `bytes` and `string`
+/// argument holding `len` elements. `None` when the function has no
+/// dynamically sized input, so there is nothing to sweep.
+pub fn encode_calldata_with_length(
+    func: &Function,
+    len: usize,
+    caller: Address,
+) -> Result<Option<Vec<u8>>> {
+    let types = func.inputs.iter().map(param_to_dyn_sol_type).collect::<Result<Vec<_>>>()?;
+    if !types.iter().any(has_dynamic_length) {
+        return Ok(None);
+    }
+    let values = func
+        .inputs
+        .iter()
+        .zip(&types)
+        .map(|(p, ty)| sized_value(p, ty, caller, len))
+        .collect();
+    let mut calldata = func.selector().to_vec();
+    calldata.extend_from_slice(&DynSolValue::Tuple(values).abi_encode_params());
+    Ok(Some(calldata))
+}
+
+/// Leaf parameter types of `func` that cannot be turned into ABI values (e.g.
+/// `fixed128x18`, or a mapping in a hand-written ABI), deduplicated. Every
+/// strategy encodes through the same type parser, so a function with any of
+/// these cannot be called at all.
+pub fn unsupported_types(func: &Function) -> Vec<String> {
+    fn collect(param: &Param, out: &mut Vec<String>) {
+        if param.ty.starts_with("tuple") {
+            param.components.iter().for_each(|c| collect(c, out));
+        } else if param.ty.parse::<DynSolType>().is_err() && !out.contains(&param.ty) {
+            out.push(param.ty.clone());
+        }
+    }
+    let mut out = Vec::new();
+    func.inputs.iter().for_each(|p| collect(p, &mut out));
+    out
+}
+
+/// Value a parameter's name suggests for its leaf type, before any strategy:
+/// a far-future timestamp for deadlines/expiries, the caller for
+/// recipient/owner-style addresses, and [`AMOUNT_HINT`] for amounts. `None`
+/// leaves the choice to the strategy.
+fn name_hint(param: &Param, ty: &DynSolType, caller: Address) -> Option<DynSolValue> {
+    if let Some(deadline) = deadline_hint(param, ty) {
+        return Some(deadline);
+    }
+    let name = param.name.trim_start_matches('_').to_lowercase();
+    match ty {
+        DynSolType::Address if RECIPIENT_NAMES.contains(&name.as_str()) => Some(DynSolValue::Address(caller)),
+        DynSolType::Uint(bits) if AMOUNT_NAMES.iter().any(|k| name.contains(k)) => {
+            Some(DynSolValue::Uint(U256::from(AMOUNT_HINT).min(uint_max(*bits)), *bits))
+        }
+        _ => None,
+    }
+}
+
+/// [`FAR_FUTURE_TIMESTAMP`] for a `uint` named like a deadline or expiry,
+/// capped to its width.
+fn deadline_hint(param: &Param, ty: &DynSolType) -> Option<DynSolValue> {
+    let name = param.name.to_lowercase();
+    match ty {
+        DynSolType::Uint(bits) if DEADLINE_NAMES.iter().any(|k| name.contains(k)) => {
+            Some(DynSolValue::Uint(U256::from(FAR_FUTURE_TIMESTAMP).min(uint_max(*bits)), *bits))
+        }
+        _ => None,
+    }
+}
+
+fn uint_max(bits: usize) -> U256 {
+    if bits >= 256 { U256::MAX } else { (U256::from(1) << bits) - U256::from(1) }
+}
+
+/// Parameters matching a tuple's component types, falling back to unnamed
+/// ones when the ABI doesn't describe the components.
+fn components(param: &Param, len: usize) -> impl Iterator<Item = &Param> {
+    const UNNAMED: &Param = &Param { ty: String::new(), name: String::new(), components: Vec::new(), internal_type: None };
+    let named = if param.components.len() == len { &param.components[..] } else { &[] };
+    named.iter().chain(std::iter::repeat(UNNAMED)).take(len)
+}
+
+fn has_dynamic_length(ty: &DynSolType) -> bool {
+    match ty {
+        DynSolType::Bytes | DynSolType::String | DynSolType::Array(_) => true,
+        DynSolType::FixedArray(inner, _) => has_dynamic_length(inner),
+        DynSolType::Tuple(types) => types.iter().any(has_dynamic_length),
+        _ => false,
+    }
+}
+
+/// Smart defaults, except dynamically sized values get exactly `len` elements.
+fn sized_value(param: &Param, ty: &DynSolType, caller: Address, len: usize) -> DynSolValue {
+    match ty {
+        DynSolType::Bytes => DynSolValue::Bytes(vec![0x01; len]),
+        DynSolType::String => DynSolValue::String("a".repeat(len)),
+        DynSolType::Array(inner) => {
+            DynSolValue::Array((0..len).map(|_| sized_value(param, inner, caller, len)).collect())
+        }
+        DynSolType::FixedArray(inner, n) => {
+            DynSolValue::FixedArray((0..*n).map(|_| sized_value(param, inner, caller, len)).collect())
+        }
+        DynSolType::Tuple(types) => DynSolValue::Tuple(
+            components(param, types.len()).zip(types).map(|(p, t)| sized_value(p, t, caller, len)).collect(),
+        ),
+        _ => smart_value(param, ty, caller),
+    }
+}
+
+/// Encode constructor arguments given as Solidity literals (`--constructor-args`),
+/// one per constructor parameter: `0xabc..`, `1000`, `[1,2]`, `(1,true)`.
+pub fn encode_constructor_args_from_strs(abi: &JsonAbi, args: &[String]) -> Result<Vec<u8>> {
+    let params = abi.constructor.as_ref().map_or(&[][..], |c| &c.inputs[..]);
+    if params.len() != args.len() {
+        let types: Vec<String> = params.iter().map(|p| p.selector_type().into_owned()).collect();
+        bail!("constructor takes {} arguments ({}), got {}", params.len(), types.join(","), args.len());
+    }
+    let values = params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            param_to_dyn_sol_type(param)?
+                .coerce_str(arg)
+                .wrap_err_with(|| format!("invalid {} {} argument {arg:?}", param.selector_type(), param.name))
+        })
         .collect::<Result<Vec<_>>>()?;
     Ok(DynSolValue::Tuple(values).abi_encode_params())
 }
@@ -95,18 +313,27 @@ fn param_to_dyn_sol_type(param: &Param) -> Result<DynSolType> {
         .wrap_err_with(|| format!("failed to parse Solidity type: {ty_str}"))
 }
 
-fn strategy_value(ty: &DynSolType, strategy: CallStrategy, caller: Address) -> DynSolValue {
+/// `param` supplies the name hints; zero and max defaults ignore them, since
+/// those strategies exist to hit boundary checks.
+fn strategy_value(param: &Param, ty: &DynSolType, strategy: CallStrategy, caller: Address) -> DynSolValue {
     match strategy {
-        CallStrategy::SmartDefaults => smart_value(ty, caller),
-        CallStrategy::IncrementingArgs => smart_value(ty, caller), // fallback; real logic in encode_calldata_with_strategy
-        CallStrategy::CallerAddress => caller_value(ty, caller),
+        CallStrategy::SmartDefaults => smart_value(param, ty, caller),
+        CallStrategy::IncrementingArgs => smart_value(param, ty, caller), // fallback; real logic in encode_calldata_with_strategy
+        CallStrategy::CallerAddress => caller_value(param, ty, caller),
         CallStrategy::ZeroDefaults => zero_value(ty),
+        CallStrategy::MaxDefaults => max_value(ty),
+        CallStrategy::Fuzz => smart_value(param, ty, caller), // fallback; real logic in encode_fuzz_calldata
     }
 }
 
 /// Incrementing defaults: each numeric param gets an increasing value.
 /// Passes guards like require(a < b), require(a != b), require(a <= b).
-fn incrementing_value(ty: &DynSolType, caller: Address, counter: &mut u64) -> DynSolValue {
+/// Only deadline names override the counter; amount hints would equalize
+/// the very values this strategy keeps distinct.
+fn incrementing_value(param: &Param, ty: &DynSolType, caller: Address, counter: &mut u64) -> DynSolValue {
+    if let Some(deadline) = deadline_hint(param, ty) {
+        return deadline;
+    }
     match ty {
         DynSolType::Uint(b) => {
             let val = *counter;
@@ -128,14 +355,14 @@ fn incrementing_value(ty: &DynSolType, caller: Address, counter: &mut u64) -> Dy
             DynSolValue::FixedBytes(alloy_primitives::B256::from(b), *n)
         }
         DynSolType::Array(inner) => {
-            DynSolValue::Array(vec![incrementing_value(inner, caller, counter)])
+            DynSolValue::Array(vec![incrementing_value(param, inner, caller, counter)])
         }
         DynSolType::FixedArray(inner, n) => {
-            DynSolValue::FixedArray((0..*n).map(|_| incrementing_value(inner, caller, counter)).collect())
-        }
-        DynSolType::Tuple(types) => {
-            DynSolValue::Tuple(types.iter().map(|t| incrementing_value(t, caller, counter)).collect())
+            DynSolValue::FixedArray((0..*n).map(|_| incrementing_value(param, inner, caller, counter)).collect())
         }
+        DynSolType::Tuple(types) => DynSolValue::Tuple(
+            components(param, types.len()).zip(types).map(|(p, t)| incrementing_value(p, t, caller, counter)).collect(),
+        ),
         DynSolType::Function => {
             let mut f = [0u8; 24];
             f[23] = 1;
@@ -144,8 +371,12 @@ fn incrementing_value(ty: &DynSolType, caller: Address, counter: &mut u64) -> Dy
     }
 }
 
-/// Non-zero defaults that pass common require guards.
-fn smart_value(ty: &DynSolType, caller: Address) -> DynSolValue {
+/// Non-zero defaults that pass common require guards, refined by the
+/// parameter's name (see [`name_hint`]).
+fn smart_value(param: &Param, ty: &DynSolType, caller: Address) -> DynSolValue {
+    if let Some(hinted) = name_hint(param, ty, caller) {
+        return hinted;
+    }
     match ty {
         DynSolType::Bool => DynSolValue::Bool(true),
         DynSolType::Uint(b) => DynSolValue::Uint(U256::from(1), *b),
@@ -158,13 +389,13 @@ fn smart_value(ty: &DynSolType, caller: Address) -> DynSolValue {
             if *n > 0 { b[n - 1] = 1; }
             DynSolValue::FixedBytes(alloy_primitives::B256::from(b), *n)
         }
-        DynSolType::Array(inner) => DynSolValue::Array(vec![smart_value(inner, caller)]),
+        DynSolType::Array(inner) => DynSolValue::Array(vec![smart_value(param, inner, caller)]),
         DynSolType::FixedArray(inner, n) => {
-            DynSolValue::FixedArray((0..*n).map(|_| smart_value(inner, caller)).collect())
-        }
-        DynSolType::Tuple(types) => {
-            DynSolValue::Tuple(types.iter().map(|t| smart_value(t, caller)).collect())
+            DynSolValue::FixedArray((0..*n).map(|_| smart_value(param, inner, caller)).collect())
         }
+        DynSolType::Tuple(types) => DynSolValue::Tuple(
+            components(param, types.len()).zip(types).map(|(p, t)| smart_value(p, t, caller)).collect(),
+        ),
         DynSolType::Function => {
             let mut f = [0u8; 24];
             f[23] = 1;
@@ -174,17 +405,17 @@ fn smart_value(ty: &DynSolType, caller: Address) -> DynSolValue {
 }
 
 /// Use CALLER for address params, smart defaults for the rest.
-fn caller_value(ty: &DynSolType, caller: Address) -> DynSolValue {
+fn caller_value(param: &Param, ty: &DynSolType, caller: Address) -> DynSolValue {
     match ty {
         DynSolType::Address => DynSolValue::Address(caller),
-        DynSolType::Array(inner) => DynSolValue::Array(vec![caller_value(inner, caller)]),
+        DynSolType::Array(inner) => DynSolValue::Array(vec![caller_value(param, inner, caller)]),
         DynSolType::FixedArray(inner, n) => {
-            DynSolValue::FixedArray((0..*n).map(|_| caller_value(inner, caller)).collect())
-        }
-        DynSolType::Tuple(types) => {
-            DynSolValue::Tuple(types.iter().map(|t| caller_value(t, caller)).collect())
+            DynSolValue::FixedArray((0..*n).map(|_| caller_value(param, inner, caller)).collect())
         }
-        _ => smart_value(ty, caller),
+        DynSolType::Tuple(types) => DynSolValue::Tuple(
+            components(param, types.len()).zip(types).map(|(p, t)| caller_value(p, t, caller)).collect(),
+        ),
+        _ => smart_value(param, ty, caller),
     }
 }
 
@@ -208,3 +439,134 @@ fn zero_value(ty: &DynSolType) -> DynSolValue {
         DynSolType::Function => DynSolValue::Function(alloy_primitives::Function::ZERO),
     }
 }
+
+/// Largest value of each type: integers at their bit width's maximum, all-`ff`
+/// addresses and fixed bytes, and a few elements in dynamic arrays.
+fn max_value(ty: &DynSolType) -> DynSolValue {
+    match ty {
+        DynSolType::Bool => DynSolValue::Bool(true),
+        DynSolType::Uint(b) => DynSolValue::Uint(U256::MAX >> (256 - b), *b),
+        DynSolType::Int(b) => DynSolValue::Int(I256::from_raw(U256::MAX >> (257 - b)), *b),
+        DynSolType::Address => DynSolValue::Address(Address::repeat_byte(0xff)),
+        DynSolType::Bytes => DynSolValue::Bytes(vec![0xff; MAX_DEFAULTS_LEN]),
+        DynSolType::String => DynSolValue::String("z".repeat(MAX_DEFAULTS_LEN)),
+        DynSolType::FixedBytes(n) => {
+            let mut b = [0u8; 32];
+            b[..*n].fill(0xff);
+            DynSolValue::FixedBytes(alloy_primitives::B256::from(b), *n)
+        }
+        DynSolType::Array(inner) => DynSolValue::Array(vec![max_value(inner); MAX_DEFAULTS_LEN]),
+        DynSolType::FixedArray(inner, n) => DynSolValue::FixedArray(vec![max_value(inner); *n]),
+        DynSolType::Tuple(types) => DynSolValue::Tuple(types.iter().map(max_value).collect()),
+        DynSolType::Function => DynSolValue::Function(alloy_primitives::Function::from([0xff; 24])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_arguments_by_name_then_order() {
+        let params: Vec<Param> = serde_json::from_value(serde_json::json!([
+            { "name": "_oracle", "type": "address" },
+            { "name": "fee", "type": "uint256" },
+            { "name": "admin", "type": "address" },
+            { "name": "_token", "type": "address" },
+            { "name": "spare", "type": "address" },
+        ]))
+        .unwrap();
+        let (token, oracle, pool) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let deps = [("Token".to_string(), token), ("Pool".to_string(), pool), ("oracle".to_string(), oracle)];
+        assert_eq!(dependency_arguments(&params, &deps), [Some(oracle), None, Some(pool), Some(token), None]);
+    }
+
+    #[test]
+    fn test_sized_value_fills_dynamic_lengths() {
+        let ty = DynSolType::Tuple(vec![
+            DynSolType::Array(Box::new(DynSolType::Uint(256))),
+            DynSolType::Bytes,
+            DynSolType::Uint(8),
+        ]);
+        assert!(has_dynamic_length(&ty));
+        assert!(!has_dynamic_length(&DynSolType::FixedArray(Box::new(DynSolType::Bool), 3)));
+        let DynSolValue::Tuple(values) = sized_value(&param("args", "tuple"), &ty, Address::ZERO, 5) else {
+            panic!("expected tuple");
+        };
+        assert!(matches!(&values[0], DynSolValue::Array(items) if items.len() == 5));
+        assert!(matches!(&values[1], DynSolValue::Bytes(b) if b.len() == 5));
+        assert!(matches!(values[2], DynSolValue::Uint(v, 8) if v == U256::from(1)));
+    }
+
+    #[test]
+    fn test_max_value_fills_type_maxima() {
+        assert_eq!(max_value(&DynSolType::Uint(256)), DynSolValue::Uint(U256::MAX, 256));
+        assert_eq!(max_value(&DynSolType::Uint(16)), DynSolValue::Uint(U256::from(u16::MAX), 16));
+        assert_eq!(max_value(&DynSolType::Int(8)), DynSolValue::Int(I256::try_from(i8::MAX).unwrap(), 8));
+        assert_eq!(max_value(&DynSolType::Int(256)), DynSolValue::Int(I256::MAX, 256));
+        let DynSolValue::FixedBytes(word, 4) = max_value(&DynSolType::FixedBytes(4)) else { panic!("expected bytes4") };
+        assert_eq!(&word[..5], &[0xff, 0xff, 0xff, 0xff, 0]);
+        let array = max_value(&DynSolType::Array(Box::new(DynSolType::Address)));
+        assert_eq!(array, DynSolValue::Array(vec![DynSolValue::Address(Address::repeat_byte(0xff)); MAX_DEFAULTS_LEN]));
+    }
+
+    #[test]
+    fn test_fuzz_calldata_is_seeded() {
+        let func = Function::parse("ping()").unwrap();
+        let inputs = encode_fuzz_calldata(&func, Address::ZERO, 7, 3, FUZZ_MAX_LEN);
+        assert_eq!(inputs, vec![func.selector().to_vec(); 3]);
+        assert!(encode_fuzz_calldata(&func, Address::ZERO, 7, 0, FUZZ_MAX_LEN).is_empty());
+        // Selector, offset and a zero length: every array comes out empty.
+        let func = Function::parse("f(uint8[])").unwrap();
+        assert!(encode_fuzz_calldata(&func, Address::ZERO, 7, 5, 0).iter().all(|cd| cd.len() == 4 + 64));
+    }
+
+    #[test]
+    fn test_constructor_args_arity_checked() {
+        let abi = JsonAbi::parse(["constructor(address owner, uint256 cap)"]).unwrap();
+        let err = encode_constructor_args_from_strs(&abi, &["0x01".into()]).unwrap_err();
+        assert_eq!(err.to_string(), "constructor takes 2 arguments (address,uint256), got 1");
+        let err = encode_constructor_args_from_strs(&JsonAbi::new(), &["1".into()]).unwrap_err();
+        assert_eq!(err.to_string(), "constructor takes 0 arguments (), got 1");
+    }
+
+    fn param(name: &str, ty: &str) -> Param {
+        Param { name: name.into(), ty: ty.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_deadline_params_get_far_future_timestamp() {
+        let far = U256::from(FAR_FUTURE_TIMESTAMP);
+        let params = Param {
+            components: vec![param("fee", "uint256"), param("deadline", "uint256")],
+            ..param("params", "tuple")
+        };
+        let ty = DynSolType::Tuple(vec![DynSolType::Uint(256), DynSolType::Uint(256)]);
+        let filled = smart_value(&params, &ty, Address::ZERO);
+        assert_eq!(
+            filled,
+            DynSolValue::Tuple(vec![DynSolValue::Uint(U256::from(1), 256), DynSolValue::Uint(far, 256)])
+        );
+        let mut counter = 1;
+        let expiry = incrementing_value(&param("expiry", "uint32"), &DynSolType::Uint(32), Address::ZERO, &mut counter);
+        assert_eq!(expiry, DynSolValue::Uint(far, 32));
+        let tiny = smart_value(&param("deadline", "uint8"), &DynSolType::Uint(8), Address::ZERO);
+        assert_eq!(tiny, DynSolValue::Uint(U256::from(255), 8));
+    }
+
+    #[test]
+    fn test_names_hint_recipients_and_amounts() {
+        let caller = Address::repeat_byte(0xca);
+        let value = |name: &str, ty: &DynSolType| smart_value(&param(name, "_"), ty, caller);
+        assert_eq!(value("_to", &DynSolType::Address), DynSolValue::Address(caller));
+        assert_eq!(value("spender", &DynSolType::Address), DynSolValue::Address(Address::with_last_byte(1)));
+        assert_eq!(value("token", &DynSolType::Address), DynSolValue::Address(Address::with_last_byte(1)));
+        assert_eq!(value("amountIn", &DynSolType::Uint(256)), DynSolValue::Uint(U256::from(AMOUNT_HINT), 256));
+        assert_eq!(value("tokenId", &DynSolType::Uint(256)), DynSolValue::Uint(U256::from(1), 256));
+        // Zero and max defaults keep ignoring names.
+        let max = strategy_value(&param("amount", "uint8"), &DynSolType::Uint(8), CallStrategy::MaxDefaults, caller);
+        assert_eq!(max, DynSolValue::Uint(U256::from(255), 8));
+        let zero = strategy_value(&param("to", "address"), &DynSolType::Address, CallStrategy::ZeroDefaults, caller);
+        assert_eq!(zero, DynSolValue::Address(Address::ZERO));
+    }
+}
@@ -1,26 +1,269 @@
-use crate::types::CompiledContract;
+use crate::types::{CompiledContract, CompilerSettings, LibraryPlaceholder};
+use alloy_primitives::Address;
 use alloy_json_abi::JsonAbi;
 use eyre::{bail, Result, WrapErr};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::fs;
 
+/// Settings that shape how a file is compiled, from the `gas` command's flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// `foundry.toml` whose compiler settings a standalone build inherits,
+    /// taking precedence over one found in an ancestor directory.
+    pub foundry_config: Option<PathBuf>,
+    /// Build without the CBOR metadata trailer (`bytecode_hash = "none"`,
+    /// `cbor_metadata = false`), whatever the project configures.
+    pub no_metadata: bool,
+    /// Always rebuild with `forge build --force`, ignoring builds cached under
+    /// the OS temp dir.
+    pub no_cache: bool,
+    /// Solc version forge builds with (`--use`), overriding any configured
+    /// one. Without it, a standalone build that configures none pins a version
+    /// satisfying the file's `pragma solidity`.
+    pub solc_version: Option<String>,
+    /// Enable the optimizer. Off by default, like forge; the project's or
+    /// inherited setting applies unless this or `optimizer_runs` is given.
+    pub optimize: bool,
+    /// Optimizer runs, overriding the configured value.
+    pub optimizer_runs: Option<u64>,
+    /// Compile through the IR pipeline.
+    pub via_ir: bool,
+    /// Solc `evmVersion`, overriding the configured one.
+    pub evm_version: Option<String>,
+    /// Library addresses written over unlinked placeholders, keyed by the
+    /// library's name or `<source path>:<Library>`. Unlinked ones stay zero.
+    pub links: Vec<(String, Address)>,
+}
+
+impl CompileOptions {
+    /// `foundry.toml` settings the compile flags override.
+    fn setting_overrides(&self) -> Vec<(&'static str, String)> {
+        let mut overrides = Vec::new();
+        if self.optimize {
+            overrides.push(("optimizer", "true".to_string()));
+        }
+        if let Some(runs) = self.optimizer_runs {
+            overrides.push(("optimizer_runs", runs.to_string()));
+        }
+        if self.via_ir {
+            overrides.push(("via_ir", "true".to_string()));
+        }
+        if let Some(evm_version) = &self.evm_version {
+            overrides.push(("evm_version", serde_json::Value::String(evm_version.clone()).to_string()));
+        }
+        overrides
+    }
+}
+
+/// Compiler settings copied from a project's `[profile.default]` into a
+/// standalone build, so its gas matches what the project itself would produce.
+const INHERITED_SETTINGS: &[&str] = &[
+    "solc",
+    "solc_version",
+    "auto_detect_solc",
+    "evm_version",
+    "optimizer",
+    "optimizer_runs",
+    "optimizer_details",
+    "via_ir",
+    "bytecode_hash",
+    "cbor_metadata",
+    "revert_strings",
+];
+
 /// Compile a `.sol` file and return all contracts found.
 ///
 /// Strategy:
-/// 1. If the file lives inside a Foundry project → use `forge build` in-place
-/// 2. Otherwise → create a temp Foundry project, copy the file, compile there
+/// 1. If the file lives inside a Foundry project's sources → use `forge build` in-place
+/// 2. If it lives in a Hardhat project with up-to-date artifacts for it → read those
+/// 3. Otherwise → create a temp Foundry project, copy the file, compile there,
+///    inheriting compiler settings from `--foundry-config` or an ancestor `foundry.toml`
 ///
 /// This means the runner works with **any** `.sol` file — no project structure required.
 pub fn compile(sol_path: &Path) -> Result<Vec<CompiledContract>> {
+    compile_with_options(sol_path, &CompileOptions::default())
+}
+
+/// [`compile`] with explicit [`CompileOptions`].
+pub fn compile_with_options(sol_path: &Path, opts: &CompileOptions) -> Result<Vec<CompiledContract>> {
+    let mut contracts = compile_unlinked(sol_path, opts)?;
+    for contract in &mut contracts {
+        link_libraries(contract, &opts.links);
+    }
+    Ok(contracts)
+}
+
+fn compile_unlinked(sol_path: &Path, opts: &CompileOptions) -> Result<Vec<CompiledContract>> {
     let sol_path = fs::canonicalize(sol_path)
         .wrap_err_with(|| format!("cannot resolve path: {}", sol_path.display()))?;
 
-    if let Some(root) = find_foundry_root(&sol_path) {
-        compile_in_project(&sol_path, &root)
-    } else {
-        compile_standalone(&sol_path)
+    let root = find_foundry_root(&sol_path);
+    if let Some(root) = &root
+        && is_project_source(&sol_path, root)
+    {
+        return compile_in_project(&sol_path, root, opts);
     }
+    if let Some(out_dir) = hardhat_artifacts_for(&sol_path, opts) {
+        return read_artifacts(&out_dir, &sol_path);
+    }
+
+    compile_standalone(&sol_path, &standalone_settings(&sol_path, root.as_deref(), opts)?, None, opts)
+}
+
+/// Compile a generated `harness_source` (saved as `harness_file`) next to a
+/// copy of `sol_path` in a standalone project and return the harness file's
+/// contracts. The harness imports the original as `./<file name>`.
+pub fn compile_harness(
+    sol_path: &Path,
+    harness_file: &str,
+    harness_source: &str,
+    opts: &CompileOptions,
+) -> Result<Vec<CompiledContract>> {
+    let sol_path = fs::canonicalize(sol_path)
+        .wrap_err_with(|| format!("cannot resolve path: {}", sol_path.display()))?;
+    let root = find_foundry_root(&sol_path);
+    let settings = standalone_settings(&sol_path, root.as_deref(), opts)?;
+    let mut contracts = compile_standalone(&sol_path, &settings, Some((harness_file, harness_source)), opts)?;
+    for contract in &mut contracts {
+        link_libraries(contract, &opts.links);
+    }
+    Ok(contracts)
+}
+
+/// Write each library address in `links` over the placeholders it names,
+/// given as the library's name or as `<source path>:<Library>`.
+pub fn link_libraries(contract: &mut CompiledContract, links: &[(String, Address)]) {
+    let bytecode = &mut contract.bytecode;
+    contract.libraries.retain(|placeholder| {
+        let names = |name: &str| {
+            let library = placeholder.library.as_deref();
+            library == Some(name)
+                || library.and_then(|l| l.rsplit_once(':')).is_some_and(|(_, bare)| bare == name)
+                || placeholder.hash == placeholder_hash(name)
+        };
+        let Some((_, address)) = links.iter().find(|(name, _)| names(name)) else {
+            return true;
+        };
+        match bytecode.get_mut(placeholder.offset..placeholder.offset + 20) {
+            Some(slot) => slot.copy_from_slice(address.as_slice()),
+            None => return true,
+        }
+        false
+    });
+}
+
+/// Placeholder hash of a fully qualified library name.
+fn placeholder_hash(qualified: &str) -> String {
+    hex::encode(alloy_primitives::keccak256(qualified))[..PLACEHOLDER_LEN - 6].to_string()
+}
+
+/// Settings for a standalone build. Outside any project's sources forge would
+/// not build the file in place, but a project above it still says how the user
+/// compiles their code. When neither it nor `--solc-version` names a
+/// compiler, the one picked from `sol_path`'s pragma is added.
+fn standalone_settings(sol_path: &Path, root: Option<&Path>, opts: &CompileOptions) -> Result<String> {
+    let config = opts
+        .foundry_config
+        .clone()
+        .or_else(|| root.map(|r| r.join("foundry.toml")));
+    let mut settings = match &config {
+        Some(path) => inherited_settings(path)?,
+        None => String::new(),
+    };
+    for (key, value) in opts.setting_overrides() {
+        settings = settings.lines().filter(|l| !l.starts_with(&format!("{key} "))).map(|l| format!("{l}\n")).collect();
+        settings.push_str(&format!("{key} = {value}\n"));
+    }
+    let pins_solc = settings.lines().any(|l| l.starts_with("solc ") || l.starts_with("solc_version "));
+    if opts.solc_version.is_none() && !pins_solc {
+        let source = fs::read_to_string(sol_path).unwrap_or_default();
+        if let Some(version) = pragma_constraint(&source).and_then(|c| pick_solc(&c, &installed_solc_versions())) {
+            settings.push_str(&format!("solc = \"{version}\"\n"));
+        }
+    }
+    Ok(settings)
+}
+
+// ---------------------------------------------------------------------------
+// Solc version selection
+// ---------------------------------------------------------------------------
+
+type Version = (u64, u64, u64);
+
+/// Version constraint of the first `pragma solidity` directive in `source`.
+fn pragma_constraint(source: &str) -> Option<String> {
+    let start = source.find("pragma solidity")? + "pragma solidity".len();
+    let rest = &source[start..];
+    Some(rest[..rest.find(';')?].trim().to_string())
+}
+
+/// The newest installed version satisfying `constraint`, or the version an
+/// exact constraint names (forge installs it on demand). `None` leaves the
+/// choice to forge's own detection.
+fn pick_solc(constraint: &str, installed: &[Version]) -> Option<String> {
+    let render = |(major, minor, patch): Version| format!("{major}.{minor}.{patch}");
+    if let Some(&newest) = installed.iter().filter(|&&v| satisfies(v, constraint)).max() {
+        return Some(render(newest));
+    }
+    let exact = constraint.trim_start_matches('=').trim();
+    parse_version(exact).filter(|_| exact.split('.').count() == 3).map(render)
+}
+
+/// Whether `version` meets a pragma constraint such as `^0.8.20`,
+/// `>=0.7.0 <0.9.0` or `0.8.26 || ^0.7.6`.
+fn satisfies(version: Version, constraint: &str) -> bool {
+    constraint.split("||").any(|alternative| {
+        // Join operators to their versions: `>= 0.8.0` is `>=0.8.0`.
+        let mut joined = alternative.trim().to_string();
+        for op in [">=", "<=", ">", "<", "=", "^", "~"] {
+            while joined.contains(&format!("{op} ")) {
+                joined = joined.replace(&format!("{op} "), op);
+            }
+        }
+        joined.split_whitespace().all(|comparator| {
+            let op_len = comparator.find(|c: char| c.is_ascii_digit()).unwrap_or(comparator.len());
+            let (op, bound) = comparator.split_at(op_len);
+            let Some(bound) = parse_version(bound) else {
+                return false;
+            };
+            match op {
+                ">=" => version >= bound,
+                ">" => version > bound,
+                "<=" => version <= bound,
+                "<" => version < bound,
+                "" | "=" => version == bound,
+                "^" if bound.0 == 0 => version >= bound && version.1 == bound.1,
+                "^" => version >= bound && version.0 == bound.0,
+                "~" => version >= bound && (version.0, version.1) == (bound.0, bound.1),
+                _ => false,
+            }
+        })
+    })
+}
+
+/// `major[.minor[.patch]]`, missing parts read as zero.
+fn parse_version(s: &str) -> Option<Version> {
+    let mut parts = s.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    parts.next().is_none().then_some((major, minor, patch))
+}
+
+/// Solc versions installed by forge's version manager (svm).
+fn installed_solc_versions() -> Vec<Version> {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let data = std::env::var_os("XDG_DATA_HOME").map_or_else(|| home.join(".local/share"), PathBuf::from);
+    [home.join(".svm"), data.join("svm")]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| parse_version(entry.ok()?.file_name().to_str()?))
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
@@ -28,9 +271,14 @@ pub fn compile(sol_path: &Path) -> Result<Vec<CompiledContract>> {
 // ---------------------------------------------------------------------------
 
 fn find_foundry_root(sol_path: &Path) -> Option<PathBuf> {
+    find_root(sol_path, &["foundry.toml"])
+}
+
+/// Nearest ancestor of `sol_path` holding one of `markers`.
+fn find_root(sol_path: &Path, markers: &[&str]) -> Option<PathBuf> {
     let mut dir = sol_path.parent()?;
     loop {
-        if dir.join("foundry.toml").exists() {
+        if markers.iter().any(|m| dir.join(m).exists()) {
             return Some(dir.to_path_buf());
         }
         match dir.parent() {
@@ -40,12 +288,108 @@ fn find_foundry_root(sol_path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Config files marking a Hardhat project root.
+const HARDHAT_CONFIGS: &[&str] =
+    &["hardhat.config.js", "hardhat.config.ts", "hardhat.config.cjs", "hardhat.config.mjs"];
+
+/// Directory whose `<File>.sol/` holds Hardhat's artifacts for `sol_path`,
+/// i.e. `artifacts/<path from the project root>` minus the file name. Only
+/// artifacts at least as new as the source count, and only when no compile
+/// flag asks for settings Hardhat's build may not have used.
+fn hardhat_artifacts_for(sol_path: &Path, opts: &CompileOptions) -> Option<PathBuf> {
+    if opts.no_metadata || opts.solc_version.is_some() || !opts.setting_overrides().is_empty() {
+        return None;
+    }
+    let root = find_root(sol_path, HARDHAT_CONFIGS)?;
+    let relative = sol_path.strip_prefix(&root).ok()?;
+    let artifact_dir = root.join("artifacts").join(relative);
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    if modified(&artifact_dir)? < modified(sol_path)? {
+        return None;
+    }
+    artifact_dir.parent().map(Path::to_path_buf)
+}
+
+/// Whether forge would compile `sol_path` when building `foundry_root`, i.e. it
+/// sits under the project's `src`, `test` or `script` directory.
+fn is_project_source(sol_path: &Path, foundry_root: &Path) -> bool {
+    let profile = read_default_profile(&foundry_root.join("foundry.toml")).unwrap_or_default();
+    [("src", "src"), ("test", "test"), ("script", "script")]
+        .iter()
+        .map(|(key, default)| profile.get(*key).and_then(|v| v.as_str()).unwrap_or(default))
+        .any(|dir| sol_path.starts_with(foundry_root.join(dir)))
+}
+
+fn read_default_profile(toml_path: &Path) -> Option<toml::Table> {
+    let contents = fs::read_to_string(toml_path).ok()?;
+    let mut value = contents.parse::<toml::Table>().ok()?;
+    match value.remove("profile")? {
+        toml::Value::Table(mut profiles) => match profiles.remove("default")? {
+            toml::Value::Table(default) => Some(default),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Render the [`INHERITED_SETTINGS`] present in `config` as `key = value` lines
+/// for a `[profile.default]` section.
+fn inherited_settings(config: &Path) -> Result<String> {
+    if !config.is_file() {
+        bail!("foundry config not found: {}", config.display());
+    }
+    let Some(profile) = read_default_profile(config) else {
+        return Ok(String::new());
+    };
+    let mut lines = String::new();
+    for key in INHERITED_SETTINGS {
+        if let Some(value) = profile.get(*key) {
+            lines.push_str(&format!("{key} = {}\n", render_toml_value(value)));
+        }
+    }
+    Ok(lines)
+}
+
+/// Inline TOML for `value` (the `toml` crate is built without its serializer).
+fn render_toml_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s.clone()).to_string(),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(d) => d.to_string(),
+        toml::Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(render_toml_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        toml::Value::Table(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .map(|(k, v)| format!("{} = {}", serde_json::Value::String(k.clone()), render_toml_value(v)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Path 1: compile inside an existing Foundry project
 // ---------------------------------------------------------------------------
 
-fn compile_in_project(sol_path: &Path, foundry_root: &Path) -> Result<Vec<CompiledContract>> {
-    forge_build(foundry_root)?;
+/// A project keeps its own artifacts and forge cache, so a hit only drops
+/// `--force`: forge still rebuilds whatever an import changed.
+fn compile_in_project(sol_path: &Path, foundry_root: &Path, opts: &CompileOptions) -> Result<Vec<CompiledContract>> {
+    let marker = (!opts.no_cache).then(|| {
+        let config = fs::read(foundry_root.join("foundry.toml")).unwrap_or_default();
+        let source = fs::read(sol_path).unwrap_or_default();
+        let root = foundry_root.as_os_str().as_encoded_bytes();
+        build_cache_dir().join(format!("project-{}", cache_key(&[root, &config, &source], opts)))
+    });
+    let cached = marker.as_ref().is_some_and(|m| m.exists());
+    forge_build(foundry_root, false, !cached, opts)?;
+    if let Some(marker) = marker.filter(|_| !cached) {
+        let _ = fs::create_dir_all(build_cache_dir()).and_then(|_| fs::write(marker, ""));
+    }
 
     let out_dir = parse_forge_out_dir(foundry_root);
     read_artifacts(&out_dir, sol_path)
@@ -55,42 +399,133 @@ fn compile_in_project(sol_path: &Path, foundry_root: &Path) -> Result<Vec<Compil
 // Path 2: standalone .sol file — create a temp Foundry project
 // ---------------------------------------------------------------------------
 
-fn compile_standalone(sol_path: &Path) -> Result<Vec<CompiledContract>> {
-    let tmp = tempfile::tempdir().wrap_err("failed to create temp directory")?;
+/// Every standalone build gets its own temp root, and `forge_build` pins
+/// `out/` and `cache/` inside it, so concurrent builds share no writable state
+/// and each one reads back only its own artifacts.
+/// With `extra`, that `(file name, source)` is written alongside the copy and
+/// its contracts are returned instead of those of `sol_path`.
+///
+/// The project depends on nothing but its inputs, so a successful build is
+/// kept in [`build_cache_dir`] under their hash and later builds of the same
+/// inputs read its artifacts without running forge.
+fn compile_standalone(
+    sol_path: &Path,
+    settings: &str,
+    extra: Option<(&str, &str)>,
+    opts: &CompileOptions,
+) -> Result<Vec<CompiledContract>> {
+    let source = fs::read(sol_path).wrap_err_with(|| format!("reading {}", sol_path.display()))?;
+    let (extra_name, extra_source) = extra.unwrap_or_default();
+    let file_name = sol_path.file_name().unwrap_or_default().as_encoded_bytes();
+    let key = cache_key(
+        &[file_name, &source, settings.as_bytes(), extra_name.as_bytes(), extra_source.as_bytes()],
+        opts,
+    );
+    let cached = build_cache_dir().join(format!("standalone-{key}"));
+    let read_out = |root: &Path| match extra {
+        Some((name, _)) => read_artifacts(&root.join("out"), Path::new(name)),
+        None => read_artifacts(&root.join("out"), sol_path),
+    };
+    if !opts.no_cache && cached.is_dir() {
+        match read_out(&cached) {
+            Ok(contracts) => return Ok(contracts),
+            // An unreadable entry would also block storing a fresh build.
+            Err(_) => drop(fs::remove_dir_all(&cached)),
+        }
+    }
+
+    let tmp = tempfile::Builder::new()
+        .prefix("sigscan-")
+        .tempdir()
+        .wrap_err("failed to create temp directory")?;
     let root = tmp.path();
 
-    // Minimal foundry.toml
+    // Minimal foundry.toml, plus any inherited compiler settings
     fs::write(
         root.join("foundry.toml"),
-        "[profile.default]\nsrc = \"src\"\nout = \"out\"\n",
+        format!("[profile.default]\nsrc = \"src\"\nout = \"out\"\ncache_path = \"cache\"\n{settings}"),
     )?;
 
     // Create src/ and copy the .sol file into it
     let src_dir = root.join("src");
     fs::create_dir_all(&src_dir)?;
-    fs::copy(sol_path, src_dir.join(sol_path.file_name().unwrap()))?;
+    fs::write(src_dir.join(sol_path.file_name().unwrap()), &source)?;
+    if let Some((name, source)) = extra {
+        fs::write(src_dir.join(name), source)?;
+    }
 
-    forge_build(root)?;
+    forge_build(root, true, true, opts)?;
+    let contracts = read_out(root)?;
 
-    let out_dir = root.join("out");
-    read_artifacts(&out_dir, sol_path)
+    // Move the finished build into the cache; another process may have stored
+    // the same key first, in which case tmp is dropped, cleaning it up.
+    if !opts.no_cache && fs::create_dir_all(build_cache_dir()).is_ok() {
+        let _ = fs::rename(root, &cached);
+    }
+    Ok(contracts)
+}
 
-    // tmp is dropped here, cleaning up the temp directory
+/// Where builds are cached between runs, keyed by [`cache_key`].
+fn build_cache_dir() -> PathBuf {
+    std::env::temp_dir().join("sigscan-cache")
+}
+
+/// Hash of a build's inputs and the options that change its output.
+fn cache_key(parts: &[&[u8]], opts: &CompileOptions) -> String {
+    let mut hashed = Vec::new();
+    for part in parts {
+        hashed.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        hashed.extend_from_slice(part);
+    }
+    hashed.push(u8::from(opts.no_metadata));
+    hashed.extend_from_slice(opts.solc_version.as_deref().unwrap_or_default().as_bytes());
+    for (key, value) in opts.setting_overrides() {
+        hashed.extend_from_slice(format!("\n{key}={value}").as_bytes());
+    }
+    hex::encode(&alloy_primitives::keccak256(&hashed)[..16])
 }
 
 // ---------------------------------------------------------------------------
 // Shared: run forge build
 // ---------------------------------------------------------------------------
 
-fn forge_build(foundry_root: &Path) -> Result<()> {
-    let output = Command::new("forge")
-        .args([
-            "build",
-            "--force",
-            "--extra-output",
-            "abi",
-            "evm.bytecode.object",
-        ])
+/// Run `forge build` in `foundry_root`, with `--force` when `force`. With
+/// `isolated`, the root, out and cache paths are passed explicitly so
+/// `FOUNDRY_*` environment overrides cannot redirect a temp project's output
+/// into a location shared with other builds. Metadata stripping goes through
+/// the environment, which outranks `foundry.toml` for in-project and
+/// standalone builds alike.
+fn forge_build(foundry_root: &Path, isolated: bool, force: bool, opts: &CompileOptions) -> Result<()> {
+    let mut cmd = Command::new("forge");
+    cmd.arg("build");
+    if force {
+        cmd.arg("--force");
+    }
+    cmd.args(["--extra-output", "abi", "evm.bytecode.object", "storageLayout"]);
+    if isolated {
+        cmd.arg("--root").arg(foundry_root);
+        cmd.arg("--out").arg(foundry_root.join("out"));
+        cmd.arg("--cache-path").arg(foundry_root.join("cache"));
+    }
+    if let Some(version) = &opts.solc_version {
+        cmd.arg("--use").arg(version);
+    }
+    if opts.optimize {
+        cmd.arg("--optimize");
+    }
+    if let Some(runs) = opts.optimizer_runs {
+        cmd.arg("--optimizer-runs").arg(runs.to_string());
+    }
+    if opts.via_ir {
+        cmd.arg("--via-ir");
+    }
+    if let Some(evm_version) = &opts.evm_version {
+        cmd.arg("--evm-version").arg(evm_version);
+    }
+    if opts.no_metadata {
+        cmd.env("FOUNDRY_BYTECODE_HASH", "none").env("FOUNDRY_CBOR_METADATA", "false");
+    }
+    let output = cmd
         .current_dir(foundry_root)
         .output()
         .wrap_err("failed to run `forge build` — is forge installed?")?;
@@ -136,90 +571,269 @@ fn read_artifacts(out_dir: &Path, sol_path: &Path) -> Result<Vec<CompiledContrac
     let mut contracts = Vec::new();
 
     for entry in fs::read_dir(&search_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("json") {
-            continue;
+        let path = entry?.path();
+        if is_artifact_file(&path) {
+            contracts.extend(parse_artifact(&path)?);
         }
+    }
 
-        let contract_name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        let raw: serde_json::Value =
-            serde_json::from_str(&fs::read_to_string(&path)?).wrap_err_with(|| {
-                format!("failed to parse artifact {}", path.display())
-            })?;
-
-        // Parse ABI
-        let abi_value = raw
-            .get("abi")
-            .cloned()
-            .unwrap_or(serde_json::Value::Array(vec![]));
-        let abi: JsonAbi = serde_json::from_value(abi_value)
-            .wrap_err("failed to parse ABI from forge artifact")?;
-
-        // Parse bytecode — forge puts it at /bytecode/object, solc at /evm/bytecode/object
-        let bytecode_hex = raw
-            .pointer("/bytecode/object")
-            .or_else(|| raw.pointer("/evm/bytecode/object"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-
-        // Replace unlinked library placeholders (__$...$__) with a zero address.
-        // These appear when a contract uses external libraries. Each placeholder is
-        // 40 hex chars (20 bytes = an address slot). Replacing with zeros lets us
-        // deploy and measure gas — library calls will revert but non-library
-        // functions still produce accurate gas.
-        let cleaned_hex = replace_library_placeholders(bytecode_hex.trim_start_matches("0x"));
-        let bytecode = hex::decode(&cleaned_hex).unwrap_or_default();
-
-        // Skip artifacts with no bytecode (interfaces, abstract contracts)
-        if bytecode.is_empty() {
-            continue;
+    Ok(contracts)
+}
+
+/// Read contracts from prebuilt forge or hardhat artifacts without running
+/// forge: one artifact file, or every artifact under a directory such as
+/// forge's `out/` or hardhat's `artifacts/`. Files without an `abi` (build
+/// info, debug files, caches) are skipped, as are contracts without bytecode.
+pub fn load_artifacts(path: &Path) -> Result<Vec<CompiledContract>> {
+    if path.is_file() {
+        let contracts = parse_artifact(path)?;
+        if contracts.is_none() {
+            bail!("{} is not a contract artifact with bytecode", path.display());
         }
+        return Ok(contracts.into_iter().collect());
+    }
+    if !path.is_dir() {
+        bail!("artifact not found: {}", path.display());
+    }
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).wrap_err_with(|| format!("reading {}", dir.display()))? {
+            let entry_path = entry?.path();
+            let name = entry_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if entry_path.is_dir() {
+                if name != "build-info" {
+                    dirs.push(entry_path);
+                }
+            } else if is_artifact_file(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    let mut contracts = Vec::new();
+    for file in files {
+        let raw: serde_json::Value = match fs::read_to_string(&file).map(|s| serde_json::from_str(&s)) {
+            Ok(Ok(raw)) => raw,
+            _ => continue,
+        };
+        if raw.get("abi").is_some() {
+            contracts.extend(contract_from_artifact(&file, &raw)?);
+        }
+    }
+    if contracts.is_empty() {
+        bail!("no contract artifacts with bytecode under {}", path.display());
+    }
+    Ok(contracts)
+}
 
-        contracts.push(CompiledContract {
-            name: contract_name,
-            abi,
-            bytecode,
-        });
+/// A contract from raw bytecode and its ABI, without compiling: `bytecode` is
+/// `0x`-prefixed hex or a file holding it, `abi` a JSON file with the ABI
+/// array (or an object with an `abi` key). The contract is named after the
+/// ABI file.
+pub fn load_bytecode(bytecode: &str, abi: &Path) -> Result<CompiledContract> {
+    let hex_str = match bytecode.strip_prefix("0x") {
+        Some(_) => bytecode.to_string(),
+        None => fs::read_to_string(bytecode).wrap_err_with(|| format!("reading bytecode from {bytecode}"))?,
+    };
+    let (cleaned_hex, libraries) = replace_library_placeholders(hex_str.trim().trim_start_matches("0x"));
+    let bytecode = hex::decode(cleaned_hex).wrap_err("bytecode is not valid hex")?;
+    if bytecode.is_empty() {
+        bail!("bytecode is empty");
     }
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(abi)?)
+        .wrap_err_with(|| format!("failed to parse ABI {}", abi.display()))?;
+    let abi_value = raw.get("abi").cloned().unwrap_or(raw);
+    let parsed: JsonAbi =
+        serde_json::from_value(abi_value).wrap_err_with(|| format!("failed to parse ABI {}", abi.display()))?;
+    Ok(CompiledContract {
+        name: abi.file_stem().and_then(|s| s.to_str()).unwrap_or("Contract").to_string(),
+        abi: parsed,
+        bytecode,
+        gas_budgets: HashMap::new(),
+        storage_variables: HashSet::new(),
+        compiler: CompilerSettings::default(),
+        libraries,
+    })
+}
 
-    Ok(contracts)
+/// A `.json` file other than a Hardhat `<Name>.dbg.json` build-info pointer.
+fn is_artifact_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(".json") && !name.ends_with(".dbg.json")
+}
+
+/// The contract in the artifact at `path`, or `None` for one without
+/// bytecode (interfaces, abstract contracts).
+fn parse_artifact(path: &Path) -> Result<Option<CompiledContract>> {
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)
+        .wrap_err_with(|| format!("failed to parse artifact {}", path.display()))?;
+    contract_from_artifact(path, &raw)
+}
+
+fn contract_from_artifact(path: &Path, raw: &serde_json::Value) -> Result<Option<CompiledContract>> {
+    // Hardhat records the name; forge names the file after the contract.
+    let contract_name = raw
+        .get("contractName")
+        .and_then(|n| n.as_str())
+        .or_else(|| path.file_stem().and_then(|s| s.to_str()))
+        .unwrap_or("Unknown")
+        .to_string();
+
+    // Parse ABI
+    let abi_value = raw
+        .get("abi")
+        .cloned()
+        .unwrap_or(serde_json::Value::Array(vec![]));
+    let abi: JsonAbi = serde_json::from_value(abi_value)
+        .wrap_err_with(|| format!("failed to parse ABI from artifact {}", path.display()))?;
+
+    // Parse bytecode — forge puts it at /bytecode/object, solc at
+    // /evm/bytecode/object, hardhat at /bytecode as a plain string
+    let bytecode_hex = raw
+        .pointer("/bytecode/object")
+        .or_else(|| raw.pointer("/evm/bytecode/object"))
+        .or_else(|| raw.get("bytecode"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    // Replace unlinked library placeholders (__$...$__) with a zero address.
+    // These appear when a contract uses external libraries. Each placeholder is
+    // 40 hex chars (20 bytes = an address slot). Replacing with zeros lets us
+    // deploy and measure gas — library calls will revert but non-library
+    // functions still produce accurate gas. `--link` fills them in later.
+    let (cleaned_hex, mut libraries) = replace_library_placeholders(bytecode_hex.trim_start_matches("0x"));
+    // A malformed placeholder stays in the hex, which then cannot be decoded.
+    let bytecode = hex::decode(&cleaned_hex)
+        .wrap_err_with(|| format!("bytecode in artifact {} is not valid hex", path.display()))?;
+    // Name placeholders after the link references (forge nests them in
+    // `bytecode`, hardhat keeps them at the top level).
+    let references = raw.pointer("/bytecode/linkReferences").or_else(|| raw.get("linkReferences"));
+    let qualified: Vec<String> = references
+        .and_then(|r| r.as_object())
+        .into_iter()
+        .flatten()
+        .flat_map(|(file, libs)| libs.as_object().into_iter().flatten().map(move |(lib, _)| format!("{file}:{lib}")))
+        .collect();
+    for placeholder in &mut libraries {
+        placeholder.library = qualified.iter().find(|q| placeholder_hash(q) == placeholder.hash).cloned();
+    }
+
+    // Skip artifacts with no bytecode (interfaces, abstract contracts)
+    if bytecode.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(CompiledContract {
+        name: contract_name,
+        abi,
+        bytecode,
+        gas_budgets: parse_gas_budgets(raw),
+        storage_variables: parse_storage_variables(raw),
+        compiler: parse_compiler_settings(raw),
+        libraries,
+    }))
 }
 
+/// Natspec tag carrying a per-function gas budget, e.g. `/// @custom:gas-budget 50000`.
+const GAS_BUDGET_TAG: &str = "custom:gas-budget";
+
+/// Collect `@custom:gas-budget` values from the artifact's devdoc.
+///
+/// Solc records custom natspec tags under `devdoc.methods["<signature>"]`. Forge
+/// embeds the devdoc in `metadata.output`, and also at the top level when
+/// `devdoc` is requested as extra output, so both locations are checked.
+fn parse_gas_budgets(raw: &serde_json::Value) -> HashMap<String, u64> {
+    let methods = raw
+        .pointer("/devdoc/methods")
+        .or_else(|| raw.pointer("/metadata/output/devdoc/methods"))
+        .and_then(|m| m.as_object());
+
+    let mut budgets = HashMap::new();
+    for (signature, doc) in methods.into_iter().flatten() {
+        let Some(tag) = doc.get(GAS_BUDGET_TAG).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let digits: String = tag.trim().chars().filter(|c| *c != '_' && *c != ',').collect();
+        match digits.parse::<u64>() {
+            Ok(budget) => {
+                budgets.insert(signature.clone(), budget);
+            }
+            Err(_) => eprintln!("Warning: ignoring malformed @{GAS_BUDGET_TAG} on {signature}: {tag}"),
+        }
+    }
+    budgets
+}
+
+/// Names of the contract's storage variables (including inherited ones), from
+/// the `storageLayout` extra output.
+fn parse_storage_variables(raw: &serde_json::Value) -> HashSet<String> {
+    raw.pointer("/storageLayout/storage")
+        .and_then(|s| s.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get("label").and_then(|l| l.as_str()))
+        .map(String::from)
+        .collect()
+}
+
+/// Compiler version and settings from the artifact's solc metadata.
+fn parse_compiler_settings(raw: &serde_json::Value) -> CompilerSettings {
+    let str_at = |ptr: &str| raw.pointer(ptr).and_then(|v| v.as_str()).map(String::from);
+    CompilerSettings {
+        solc: str_at("/metadata/compiler/version"),
+        optimizer: raw.pointer("/metadata/settings/optimizer/enabled").and_then(|v| v.as_bool()),
+        optimizer_runs: raw.pointer("/metadata/settings/optimizer/runs").and_then(|v| v.as_u64()),
+        evm_version: str_at("/metadata/settings/evmVersion"),
+        // Solc omits `viaIR` unless it is enabled.
+        via_ir: raw
+            .pointer("/metadata/settings")
+            .map(|s| s.get("viaIR").and_then(|v| v.as_bool()).unwrap_or(false)),
+    }
+}
+
+/// Length of a library placeholder: `__$`, 34 hex chars of the library's
+/// hashed name, `$__`.
+const PLACEHOLDER_LEN: usize = 40;
+
 /// Replace unlinked library placeholders (`__$<hash>$__`) with zero addresses.
 ///
 /// Forge emits 40-char placeholders like `__$1f06ac8d622ce42796cee98ba1044ce165$__`
 /// for contracts that use external libraries. Each placeholder occupies exactly
-/// 40 hex characters (20 bytes = one EVM address slot).
-fn replace_library_placeholders(hex_str: &str) -> String {
+/// 40 hex characters (20 bytes = one EVM address slot). A `__$` that does not
+/// start a well-formed placeholder is left as is, with a warning, since zeroing
+/// a span of another length would shift every byte after it. Returns the
+/// zeroed placeholders alongside, for [`link_libraries`].
+fn replace_library_placeholders(hex_str: &str) -> (String, Vec<LibraryPlaceholder>) {
     let mut result = String::with_capacity(hex_str.len());
-    let bytes = hex_str.as_bytes();
-    let mut i = 0;
-
-    while i < bytes.len() {
-        if i + 1 < bytes.len() && bytes[i] == b'_' && bytes[i + 1] == b'_' {
-            // Find the closing `$__`
-            if let Some(end) = hex_str[i..].find("$__") {
-                let placeholder_end = i + end + 3; // past the closing `$__`
-                let placeholder_len = placeholder_end - i;
-                // Each placeholder should be 40 chars; pad with zeros
-                for _ in 0..placeholder_len {
-                    result.push('0');
-                }
-                i = placeholder_end;
-                continue;
-            }
+    let mut placeholders = Vec::new();
+    let mut rest = hex_str;
+    while let Some(start) = rest.find("__$") {
+        result.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let hash = candidate.get(3..PLACEHOLDER_LEN - 3);
+        if let Some(hash) = hash.filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            && candidate.get(PLACEHOLDER_LEN - 3..PLACEHOLDER_LEN) == Some("$__")
+        {
+            let hash = hash.to_ascii_lowercase();
+            placeholders.push(LibraryPlaceholder { hash, library: None, offset: result.len() / 2 });
+            result.push_str(&"0".repeat(PLACEHOLDER_LEN));
+            rest = &candidate[PLACEHOLDER_LEN..];
+            continue;
+        }
+        let offset = hex_str.len() - candidate.len();
+        match candidate[3..].find("$__") {
+            Some(end) => eprintln!(
+                "Warning: library placeholder at hex offset {offset} is {} chars, not {PLACEHOLDER_LEN}; left unlinked",
+                end + 6
+            ),
+            None => eprintln!("Warning: unterminated library placeholder at hex offset {offset}; left unlinked"),
         }
-        result.push(bytes[i] as char);
-        i += 1;
+        result.push_str("__$");
+        rest = &candidate[3..];
     }
-
-    result
+    result.push_str(rest);
+    (result, placeholders)
 }
 
 fn parse_forge_out_dir(foundry_root: &Path) -> PathBuf {
@@ -238,3 +852,252 @@ fn parse_forge_out_dir(foundry_root: &Path) -> PathBuf {
     }
     foundry_root.join("out")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_budgets_from_metadata_devdoc() {
+        let raw = serde_json::json!({
+            "metadata": { "output": { "devdoc": { "methods": {
+                "transfer(address,uint256)": { "custom:gas-budget": "50_000" },
+                "approve(address,uint256)": { "details": "no budget here" }
+            }}}}
+        });
+        let budgets = parse_gas_budgets(&raw);
+        assert_eq!(budgets.len(), 1);
+        assert_eq!(budgets["transfer(address,uint256)"], 50_000);
+    }
+
+    #[test]
+    fn test_compiler_settings_from_metadata() {
+        let raw = serde_json::json!({
+            "metadata": {
+                "compiler": { "version": "0.8.26+commit.8a97fa7a" },
+                "settings": { "optimizer": { "enabled": true, "runs": 200 }, "evmVersion": "cancun" }
+            }
+        });
+        let settings = parse_compiler_settings(&raw);
+        assert_eq!(settings.solc.as_deref(), Some("0.8.26+commit.8a97fa7a"));
+        assert_eq!((settings.optimizer, settings.optimizer_runs), (Some(true), Some(200)));
+        assert_eq!(settings.evm_version.as_deref(), Some("cancun"));
+        assert_eq!(settings.via_ir, Some(false));
+        assert_eq!(parse_compiler_settings(&serde_json::json!({})), CompilerSettings::default());
+    }
+
+    #[test]
+    fn test_storage_variables() {
+        let raw = serde_json::json!({
+            "storageLayout": { "storage": [
+                { "label": "owner", "slot": "0", "offset": 0 },
+                { "label": "balances", "slot": "1", "offset": 0 }
+            ]}
+        });
+        let vars = parse_storage_variables(&raw);
+        assert!(vars.contains("owner") && vars.contains("balances"));
+        assert!(parse_storage_variables(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_gas_budgets_malformed_ignored() {
+        let raw = serde_json::json!({
+            "devdoc": { "methods": { "foo()": { "custom:gas-budget": "lots" } } }
+        });
+        assert!(parse_gas_budgets(&raw).is_empty());
+    }
+
+    #[test]
+    fn test_cache_key_covers_inputs_and_options() {
+        let opts = CompileOptions::default();
+        let key = cache_key(&[b"A.sol", b"contract A {}"], &opts);
+        assert_eq!(key, cache_key(&[b"A.sol", b"contract A {}"], &opts));
+        assert_ne!(key, cache_key(&[b"A.sol", b"contract B {}"], &opts));
+        assert_ne!(key, cache_key(&[b"A.so", b"lcontract A {}"], &opts));
+        let source: [&[u8]; 2] = [b"A.sol", b"contract A {}"];
+        assert_ne!(key, cache_key(&source, &CompileOptions { no_metadata: true, ..opts.clone() }));
+        assert_ne!(key, cache_key(&source, &CompileOptions { optimizer_runs: Some(200), ..opts }));
+    }
+
+    #[test]
+    fn test_load_forge_and_hardhat_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let forge = dir.path().join("out/Token.sol");
+        let hardhat = dir.path().join("artifacts/contracts/Vault.sol");
+        fs::create_dir_all(&forge).unwrap();
+        fs::create_dir_all(&hardhat).unwrap();
+        fs::create_dir_all(dir.path().join("out/build-info")).unwrap();
+        fs::write(forge.join("Token.json"), r#"{"abi": [], "bytecode": {"object": "0x6001"}}"#).unwrap();
+        fs::write(forge.join("IToken.json"), r#"{"abi": [], "bytecode": {"object": "0x"}}"#).unwrap();
+        fs::write(hardhat.join("Vault.json"), r#"{"contractName": "Vault", "abi": [], "bytecode": "0x6002"}"#).unwrap();
+        fs::write(hardhat.join("Vault.dbg.json"), r#"{"buildInfo": "x"}"#).unwrap();
+        fs::write(dir.path().join("out/build-info/1.json"), r#"{"abi": "not an artifact"}"#).unwrap();
+
+        let contracts = load_artifacts(dir.path()).unwrap();
+        let names: Vec<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Vault", "Token"]);
+        assert_eq!(contracts[1].bytecode, [0x60, 0x01]);
+        assert_eq!(load_artifacts(&hardhat.join("Vault.json")).unwrap()[0].bytecode, [0x60, 0x02]);
+        assert!(load_artifacts(&forge.join("IToken.json")).is_err());
+    }
+
+    #[test]
+    fn test_reads_hardhat_project_artifacts() {
+        let root = tempfile::tempdir().unwrap();
+        let sol = root.path().join("contracts/Vault.sol");
+        let artifacts = root.path().join("artifacts/contracts/Vault.sol");
+        fs::create_dir_all(sol.parent().unwrap()).unwrap();
+        fs::create_dir_all(&artifacts).unwrap();
+        fs::write(root.path().join("hardhat.config.ts"), "export default {};").unwrap();
+        fs::write(&sol, "contract Vault {}").unwrap();
+        let artifact = r#"{"contractName": "Vault", "abi": [], "bytecode": "0x6002"}"#;
+        fs::write(artifacts.join("Vault.json"), artifact).unwrap();
+        fs::write(artifacts.join("Vault.dbg.json"), r#"{"_format": "hh-sol-dbg-1"}"#).unwrap();
+
+        let contracts = compile(&sol).unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!((contracts[0].name.as_str(), &contracts[0].bytecode[..]), ("Vault", &[0x60, 0x02][..]));
+        let overridden = CompileOptions { optimize: true, ..Default::default() };
+        let sol = fs::canonicalize(&sol).unwrap();
+        assert!(hardhat_artifacts_for(&sol, &overridden).is_none());
+    }
+
+    #[test]
+    fn test_load_bytecode_inline_or_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let abi = dir.path().join("Verified.json");
+        fs::write(&abi, "[]").unwrap();
+        let contract = load_bytecode("0x600160005500", &abi).unwrap();
+        assert_eq!((contract.name.as_str(), contract.bytecode.len()), ("Verified", 6));
+        let code = dir.path().join("code.hex");
+        fs::write(&code, "600160005500\n").unwrap();
+        assert_eq!(load_bytecode(code.to_str().unwrap(), &abi).unwrap().bytecode, contract.bytecode);
+        assert!(load_bytecode("0xzz", &abi).is_err());
+    }
+
+    #[test]
+    fn test_library_placeholders_zeroed() {
+        let placeholder = "__$1f06ac8d622ce42796cee98ba1044ce165$__";
+        let code = format!("6080{placeholder}5b{placeholder}00");
+        let (linked, placeholders) = replace_library_placeholders(&code);
+        assert_eq!(linked, format!("6080{}5b{}00", "0".repeat(40), "0".repeat(40)));
+        assert_eq!(hex::decode(linked).unwrap().len(), 2 + 20 + 1 + 20 + 1);
+        let offsets: Vec<usize> = placeholders.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, [2, 23]);
+    }
+
+    #[test]
+    fn test_link_libraries_by_name() {
+        let hash = placeholder_hash("src/MathLib.sol:MathLib");
+        let artifact = serde_json::json!({
+            "abi": [],
+            "bytecode": {
+                "object": format!("0x73__${hash}$__00"),
+                "linkReferences": { "src/MathLib.sol": { "MathLib": [{ "start": 1, "length": 20 }] } }
+            }
+        });
+        let mut contract = contract_from_artifact(Path::new("Vault.json"), &artifact).unwrap().unwrap();
+        assert_eq!(contract.libraries[0].library.as_deref(), Some("src/MathLib.sol:MathLib"));
+        link_libraries(&mut contract, &[("Other".into(), Address::repeat_byte(1))]);
+        assert_eq!(contract.libraries.len(), 1);
+        let lib = Address::repeat_byte(0x11);
+        link_libraries(&mut contract, &[("MathLib".into(), lib)]);
+        assert!(contract.libraries.is_empty());
+        assert_eq!(&contract.bytecode[1..21], lib.as_slice());
+    }
+
+    #[test]
+    fn test_malformed_library_placeholders_left_alone() {
+        // 33-char hash, non-hex hash, and no closing `$__`.
+        let malformed =
+            ["6080__$1f06ac8d622ce42796cee98ba1044ce16$__00", "60__$zz06ac8d622ce42796cee98ba1044ce165$__", "60__$1f"];
+        for code in malformed {
+            assert_eq!(replace_library_placeholders(code), (code.to_string(), Vec::new()));
+        }
+        let placeholder = "__$1f06ac8d622ce42796cee98ba1044ce165$__";
+        let mixed = format!("__$bad$__{placeholder}");
+        assert_eq!(replace_library_placeholders(&mixed).0, format!("__$bad$__{}", "0".repeat(40)));
+    }
+
+    #[test]
+    fn test_artifact_with_malformed_placeholder_is_an_error() {
+        let artifact = serde_json::json!({
+            "abi": [],
+            "bytecode": { "object": "0x6080__$1f06ac8d622ce42796cee98ba1044ce16$__00" }
+        });
+        let err = contract_from_artifact(Path::new("Vault.json"), &artifact).unwrap_err();
+        assert!(err.to_string().contains("Vault.json"), "{err}");
+        let interface = serde_json::json!({ "abi": [], "bytecode": { "object": "0x" } });
+        assert!(contract_from_artifact(Path::new("IVault.json"), &interface).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pick_solc_from_pragma() {
+        let source = "// SPDX-License-Identifier: MIT\npragma solidity >= 0.8.4 <0.9.0;\ncontract A {}";
+        assert_eq!(pragma_constraint(source).as_deref(), Some(">= 0.8.4 <0.9.0"));
+        let installed = [(0, 7, 6), (0, 8, 3), (0, 8, 26), (0, 9, 1)];
+        assert_eq!(pick_solc(">= 0.8.4 <0.9.0", &installed).as_deref(), Some("0.8.26"));
+        assert_eq!(pick_solc("^0.7.0", &installed).as_deref(), Some("0.7.6"));
+        assert_eq!(pick_solc("0.8.3 || ^0.9.0", &installed).as_deref(), Some("0.9.1"));
+        assert_eq!(pick_solc("=0.8.19", &installed).as_deref(), Some("0.8.19"));
+        assert_eq!(pick_solc("^0.6.0", &installed), None);
+        assert!(satisfies((0, 8, 26), "~0.8.0") && !satisfies((0, 9, 0), "~0.8.0"));
+    }
+
+    #[test]
+    fn test_concurrent_standalone_builds() {
+        if Command::new("forge").arg("--version").output().is_err() {
+            eprintln!("forge not installed; skipping");
+            return;
+        }
+        // Same file name in every dir, so artifacts would collide in a shared out/.
+        let dirs: Vec<_> = (0..4).map(|_| tempfile::tempdir().unwrap()).collect();
+        let paths: Vec<PathBuf> = dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| {
+                let path = dir.path().join("Token.sol");
+                let source = format!(
+                    "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\
+                     contract Token{i} {{ function id() external pure returns (uint256) {{ return {i}; }} }}\n"
+                );
+                fs::write(&path, source).unwrap();
+                path
+            })
+            .collect();
+
+        let handles: Vec<_> = paths
+            .into_iter()
+            .map(|path| std::thread::spawn(move || compile_standalone(&path, "", None, &CompileOptions::default())))
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let contracts = handle.join().unwrap().unwrap();
+            let names: Vec<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+            assert_eq!(names, [format!("Token{i}")]);
+        }
+    }
+
+    #[test]
+    fn test_standalone_inherits_ancestor_settings() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(
+            root.path().join("foundry.toml"),
+            "[profile.default]\nsrc = \"contracts\"\noptimizer = true\noptimizer_runs = 10_000\n\
+             via_ir = true\nevm_version = \"cancun\"\nlibs = [\"lib\"]\n\
+             optimizer_details = { yul = false }\n",
+        )
+        .unwrap();
+        let inside = root.path().join("contracts/Token.sol");
+        let outside = root.path().join("src/Token.sol");
+        assert!(is_project_source(&inside, root.path()));
+        assert!(!is_project_source(&outside, root.path()));
+
+        let settings = inherited_settings(&root.path().join("foundry.toml")).unwrap();
+        let settings: toml::Table = settings.parse().unwrap();
+        assert_eq!(settings["optimizer_runs"].as_integer(), Some(10_000));
+        assert_eq!(settings["via_ir"].as_bool(), Some(true));
+        assert_eq!(settings["evm_version"].as_str(), Some("cancun"));
+        assert_eq!(settings["optimizer_details"]["yul"].as_bool(), Some(false));
+        assert!(!settings.contains_key("src") && !settings.contains_key("libs"));
+    }
+}
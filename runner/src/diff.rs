@@ -0,0 +1,284 @@
+use crate::types::{ContractReport, ExecutionStatus};
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The parts of a previously saved JSON report that a diff compares.
+#[derive(Debug, Deserialize)]
+pub struct BaselineContract {
+    pub contract: String,
+    pub functions: Vec<BaselineFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BaselineFunction {
+    pub signature: String,
+    pub gas: u64,
+    #[serde(default)]
+    pub sloads: Option<u64>,
+    #[serde(default)]
+    pub sstores: Option<u64>,
+}
+
+/// Gas and storage-op change of one function between baseline and current run.
+#[derive(Debug, Serialize)]
+pub struct FunctionDiff {
+    pub contract: String,
+    pub signature: String,
+    pub gas_before: u64,
+    pub gas_after: u64,
+    pub gas_delta: i64,
+    /// `gas_delta` as a percentage of `gas_before`, to two decimals; omitted
+    /// when the baseline used no gas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_pct: Option<f64>,
+    /// Omitted unless both runs counted storage ops (`--storage-ops`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sload_delta: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sstore_delta: Option<i64>,
+    /// Gas changed and so did the SLOAD or SSTORE count: the change is likely
+    /// down to storage layout (packing) rather than computation.
+    pub storage_attributed: bool,
+}
+
+/// A saved JSON report: the versioned object, or the bare array of contract
+/// reports written before `schema_version` existed.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedReport {
+    Versioned { contracts: Vec<BaselineContract> },
+    Bare(Vec<BaselineContract>),
+}
+
+/// Read a JSON report written by an earlier `gas` run.
+pub fn load_baseline(path: &Path) -> Result<Vec<BaselineContract>> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading baseline {}", path.display()))?;
+    parse_baseline(&raw).wrap_err_with(|| format!("parsing baseline {}", path.display()))
+}
+
+fn parse_baseline(raw: &str) -> Result<Vec<BaselineContract>> {
+    Ok(match serde_json::from_str(raw)? {
+        SavedReport::Versioned { contracts } | SavedReport::Bare(contracts) => contracts,
+    })
+}
+
+/// A baseline taken from reports measured in this run, for `--baseline` with
+/// a `.sol` file.
+pub fn baseline_from_reports(reports: &[ContractReport]) -> Vec<BaselineContract> {
+    reports
+        .iter()
+        .map(|r| BaselineContract {
+            contract: r.contract.clone(),
+            functions: r
+                .functions
+                .iter()
+                .map(|f| BaselineFunction {
+                    signature: f.signature.clone(),
+                    gas: f.gas,
+                    sloads: f.sloads,
+                    sstores: f.sstores,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Pair functions by contract and signature; functions present in only one
+/// run are left out.
+pub fn diff(baseline: &[BaselineContract], current: &[ContractReport]) -> Vec<FunctionDiff> {
+    let mut diffs = Vec::new();
+    for report in current {
+        let Some(base) = baseline.iter().find(|b| b.contract == report.contract) else {
+            continue;
+        };
+        for f in &report.functions {
+            let Some(before) = base.functions.iter().find(|b| b.signature == f.signature) else {
+                continue;
+            };
+            let delta = |a: Option<u64>, b: Option<u64>| Some(b? as i64 - a? as i64);
+            let sload_delta = delta(before.sloads, f.sloads);
+            let sstore_delta = delta(before.sstores, f.sstores);
+            let gas_delta = f.gas as i64 - before.gas as i64;
+            let gas_pct = (before.gas > 0).then(|| (gas_delta as f64 * 10_000.0 / before.gas as f64).round() / 100.0);
+            diffs.push(FunctionDiff {
+                contract: report.contract.clone(),
+                signature: f.signature.clone(),
+                gas_before: before.gas,
+                gas_after: f.gas,
+                gas_delta,
+                gas_pct,
+                sload_delta,
+                sstore_delta,
+                storage_attributed: gas_delta != 0
+                    && (sload_delta.is_some_and(|d| d != 0) || sstore_delta.is_some_and(|d| d != 0)),
+            });
+        }
+    }
+    diffs
+}
+
+/// Diffs whose gas grew by more than `threshold` percent (`--threshold`).
+pub fn regressions(diffs: &[FunctionDiff], threshold: f64) -> Vec<&FunctionDiff> {
+    diffs.iter().filter(|d| d.gas_delta > 0 && d.gas_pct.is_none_or(|pct| pct > threshold)).collect()
+}
+
+/// The functions whose gas changed, most expensive change first, grouped
+/// into regressions and improvements:
+///
+/// ```text
+/// more expensive:
+///   Vault.deposit() 45000 -> 46000 (+1000, +2.22%)
+/// cheaper:
+///   Vault.total() 2450 -> 2400 (-50, -2.04%)
+/// ```
+pub fn render_table(diffs: &[FunctionDiff]) -> String {
+    let line = |d: &FunctionDiff| {
+        let pct = d.gas_pct.map(|p| format!(", {p:+.2}%")).unwrap_or_default();
+        format!("  {}.{} {} -> {} ({:+}{pct})", d.contract, d.signature, d.gas_before, d.gas_after, d.gas_delta)
+    };
+    let mut worse: Vec<&FunctionDiff> = diffs.iter().filter(|d| d.gas_delta > 0).collect();
+    let mut better: Vec<&FunctionDiff> = diffs.iter().filter(|d| d.gas_delta < 0).collect();
+    worse.sort_by_key(|d| std::cmp::Reverse(d.gas_delta));
+    better.sort_by_key(|d| d.gas_delta);
+    let mut out = Vec::new();
+    for (title, group) in [("more expensive:", worse), ("cheaper:", better)] {
+        if !group.is_empty() {
+            out.push(title.to_string());
+            out.extend(group.into_iter().map(line));
+        }
+    }
+    out.join("\n")
+}
+
+/// Gas of one signature in each compared contract, in the order requested.
+#[derive(Debug, Serialize)]
+pub struct SignatureComparison {
+    pub signature: String,
+    pub contracts: Vec<ContractGas>,
+    /// Contract with the lowest gas among successful calls, if any succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cheapest: Option<String>,
+    /// Highest minus lowest gas across the compared contracts.
+    pub spread: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContractGas {
+    pub contract: String,
+    pub gas: u64,
+    pub status: ExecutionStatus,
+}
+
+/// Compare functions shared by every contract in `names` (matched by
+/// signature, as in [`diff`]), e.g. two implementations of one interface
+/// scanned from the same file.
+pub fn compare_contracts(reports: &[ContractReport], names: &[String]) -> Result<Vec<SignatureComparison>> {
+    let selected = names
+        .iter()
+        .map(|name| match reports.iter().find(|r| &r.contract == name) {
+            Some(report) => Ok(report),
+            None => bail!("contract '{name}' not found in the scanned file"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some((first, rest)) = selected.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut rows = Vec::new();
+    for f in &first.functions {
+        let others = rest
+            .iter()
+            .map(|r| r.functions.iter().find(|g| g.signature == f.signature).map(|g| (r, g)))
+            .collect::<Option<Vec<_>>>();
+        let Some(others) = others else {
+            continue;
+        };
+        let contracts: Vec<ContractGas> = std::iter::once((first, f))
+            .chain(others)
+            .map(|(r, g)| ContractGas { contract: r.contract.clone(), gas: g.gas, status: g.status })
+            .collect();
+        let gas = contracts.iter().map(|c| c.gas);
+        let spread = gas.clone().max().unwrap_or(0) - gas.min().unwrap_or(0);
+        let cheapest = contracts
+            .iter()
+            .filter(|c| c.status == ExecutionStatus::Success)
+            .min_by_key(|c| c.gas)
+            .map(|c| c.contract.clone());
+        rows.push(SignatureComparison { signature: f.signature.clone(), contracts, cheapest, spread });
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FunctionReport;
+
+    #[test]
+    fn test_diff_attributes_storage_changes() {
+        let baseline: Vec<BaselineContract> = serde_json::from_str(
+            r#"[{"contract": "Vault", "functions": [
+                {"signature": "deposit()", "gas": 50000, "sloads": 3, "sstores": 2, "status": "success"},
+                {"signature": "total()", "gas": 2400, "sloads": 1, "sstores": 0},
+                {"signature": "removed()", "gas": 100}
+            ]}]"#,
+        )
+        .unwrap();
+        let mut deposit = FunctionReport::new("deposit".into(), "0x".into(), "deposit()".into(), 45000, ExecutionStatus::Success);
+        deposit.sloads = Some(2);
+        deposit.sstores = Some(1);
+        let mut total = FunctionReport::new("total".into(), "0x".into(), "total()".into(), 2450, ExecutionStatus::Success);
+        total.sloads = Some(1);
+        total.sstores = Some(0);
+        let current = [ContractReport::new("Vault".into(), vec![deposit, total])];
+
+        let diffs = diff(&baseline, &current);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!((diffs[0].gas_delta, diffs[0].sload_delta, diffs[0].sstore_delta), (-5000, Some(-1), Some(-1)));
+        assert!(diffs[0].storage_attributed);
+        assert_eq!(diffs[1].gas_delta, 50);
+        assert!(!diffs[1].storage_attributed);
+        assert_eq!((diffs[0].gas_pct, diffs[1].gas_pct), (Some(-10.0), Some(2.08)));
+        assert!(regressions(&diffs, 2.0).iter().map(|d| &d.signature).eq(["total()"]));
+        assert!(regressions(&diffs, 5.0).is_empty());
+        assert_eq!(
+            render_table(&diffs),
+            "more expensive:\n  Vault.total() 2400 -> 2450 (+50, +2.08%)\ncheaper:\n  Vault.deposit() 50000 -> 45000 (-5000, -10.00%)"
+        );
+    }
+
+    #[test]
+    fn test_baseline_reads_versioned_and_bare_reports() {
+        let contracts = r#"[{"contract": "Vault", "functions": [{"signature": "deposit()", "gas": 100}]}]"#;
+        let versioned = format!(r#"{{"schema_version": 1, "contracts": {contracts}}}"#);
+        for raw in [versioned.as_str(), contracts] {
+            let baseline = parse_baseline(raw).unwrap();
+            assert_eq!((baseline[0].contract.as_str(), baseline[0].functions[0].gas), ("Vault", 100));
+        }
+    }
+
+    #[test]
+    fn test_baseline_from_reports_diffs_to_zero() {
+        let f = FunctionReport::new("ping".into(), "0x".into(), "ping()".into(), 21_000, ExecutionStatus::Success);
+        let reports = [ContractReport::new("Pinger".into(), vec![f])];
+        let diffs = diff(&baseline_from_reports(&reports), &reports);
+        assert_eq!((diffs.len(), diffs[0].gas_delta, diffs[0].gas_pct), (1, 0, Some(0.0)));
+    }
+
+    #[test]
+    fn test_compare_contracts_shared_signatures() {
+        let f = |sig: &str, gas| FunctionReport::new(sig.into(), "0x".into(), sig.into(), gas, ExecutionStatus::Success);
+        let reports = [
+            ContractReport::new("ImplA".into(), vec![f("transfer()", 51_000), f("onlyA()", 1)]),
+            ContractReport::new("ImplB".into(), vec![f("transfer()", 49_000)]),
+        ];
+        let rows = compare_contracts(&reports, &["ImplA".into(), "ImplB".into()]).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].signature, "transfer()");
+        assert_eq!(rows[0].cheapest.as_deref(), Some("ImplB"));
+        assert_eq!(rows[0].spread, 2_000);
+        assert!(compare_contracts(&reports, &["Missing".into()]).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::abi_decode::format_sol_value;
+use crate::types::{DecodedEvent, EventArg};
+use alloy_dyn_abi::EventExt;
+use alloy_json_abi::{Event, JsonAbi};
+use alloy_primitives::Log;
+
+/// Decode each log against the events `abi` declares, matching topic 0 to the
+/// event selector. Logs from undeclared or anonymous events, and logs whose
+/// arguments don't decode, keep their raw topics and data instead.
+pub fn decode_logs(abi: &JsonAbi, logs: &[Log]) -> Vec<DecodedEvent> {
+    logs.iter().map(|log| decode_log(abi, log)).collect()
+}
+
+fn decode_log(abi: &JsonAbi, log: &Log) -> DecodedEvent {
+    let topics = log.topics();
+    let decoded = topics.first().and_then(|topic0| {
+        let event = abi.events().find(|e| !e.anonymous && e.selector() == *topic0)?;
+        let values = event.decode_log_parts(topics.iter().copied(), &log.data.data).ok()?;
+        Some((event, values))
+    });
+    match decoded {
+        Some((event, values)) => DecodedEvent {
+            address: log.address,
+            name: Some(event.name.clone()),
+            args: event_args(event, values),
+            topics: Vec::new(),
+            data: None,
+        },
+        None => DecodedEvent {
+            address: log.address,
+            name: None,
+            args: Vec::new(),
+            topics: topics.to_vec(),
+            data: Some(log.data.data.clone()),
+        },
+    }
+}
+
+/// Pair decoded values with the event's parameters, in declaration order.
+/// Indexed values come from the topics and the rest from the data.
+fn event_args(event: &Event, decoded: alloy_dyn_abi::DecodedEvent) -> Vec<EventArg> {
+    let (mut indexed, mut body) = (decoded.indexed.into_iter(), decoded.body.into_iter());
+    event
+        .inputs
+        .iter()
+        .filter_map(|param| {
+            let value = if param.indexed { indexed.next() } else { body.next() }?;
+            Some(EventArg {
+                name: param.name.clone(),
+                ty: param.selector_type().into_owned(),
+                value: format_sol_value(&value),
+                indexed: param.indexed,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_dyn_abi::DynSolValue;
+    use alloy_primitives::{Address, Bytes, B256, U256};
+
+    #[test]
+    fn test_unknown_event_keeps_raw_topics_and_data() {
+        let abi = JsonAbi::parse(["event Transfer(address indexed from, address indexed to, uint256 value)"]).unwrap();
+        let log = Log::new_unchecked(Address::repeat_byte(1), vec![B256::repeat_byte(0xaa)], Bytes::from(vec![1, 2]));
+        let events = decode_logs(&abi, &[log]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, None);
+        assert_eq!(events[0].topics, vec![B256::repeat_byte(0xaa)]);
+        assert_eq!(events[0].data, Some(Bytes::from(vec![1, 2])));
+    }
+
+    #[test]
+    fn test_args_follow_declaration_order() {
+        let abi = JsonAbi::parse(["event Transfer(address indexed from, uint256 value, address indexed to)"]).unwrap();
+        let event = abi.events().next().unwrap();
+        let decoded = alloy_dyn_abi::DecodedEvent {
+            selector: Some(event.selector()),
+            indexed: vec![DynSolValue::Address(Address::ZERO), DynSolValue::Address(Address::repeat_byte(2))],
+            body: vec![DynSolValue::Uint(U256::from(7), 256)],
+        };
+        let args = event_args(event, decoded);
+        let names: Vec<_> = args.iter().map(|a| (a.name.as_str(), a.indexed)).collect();
+        assert_eq!(names, [("from", true), ("value", false), ("to", true)]);
+        assert_eq!(args[1].value, "7");
+        assert_eq!(args[2].value, Address::repeat_byte(2).to_string());
+    }
+}
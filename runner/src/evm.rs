@@ -1,96 +1,1115 @@
+use crate::abi_decode;
+use crate::adaptive;
+use crate::args::ArgsFile;
+use crate::atomic::{self, AtomicStep};
+use crate::call_graph;
 use crate::calldata::{
-    encode_calldata_with_strategy, encode_constructor_args_with_strategy, CallStrategy,
+    CallStrategy, FUZZ_MAX_LEN, encode_calldata_with_length, encode_calldata_with_strategy,
+    encode_constructor_args_from_strs, encode_constructor_args_with_dependencies, encode_fuzz_calldata,
+    unsupported_types,
 };
-use crate::types::{CompiledContract, ExecutionStatus, FunctionReport};
-use alloy_primitives::{Address, Bytes, TxKind, U256};
-use eyre::{bail, Result};
+use crate::events;
+use crate::fork::Backend;
+use crate::forwarder;
+use crate::inspector::{MemoryExpansionMeter, OpcodeCounter, OpcodeGasMeter, StorageOpCounter};
+use crate::merkle;
+use crate::revert;
+use crate::sequence;
+use crate::signature_db::{self, SelectorNames};
+use crate::signer;
+use crate::state;
+use crate::token;
+use crate::types::{
+    CompiledContract, ExecutionStatus, FunctionReport, ParamDetail, SequenceStepReport,
+    StrategyResult, UnsupportedFunction,
+};
+use crate::userop;
+use alloy_json_abi::StateMutability;
+use alloy_primitives::{Address, B256, Bytes, TxKind, U256, keccak256};
+use eyre::{Result, WrapErr, bail};
+use revm::bytecode::Bytecode;
 use revm::context::TxEnv;
-use revm::context_interface::result::{ExecutionResult, Output};
+use revm::context::transaction::{AccessList, AccessListItem};
+use revm::context_interface::result::{ExecutionResult, HaltReason, Output};
 use revm::database::CacheDB;
-use revm::database_interface::EmptyDB;
+use revm::database_interface::Database;
+use revm::handler::{MainnetContext, MainnetEvm};
+use revm::primitives::hardfork::SpecId;
 use revm::state::AccountInfo;
-use revm::{ExecuteCommitEvm, ExecuteEvm, MainBuilder, MainContext};
+use revm::{
+    DatabaseCommit, ExecuteCommitEvm, ExecuteEvm, InspectEvm, Inspector, MainBuilder, MainContext,
+};
 
-const GAS_LIMIT: u64 = 30_000_000;
-const STRATEGIES: [CallStrategy; 4] = [
+pub(crate) const GAS_LIMIT: u64 = 30_000_000;
+/// EIP-170 limit on deployed runtime code. Deployment doesn't enforce it, so
+/// oversized contracts are still measured and flagged.
+pub const MAX_RUNTIME_SIZE: usize = 24_576;
+const STRATEGIES: [CallStrategy; 5] = [
     CallStrategy::SmartDefaults,
     CallStrategy::IncrementingArgs,
     CallStrategy::CallerAddress,
     CallStrategy::ZeroDefaults,
+    CallStrategy::MaxDefaults,
+];
+/// Dynamic input lengths compared by `--check-unbounded`.
+const SWEEP_LENGTHS: (usize, usize) = (2, 16);
+/// Execution gas per extra input element above which growth counts as
+/// unbounded. ABI-decoding an element into memory costs well under this; a loop
+/// body touching storage or making calls costs well over it.
+const UNBOUNDED_GAS_PER_ELEMENT: u64 = 200;
+
+/// Synthetic EOA that deploys and calls unless `ExecOptions::caller` overrides it.
+pub(crate) const DEFAULT_CALLER: Address = Address::new([
+    0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+]);
+
+/// Wei sent to payable functions when `--value` is not given: 1 ether, well
+/// within the caller's [`setup_db`] balance.
+pub(crate) const DEFAULT_VALUE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// Knobs for `execute_contract`, set from the `gas` command's CLI flags.
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    /// Attach structured input/output parameter lists to each report.
+    pub include_signature_detail: bool,
+    /// Number of dummy accounts to seed into the state before deployment.
+    pub prefill_accounts: u64,
+    /// Try every strategy even after a success and keep the highest-gas success.
+    pub exhaustive: bool,
+    /// Try every strategy even after a success and record each one's call
+    /// alongside the reported result, which is chosen as usual.
+    pub all_strategies: bool,
+    /// Value + data to send through receive/fallback; `None` skips the entry point.
+    pub fallback: Option<FallbackInput>,
+    /// Address that deploys and calls; `None` uses [`DEFAULT_CALLER`].
+    pub caller: Option<Address>,
+    /// Key that signs synthetic messages for signature-verifying functions;
+    /// `caller` is its address.
+    pub signer_key: Option<B256>,
+    /// Setup calls run in the same transaction, just before each measured call.
+    pub atomic_steps: Vec<AtomicStep>,
+    /// Storage written over the state after deployment (`--storage-snapshot`).
+    pub storage_snapshot: Option<state::StorageSnapshot>,
+    /// Slot -> value written into the deployed contract's storage after the
+    /// snapshot (`--storage`).
+    pub storage: Vec<(U256, U256)>,
+    /// Count SLOAD/SSTORE executed by each function's reported call.
+    pub storage_ops: bool,
+    /// Count every opcode executed by each function's reported call.
+    pub trace: bool,
+    /// Measure the gas each function's reported call spends growing memory.
+    pub memory_gas: bool,
+    /// Sweep dynamic input lengths and flag functions whose gas keeps growing.
+    pub check_unbounded: bool,
+    /// Classify functions whose execution gas is the same for every input tried.
+    pub constant_gas: bool,
+    /// Re-run each function's reported call this many times in total and
+    /// flag any gas variance between the runs; 0 or 1 runs once.
+    pub repeat: u32,
+    /// Execute each function's reported call this many times after one
+    /// uncounted warm-up run, committing each run, and report the median
+    /// gas; 0 or 1 runs once.
+    pub runs: u32,
+    /// Hash each function's gas, status, return data and logs.
+    pub fingerprint: bool,
+    /// Record the contract storage slots each function's call changes.
+    pub storage_diff: bool,
+    /// `SIG[=ARGS][@WEI]` steps run in order against shared, committed state
+    /// (`--sequence`), each measured.
+    pub sequence: Vec<String>,
+    /// `SIG[=ARGS][@WEI]` calls committed in order after deployment (and
+    /// `initialize`), so functions are measured in the state they leave.
+    pub setup: Vec<String>,
+    /// Opcodes whose gas is subtracted from each reported total; budgets are
+    /// still checked against the full cost.
+    pub exclude_opcodes: Vec<u8>,
+    /// Selector database (`--selectors`) used to name and measure functions
+    /// the bytecode dispatches but the ABI does not declare.
+    pub selector_names: Option<SelectorNames>,
+    /// Explicit function and per-contract constructor arguments (`--args`).
+    pub args: Option<ArgsFile>,
+    /// Route measured calls through a forwarder contract so `msg.sender` has code.
+    pub caller_is_contract: bool,
+    /// Accounts loaded into the state before anything else (`--genesis`).
+    pub genesis: Option<state::GenesisAlloc>,
+    /// Balances and code set before deployment (`--account`, `--code`),
+    /// applied last so they win over the genesis and the caller's funding.
+    pub accounts: Vec<state::AccountOverride>,
+    /// Call ERC-20/721/1155 functions with holder-style arguments and compare
+    /// them against typical gas for the standard.
+    pub token_mode: bool,
+    /// Make measured calls from this account without its key (deployment
+    /// still comes from the caller), like forge's `vm.prank`.
+    pub impersonate: Option<Address>,
+    /// Commit a call to `initialize` after deployment and measure the other
+    /// functions against the initialized contract.
+    pub initialize: bool,
+    /// Decode the revert reason of functions that only revert, apply the
+    /// matching [`adaptive::Remedy`] and retry.
+    pub adaptive: bool,
+    /// Build each function's EIP-2930 access list and measure what it saves.
+    pub access_list: bool,
+    /// Decode the events each function's reported call emits.
+    pub include_events: bool,
+    /// Transaction gas limit for deployment and every call (`--gas-limit`);
+    /// `None` uses [`GAS_LIMIT`].
+    pub default_gas_limit: Option<u64>,
+    /// Solidity literals for the constructor's parameters, used for every
+    /// contract whose constructor takes any (`--constructor-args`).
+    pub constructor_args: Vec<String>,
+    /// Random inputs tried per function after the strategies (`--fuzz`).
+    pub fuzz_iterations: u32,
+    /// Seed for the `--fuzz` inputs.
+    pub fuzz_seed: u64,
+    /// Most elements in a `--fuzz` dynamic array (`--fuzz-max-len`); `None`
+    /// uses [`FUZZ_MAX_LEN`].
+    pub fuzz_max_len: Option<usize>,
+    /// Block number, timestamp and basefee of the deployment and every call.
+    pub block: BlockSettings,
+    /// `msg.value` sent to payable functions and constructors (`--value`);
+    /// `None` sends [`DEFAULT_VALUE`]. Calls wrapped with `--atomic-step`
+    /// helpers send none.
+    pub value: Option<U256>,
+    /// State behind the local database: empty, or a node's state forked with
+    /// `--fork-url`.
+    pub backend: Backend,
+    /// Functions to measure (`--function`), each a name, a signature or a
+    /// 0x selector; empty measures all of them.
+    pub functions: Vec<String>,
+    /// The contract's `bytecode` is runtime code (`--deployed`): it is
+    /// placed at the address a deployment would get instead of being run.
+    pub inject_runtime: bool,
+    /// Contracts deployed beforehand (`--dep`), present in the state of every
+    /// deployment and passed to `address` constructor parameters.
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A deployed contract another one depends on, moved to [`Dependency::address`]
+/// with the code and storage its constructor left.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub address: Address,
+    pub code: Bytes,
+    pub storage: Vec<(U256, U256)>,
+}
+
+impl Dependency {
+    /// Deploy `contract` as [`execute_contract`] would, then move the result
+    /// to an address derived from `name`, clear of the caller's CREATE
+    /// addresses.
+    pub fn deploy(name: &str, contract: &CompiledContract, opts: &ExecOptions) -> Result<Self> {
+        let Deployment { db, addr, runtime_bytecode, .. } = deploy_best(contract, opts)?;
+        let storage = db.cache.accounts.get(&addr).map(|a| a.storage.iter().map(|(&k, &v)| (k, v)).collect());
+        Ok(Dependency {
+            name: name.to_string(),
+            address: Address::from_word(keccak256(format!("sigscan:dependency:{name}"))),
+            code: runtime_bytecode.into(),
+            storage: storage.unwrap_or_default(),
+        })
+    }
+}
+
+impl ExecOptions {
+    pub fn caller(&self) -> Address {
+        self.caller.unwrap_or(DEFAULT_CALLER)
+    }
+
+    /// Whether `func` passes the `--function` filter.
+    pub fn selects(&self, func: &alloy_json_abi::Function) -> bool {
+        let selector = format!("0x{}", hex::encode(func.selector()));
+        self.functions.is_empty()
+            || self.functions.iter().any(|f| {
+                *f == func.name || *f == func.signature() || f.eq_ignore_ascii_case(&selector)
+            })
+    }
+
+    /// `msg.value` for calling a function or constructor of `mutability`:
+    /// [`Self::value`] for payable ones, zero for the rest, which would revert.
+    pub fn call_value(&self, mutability: StateMutability) -> U256 {
+        match mutability {
+            StateMutability::Payable => self.value.unwrap_or(DEFAULT_VALUE),
+            _ => U256::ZERO,
+        }
+    }
+
+    /// Transaction gas limit for deployment and calls without one of their own.
+    pub fn base_gas_limit(&self) -> u64 {
+        self.default_gas_limit.unwrap_or(GAS_LIMIT)
+    }
+
+    /// Transaction gas limit for calls to `signature`: the `--args` file's
+    /// `gas_limit` for it, else [`Self::base_gas_limit`].
+    pub fn gas_limit(&self, signature: &str) -> u64 {
+        self.args.as_ref().and_then(|a| a.gas_limit(signature)).unwrap_or_else(|| self.base_gas_limit())
+    }
+
+    /// Reject option combinations whose results would be misleading:
+    /// `repeat` and `runs` together, which share `gas_min`/`gas_max`, and
+    /// measurements that re-run the reported call as a direct transaction
+    /// from the caller when `--atomic-step` or `--caller-is-contract` wraps it
+    /// in a helper's transaction, since they would describe a different call
+    /// than the reported `gas`.
+    pub fn validate(&self) -> Result<()> {
+        if self.repeat > 1 && self.runs > 1 {
+            bail!("--repeat cannot be combined with --runs");
+        }
+        if self.atomic_steps.is_empty() && !self.caller_is_contract {
+            return Ok(());
+        }
+        let direct = [
+            ("--storage-ops (or --baseline)", self.storage_ops),
+            ("--trace", self.trace),
+            ("--memory-gas", self.memory_gas),
+            ("--runs", self.runs > 1),
+            ("--exclude-opcodes", !self.exclude_opcodes.is_empty()),
+            ("--fingerprint", self.fingerprint),
+            ("--storage-diff", self.storage_diff),
+            ("--access-list-out", self.access_list),
+            ("--include-events", self.include_events),
+        ];
+        match direct.iter().find(|(_, set)| *set) {
+            Some((flag, _)) => bail!("{flag} cannot be combined with --atomic-step or --caller-is-contract"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Solc `evmVersion` names accepted by `--evm-version`, with the revm spec
+/// that runs the same hardfork.
+pub const EVM_VERSIONS: &[(&str, SpecId)] = &[
+    ("homestead", SpecId::HOMESTEAD),
+    ("tangerineWhistle", SpecId::TANGERINE),
+    ("spuriousDragon", SpecId::SPURIOUS_DRAGON),
+    ("byzantium", SpecId::BYZANTIUM),
+    ("constantinople", SpecId::CONSTANTINOPLE),
+    ("petersburg", SpecId::PETERSBURG),
+    ("istanbul", SpecId::ISTANBUL),
+    ("berlin", SpecId::BERLIN),
+    ("london", SpecId::LONDON),
+    ("paris", SpecId::MERGE),
+    ("shanghai", SpecId::SHANGHAI),
+    ("cancun", SpecId::CANCUN),
+    ("prague", SpecId::PRAGUE),
+    ("osaka", SpecId::OSAKA),
 ];
 
-fn caller() -> Address {
-    Address::new([
-        0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
-    ])
+/// The revm spec for a solc EVM version name, matched case-insensitively.
+pub fn parse_evm_version(name: &str) -> Result<SpecId> {
+    match EVM_VERSIONS.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+        Some(&(_, spec)) => Ok(spec),
+        None => {
+            let known: Vec<&str> = EVM_VERSIONS.iter().map(|(n, _)| *n).collect();
+            bail!("unknown EVM version {name:?}; expected one of {}", known.join(", "))
+        }
+    }
+}
+
+/// Solc EVM version name of `spec`.
+pub fn evm_version_name(spec: SpecId) -> String {
+    match EVM_VERSIONS.iter().find(|(_, s)| *s == spec) {
+        Some((name, _)) => name.to_string(),
+        None => <&str>::from(spec).to_lowercase(),
+    }
+}
+
+/// Block fields and hardfork set for the deployment and every measured call
+/// (`--block-number`, `--block-timestamp`, `--basefee`, `--evm-version`).
+/// Unset fields keep revm's defaults: block 0, timestamp 1, basefee 0 and
+/// the latest mainnet hardfork.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockSettings {
+    pub number: Option<u64>,
+    pub timestamp: Option<u64>,
+    pub basefee: Option<u64>,
+    pub spec: Option<SpecId>,
+    /// Accept transactions from accounts with code, which EIP-3607 rejects,
+    /// so an impersonated contract such as a multisig can send the calls.
+    pub allow_caller_code: bool,
+}
+
+impl BlockSettings {
+    /// A mainnet context over `db` whose block has these fields, running
+    /// the configured hardfork's rules and gas schedule.
+    pub(crate) fn context<DB: Database>(&self, db: DB) -> MainnetContext<DB> {
+        let ctx = revm::Context::mainnet().with_db(db);
+        let ctx = match self.spec {
+            Some(spec) => ctx.modify_cfg_chained(|cfg| cfg.set_spec_and_mainnet_gas_params(spec)),
+            None => ctx,
+        };
+        let ctx = ctx.modify_cfg_chained(|cfg| cfg.disable_eip3607 = self.allow_caller_code);
+        ctx.modify_block_chained(|block| {
+            if let Some(number) = self.number {
+                block.number = U256::from(number);
+            }
+            if let Some(timestamp) = self.timestamp {
+                block.timestamp = U256::from(timestamp);
+            }
+            if let Some(basefee) = self.basefee {
+                block.basefee = basefee;
+            }
+        })
+    }
+
+    /// `tx` priced at the basefee, the lowest gas price the block accepts.
+    pub(crate) fn priced(&self, tx: TxEnv) -> TxEnv {
+        TxEnv { gas_price: u128::from(self.basefee.unwrap_or(0)), ..tx }
+    }
+}
+
+/// A single call into the receive/fallback entry point.
+#[derive(Debug, Clone, Default)]
+pub struct FallbackInput {
+    pub value: U256,
+    pub data: Vec<u8>,
+}
+
+/// What `execute_contract` measured for one deployed contract.
+#[derive(Debug)]
+pub struct ContractExecution {
+    pub functions: Vec<FunctionReport>,
+    /// Code the constructor returned, i.e. what the functions ran against.
+    pub runtime_bytecode: Vec<u8>,
+    /// Account that deployed the contract and made the measured calls.
+    pub deployer: Address,
+    /// Functions skipped because a parameter type could not be encoded.
+    pub unsupported: Vec<UnsupportedFunction>,
+    /// The committed `initialize` call, with `opts.initialize`.
+    pub initializer: Option<FunctionReport>,
+    /// Gas of the deployment transaction that succeeded.
+    pub deploy_gas: u64,
+    /// How the successful deployment's constructor arguments were chosen.
+    pub constructor_strategy: Option<String>,
+    /// The committed `opts.setup` calls.
+    pub setup: Vec<SequenceStepReport>,
 }
 
 /// Deploy and execute every public/external function with multi-strategy retry.
-pub fn execute_contract(contract: &CompiledContract) -> Result<Vec<FunctionReport>> {
-    let caller_addr = caller();
-    let (mut db, addr) = deploy_best(contract, caller_addr)?;
+pub fn execute_contract(
+    contract: &CompiledContract,
+    opts: &ExecOptions,
+) -> Result<ContractExecution> {
+    opts.validate()?;
+    let Prepared {
+        mut db, addr, runtime_bytecode, deployer, deploy_gas, constructor_strategy, opts, initializer, setup,
+    } = prepare(contract, opts)?;
+    let opts = &opts;
 
     let mut reports = Vec::new();
+    let mut unsupported = Vec::new();
     for func_list in contract.abi.functions.values() {
-        for func in func_list {
-            match try_function(&mut db, addr, func, caller_addr) {
-                Ok(r) => reports.push(r),
-                Err(e) => eprintln!("Warning: skipping {}() — {e}", func.name),
+        for (index, func) in func_list.iter().enumerate() {
+            if !opts.selects(func) || initializer.as_ref().is_some_and(|i| i.signature == func.signature()) {
+                continue;
+            }
+            match measure_function(&db, addr, contract, func, opts) {
+                Ok(mut r) => {
+                    r.overload_index = (func_list.len() > 1).then_some(index);
+                    reports.push(r);
+                }
+                Err(e) => skip_function(contract, func, e, &mut unsupported),
+            }
+        }
+    }
+    if let Some(names) = &opts.selector_names {
+        let declared: Vec<_> = contract.abi.functions().map(|f| f.selector()).collect();
+        let mut unnamed = Vec::new();
+        for selector in call_graph::dispatched_selectors(&runtime_bytecode) {
+            if declared.iter().any(|s| format!("0x{}", hex::encode(s)) == selector) {
+                continue;
             }
+            let Some(func) = signature_db::resolve(names, &selector) else {
+                unnamed.push(selector);
+                continue;
+            };
+            if !opts.selects(&func) {
+                continue;
+            }
+            match measure_function(&db, addr, contract, &func, opts) {
+                Ok(mut r) => {
+                    r.named_from_selector_db = true;
+                    reports.push(r);
+                }
+                Err(e) => skip_function(contract, &func, e, &mut unsupported),
+            }
+        }
+        if !unnamed.is_empty() {
+            eprintln!("Warning: {} has no signature known for dispatched selectors {}", contract.name, unnamed.join(", "));
+        }
+    }
+    if let Some(fallback) = &opts.fallback {
+        match try_fallback(&mut db, addr, contract, fallback, opts) {
+            Some(Ok(r)) => reports.push(r),
+            Some(Err(e)) => eprintln!("Warning: skipping {}.fallback() — {e}", contract.name),
+            None => {}
         }
     }
-    Ok(reports)
+    Ok(ContractExecution {
+        functions: reports,
+        runtime_bytecode,
+        deployer,
+        unsupported,
+        initializer,
+        deploy_gas,
+        constructor_strategy,
+        setup,
+    })
 }
 
-/// Try deploying with SmartDefaults, then ZeroDefaults.
-fn deploy_best(
+/// Pick `func`'s calldata and annotate the winning report, against a copy of
+/// the post-deploy state so that whatever one function commits (`--adaptive`
+/// remedies) cannot change the gas of the functions measured after it.
+fn measure_function(
+    db: &CacheDB<Backend>,
+    addr: Address,
     contract: &CompiledContract,
-    caller_addr: Address,
-) -> Result<(CacheDB<EmptyDB>, Address)> {
-    let strategies = [CallStrategy::SmartDefaults, CallStrategy::ZeroDefaults];
-    let mut last_err = None;
-    for strategy in &strategies {
-        let ctor_args =
-            match encode_constructor_args_with_strategy(&contract.abi, *strategy, caller_addr) {
-                Ok(a) => a,
-                Err(e) => { last_err = Some(e); continue; }
-            };
+    func: &alloy_json_abi::Function,
+    opts: &ExecOptions,
+) -> Result<FunctionReport> {
+    let mut db = db.clone();
+    let (mut r, calldata) = try_function(&mut db, addr, func, opts.caller(), opts)?;
+    annotate(&mut db, addr, contract, func, &calldata, &mut r, opts);
+    Ok(r)
+}
+
+/// A deployed contract ready for measured calls.
+struct Prepared {
+    db: CacheDB<Backend>,
+    addr: Address,
+    runtime_bytecode: Vec<u8>,
+    deployer: Address,
+    deploy_gas: u64,
+    constructor_strategy: Option<String>,
+    /// `opts` with `caller` set to the account the calls come from.
+    opts: ExecOptions,
+    initializer: Option<FunctionReport>,
+    setup: Vec<SequenceStepReport>,
+}
+
+/// Deploy `contract` and apply everything that precedes the measured calls:
+/// impersonation, the storage snapshot and overrides, the initializer and the
+/// `--setup` calls, any of which failing fails the whole preparation.
+fn prepare(contract: &CompiledContract, opts: &ExecOptions) -> Result<Prepared> {
+    let Deployment { mut db, addr, runtime_bytecode, deployer, gas: deploy_gas, strategy: constructor_strategy } =
+        deploy_best(contract, opts)?;
+    // A constructor that only accepts a specific deployer usually makes it the
+    // owner too, so keep calling from whoever managed to deploy, unless
+    // calls are meant to come from an impersonated account.
+    if deployer != opts.caller() && opts.impersonate.is_none() {
+        eprintln!("Note: {} deployed only from {deployer}; calling from it", contract.name);
+    }
+    let call_from = opts.impersonate.unwrap_or(deployer);
+    if let Some(account) = opts.impersonate {
+        state::prepare_impersonation(&mut db, account)?;
+    }
+    let block = BlockSettings { allow_caller_code: opts.impersonate.is_some(), ..opts.block };
+    let opts = ExecOptions { caller: Some(call_from), block, ..opts.clone() };
+    if let Some(snapshot) = &opts.storage_snapshot {
+        state::apply_storage_snapshot(&mut db, snapshot)?;
+    }
+    for &(slot, value) in &opts.storage {
+        db.insert_account_storage(addr, slot, value).map_err(|e| eyre::eyre!("storage override: {e:?}"))?;
+    }
+    if let Some(merkle) = opts.args.as_ref().and_then(|a| a.merkle.as_ref())
+        && let Some(slot) = merkle.root_slot
+    {
+        db.insert_account_storage(addr, slot, merkle.root().into())
+            .map_err(|e| eyre::eyre!("seeding merkle root: {e:?}"))?;
+    }
+    let initializer = if opts.initialize { initialize(&mut db, addr, contract, &opts) } else { None };
+    let mut setup = Vec::new();
+    for spec in &opts.setup {
+        let step = sequence::parse_sequence_step(spec, call_from).wrap_err_with(|| format!("--setup {spec}"))?;
+        let gas_limit = opts.gas_limit(&step.signature);
+        let (gas, status) = commit_call(&mut db, call_from, addr, &step.calldata, step.value, gas_limit, &opts.block)?;
+        if status != ExecutionStatus::Success {
+            bail!("setup call {} did not succeed ({})", step.signature, status.as_str());
+        }
+        setup.push(SequenceStepReport { signature: step.signature, gas, status });
+    }
+    Ok(Prepared { db, addr, runtime_bytecode, deployer, deploy_gas, constructor_strategy, opts, initializer, setup })
+}
+
+/// Deploy `contract` and call `opts.sequence` in order against one shared,
+/// committed state, measuring every step. `None` when the contract doesn't
+/// declare all of the sequence's functions.
+pub fn execute_sequence(
+    contract: &CompiledContract,
+    opts: &ExecOptions,
+) -> Result<Option<Vec<SequenceStepReport>>> {
+    let declared: Vec<String> = contract.abi.functions().map(|f| f.signature()).collect();
+    for spec in &opts.sequence {
+        if !declared.contains(&sequence::step_signature(spec)?) {
+            return Ok(None);
+        }
+    }
+    let Prepared { mut db, addr, opts, .. } = prepare(contract, opts)?;
+    let caller_addr = opts.caller();
+    let steps = opts
+        .sequence
+        .iter()
+        .map(|spec| sequence::parse_sequence_step(spec, caller_addr))
+        .collect::<Result<Vec<_>>>()?;
+    let mut reports = Vec::new();
+    for step in steps {
+        let gas_limit = opts.gas_limit(&step.signature);
+        let (gas, status) = commit_call(&mut db, caller_addr, addr, &step.calldata, step.value, gas_limit, &opts.block)?;
+        reports.push(SequenceStepReport { signature: step.signature, gas, status });
+    }
+    Ok(Some(reports))
+}
+
+/// Execute a call and commit its state changes, returning `(gas_used, status)`.
+/// The caller's nonce advances, so later calls read it back from the state.
+fn commit_call(
+    db: &mut CacheDB<Backend>,
+    caller: Address,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus)> {
+    let nonce = account_nonce(db, caller);
+    let mut evm = block.context(&mut *db).build_mainnet();
+    let tx = block.priced(call_tx(caller, addr, calldata, value, gas_limit, nonce));
+    let result = evm.transact(tx).map_err(|e| eyre::eyre!("call error: {e:?}"))?;
+    db.commit(result.state);
+    Ok(match &result.result {
+        ExecutionResult::Success { gas_used, .. } => (*gas_used, ExecutionStatus::Success),
+        ExecutionResult::Revert { gas_used, .. } => (*gas_used, ExecutionStatus::Revert),
+        ExecutionResult::Halt { gas_used, .. } => (*gas_used, ExecutionStatus::Halt),
+    })
+}
+
+/// Find the contract's `initialize` function, pick its arguments like any
+/// other function's, and commit the call so later calls see an initialized
+/// contract. A call that doesn't succeed is reported but not committed.
+fn initialize(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    contract: &CompiledContract,
+    opts: &ExecOptions,
+) -> Option<FunctionReport> {
+    let func = contract.abi.function("initialize")?.first()?;
+    let caller_addr = opts.caller();
+    let (mut report, calldata) = match try_function(db, addr, func, caller_addr, opts) {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("Warning: could not call {}.initialize() — {e}", contract.name);
+            return None;
+        }
+    };
+    decode_custom_error(&mut report, contract);
+    if report.status != ExecutionStatus::Success {
+        eprintln!(
+            "Warning: {}.{} did not succeed ({:?}); measuring uninitialized",
+            contract.name, report.signature, report.status
+        );
+        return Some(report);
+    }
+    let gas_limit = opts.gas_limit(&report.signature);
+    let value = opts.call_value(func.state_mutability);
+    if let Err(e) = commit_call(db, caller_addr, addr, &calldata, value, gas_limit, &opts.block) {
+        eprintln!("Warning: committing {}.initialize() failed — {e}", contract.name);
+    }
+    Some(report)
+}
+
+/// Replace the report's revert reason with the custom error it names, when
+/// the contract's ABI declares one matching the revert data's selector.
+fn decode_custom_error(report: &mut FunctionReport, contract: &CompiledContract) {
+    if let Some(error) = report.revert_data.as_deref().and_then(|data| revert::decode_custom_error(&contract.abi, data)) {
+        report.revert_reason = Some(error);
+    }
+}
+
+/// Warn about a function that could not be measured, recording it when the
+/// cause is a parameter type the encoder cannot handle.
+fn skip_function(
+    contract: &CompiledContract,
+    func: &alloy_json_abi::Function,
+    err: eyre::Report,
+    unsupported: &mut Vec<UnsupportedFunction>,
+) {
+    let (name, types) = (&contract.name, unsupported_types(func));
+    if types.is_empty() {
+        eprintln!("Warning: skipping {name}.{}() — {err}", func.name);
+    } else {
+        eprintln!("Warning: skipping {name}.{}() — unsupported parameter types {}", func.name, types.join(", "));
+        unsupported.push(UnsupportedFunction { signature: func.signature(), types });
+    }
+}
+
+/// Attach the report fields that don't depend on which strategy won, plus the
+/// extra measurements enabled in `opts`, re-running the winning `calldata`.
+fn annotate(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    contract: &CompiledContract,
+    func: &alloy_json_abi::Function,
+    calldata: &[u8],
+    r: &mut FunctionReport,
+    opts: &ExecOptions,
+) {
+    if opts.include_signature_detail {
+        r.inputs = Some(func.inputs.iter().map(ParamDetail::from_param).collect());
+        r.outputs = Some(func.outputs.iter().map(ParamDetail::from_param).collect());
+    }
+    r.auto_getter = is_auto_getter(contract, func);
+    decode_custom_error(r, contract);
+    let caller_addr = opts.caller();
+    let gas_limit = opts.gas_limit(&r.signature);
+    let value = opts.call_value(func.state_mutability);
+    let nonce = account_nonce(db, caller_addr);
+    let tx = opts.block.priced(call_tx(caller_addr, addr, calldata, value, gas_limit, nonce));
+    if opts.storage_ops
+        && let Ok(counter) =
+            inspect_call(db, tx.clone(), &opts.block, StorageOpCounter::default())
+    {
+        r.sloads = Some(counter.sloads);
+        r.sstores = Some(counter.sstores);
+    }
+    if opts.trace {
+        let counter = inspect_call(db, tx.clone(), &opts.block, OpcodeCounter::default());
+        r.opcode_counts = counter.ok().map(|c| c.by_name());
+    }
+    if opts.memory_gas {
+        let meter =
+            inspect_call(db, tx.clone(), &opts.block, MemoryExpansionMeter::default());
+        r.memory_expansion_gas = meter.ok().map(|m| m.gas);
+    }
+    if opts.check_unbounded {
+        r.unbounded_gas_growth = check_unbounded(db, addr, func, caller_addr, opts);
+        if r.unbounded_gas_growth == Some(true) {
+            eprintln!(
+                "Warning: {}.{} gas grows with input length (possible unbounded loop)",
+                contract.name, r.signature
+            );
+        }
+    }
+    if opts.constant_gas {
+        r.constant_gas = check_constant_gas(db, addr, func, caller_addr, opts);
+    }
+    if opts.repeat > 1 {
+        let mut target = CallTarget::new(db, opts);
+        let runs = (1..opts.repeat).filter_map(|_| call(&mut target, addr, func, calldata, opts).ok());
+        let (min, max) = runs.fold((r.gas, r.gas), |(lo, hi), run| (lo.min(run.gas), hi.max(run.gas)));
+        r.gas_stable = Some(min == max);
+        if min != max {
+            r.gas_min = Some(min);
+            r.gas_max = Some(max);
+            eprintln!(
+                "Warning: {}.{} gas varied across {} runs ({min}..{max})",
+                contract.name, r.signature, opts.repeat
+            );
+        }
+    }
+    if opts.runs > 1 {
+        let gases = warm_runs(db, tx.clone(), &opts.block, opts.runs, &opts.exclude_opcodes);
+        let (min, max) = (gases.iter().min().copied(), gases.iter().max().copied());
+        r.gas_median = median(gases);
+        if min != max {
+            (r.gas_min, r.gas_max) = (min, max);
+        }
+    }
+    let unadjusted_gas = r.gas;
+    if !opts.exclude_opcodes.is_empty()
+        && let Ok(meter) = inspect_call(db, tx.clone(), &opts.block, OpcodeGasMeter::default())
+    {
+        // `--repeat` calls run on the same state as the cold call, so they
+        // spend what it spends on the excluded opcodes; `--runs` figures
+        // were already adjusted run by run.
+        let excluded = meter.gas_of(&opts.exclude_opcodes);
+        if opts.runs <= 1 {
+            for gas in [&mut r.gas_min, &mut r.gas_max].into_iter().flatten() {
+                *gas = gas.saturating_sub(excluded);
+            }
+        }
+        r.gas = r.gas.saturating_sub(excluded);
+        r.excluded_opcode_gas = Some(excluded);
+    }
+    if opts.token_mode {
+        r.token_baseline = token::baseline(&token::detect(&contract.abi), &r.signature, r.gas);
+        if let Some(b) = &r.token_baseline {
+            eprintln!(
+                "{}.{}: {} gas vs typical {}-{} for {} ({})",
+                contract.name, r.signature, r.gas, b.typical_min, b.typical_max, b.standard.label(), b.verdict
+            );
+        }
+    }
+    // Budgets cap what the call really costs, so excluded opcodes still count.
+    if let Some(&budget) = contract.gas_budgets.get(&r.signature) {
+        r.gas_budget = Some(budget);
+        r.over_budget = Some(unadjusted_gas > budget);
+    }
+    if opts.fingerprint || opts.storage_diff || opts.access_list || opts.include_events {
+        let mut evm = opts.block.context(&mut *db).build_mainnet();
+        let coinbase = evm.ctx.block.beneficiary;
+        if let Ok(result) = evm.transact(tx.clone()) {
+            if opts.include_events {
+                r.events = events::decode_logs(&contract.abi, result.result.logs());
+            }
+            if opts.fingerprint {
+                r.execution_fingerprint = Some(execution_fingerprint(r.gas, r.status, &result.result));
+            }
+            // The transaction's state is the post-call view of every touched
+            // slot next to its original value, so nothing needs committing.
+            if opts.storage_diff {
+                r.storage_diff = Some(state::storage_changes(&result.state, addr));
+            }
+            if opts.access_list {
+                let list = state::access_list(&result.state, &[caller_addr, addr, coinbase]);
+                let items = list
+                    .iter()
+                    .map(|e| AccessListItem { address: e.address, storage_keys: e.storage_keys.clone() })
+                    .collect();
+                let mut with_list = TxEnv { access_list: AccessList(items), ..tx };
+                // Marks the transaction as type 1 now that it carries a list.
+                let _ = with_list.derive_tx_type();
+                if let Ok(listed) = evm.transact(with_list) {
+                    let saved = result.result.gas_used() as i64 - listed.result.gas_used() as i64;
+                    r.access_list_gas_saved = Some(saved);
+                    r.access_list = Some(list);
+                }
+            }
+        }
+    }
+}
+
+/// Gas of `runs` consecutive executions of `tx`, each committed so the next
+/// one sees the storage it wrote, less what each run spent on the `exclude`
+/// opcodes. A first, uncounted run warms the state, since the cold call is
+/// the one already reported as `gas`. Runs on a copy of `db`; stops early if
+/// a run cannot be executed.
+fn warm_runs(db: &CacheDB<Backend>, tx: TxEnv, block: &BlockSettings, runs: u32, exclude: &[u8]) -> Vec<u64> {
+    let mut db = db.clone();
+    let to = tx.kind.to().copied().unwrap_or_default();
+    let mut run = || {
+        let nonce = account_nonce(&mut db, tx.caller);
+        let tx = block.priced(call_tx(tx.caller, to, &tx.data, tx.value, tx.gas_limit, nonce));
+        let mut evm = block.context(&mut db).build_mainnet_with_inspector(OpcodeGasMeter::default());
+        let result = evm.inspect_tx(tx).ok()?;
+        // Warm runs can touch different slots than the cold call, so each
+        // one is metered on its own.
+        let excluded = evm.inspector.gas_of(exclude);
+        db.commit(result.state);
+        Some(result.result.gas_used().saturating_sub(excluded))
+    };
+    if run().is_none() {
+        return Vec::new();
+    }
+    (0..runs).map_while(|_| run()).collect()
+}
+
+/// Middle value of `values`, averaging the two middle ones for an even count.
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(values[mid]),
+        _ => Some((values[mid - 1] + values[mid]) / 2),
+    }
+}
+
+/// Hash of the reported `gas` and `status` with the call's return data and
+/// logs. Everything hashed is fixed by the inputs (addresses, nonces and block
+/// environment are constant), so the hash only moves when behavior does.
+fn execution_fingerprint(gas: u64, status: ExecutionStatus, result: &ExecutionResult) -> B256 {
+    let mut data = gas.to_be_bytes().to_vec();
+    data.push(status_rank(&status));
+    let output = result.output().map_or(&[][..], |o| &o[..]);
+    data.extend_from_slice(&(output.len() as u64).to_be_bytes());
+    data.extend_from_slice(output);
+    for log in result.logs() {
+        data.extend_from_slice(log.address.as_slice());
+        data.push(log.topics().len() as u8);
+        for topic in log.topics() {
+            data.extend_from_slice(topic.as_slice());
+        }
+        data.extend_from_slice(&(log.data.data.len() as u64).to_be_bytes());
+        data.extend_from_slice(&log.data.data);
+    }
+    keccak256(data)
+}
+
+/// A successful deployment: the committed state, the new contract's address
+/// and runtime code, who deployed it and the deployment's gas.
+struct Deployment {
+    db: CacheDB<Backend>,
+    addr: Address,
+    runtime_bytecode: Vec<u8>,
+    deployer: Address,
+    gas: u64,
+    /// Source of the constructor arguments: a [`strategy_label`], `args_file`
+    /// or `constructor_args`; `None` for a constructor without inputs.
+    strategy: Option<String>,
+}
+
+/// Runtime code `contract`'s constructor returns, deployed as
+/// [`execute_contract`] deploys it.
+pub fn deploy_runtime(contract: &CompiledContract, opts: &ExecOptions) -> Result<Vec<u8>> {
+    Ok(deploy_best(contract, opts)?.runtime_bytecode)
+}
+
+/// Try deploying with SmartDefaults, then ZeroDefaults, from each of
+/// [`deployer_candidates`] in turn. Returns the deployer alongside the state.
+/// Constructor arguments from the `--args` file replace the strategies;
+/// `--constructor-args` are tried first, before falling back to them.
+fn deploy_best(contract: &CompiledContract, opts: &ExecOptions) -> Result<Deployment> {
+    if opts.inject_runtime {
+        return Ok(inject_runtime(contract, opts));
+    }
+    let ctor_params = contract.abi.constructor.as_ref().map_or(&[][..], |c| &c.inputs[..]);
+    let ctor_override = opts
+        .args
+        .as_ref()
+        .and_then(|args| args.constructor_args(&contract.name, ctor_params))
+        .transpose()?;
+    let strategies = match ctor_override {
+        Some(_) => &[CallStrategy::SmartDefaults][..],
+        None => &[CallStrategy::SmartDefaults, CallStrategy::ZeroDefaults][..],
+    };
+    let value = contract.abi.constructor.as_ref().map_or(U256::ZERO, |c| opts.call_value(c.state_mutability));
+    if ctor_override.is_none() && !ctor_params.is_empty() && !opts.constructor_args.is_empty() {
         let mut data = contract.bytecode.clone();
-        data.extend_from_slice(&ctor_args);
-        match deploy(setup_db(), &data) {
-            Ok(result) => return Ok(result),
-            Err(e) => { last_err = Some(e); continue; }
+        data.extend_from_slice(
+            &encode_constructor_args_from_strs(&contract.abi, &opts.constructor_args).wrap_err("--constructor-args")?,
+        );
+        let mut first_err = None;
+        for caller_addr in deployer_candidates(contract, opts) {
+            match deploy(setup_db(opts, caller_addr), &data, caller_addr, value, opts.base_gas_limit(), &opts.block) {
+                Ok((db, addr, runtime_bytecode, gas)) => {
+                    let strategy = Some("constructor_args".into());
+                    return Ok(Deployment { db, addr, runtime_bytecode, deployer: caller_addr, gas, strategy });
+                }
+                Err(e) => { first_err.get_or_insert(e); }
+            }
+        }
+        if let Some(e) = first_err {
+            eprintln!("Warning: {} did not deploy with --constructor-args ({e}); guessing arguments", contract.name);
+        }
+    }
+    let deps: Vec<(String, Address)> = opts.dependencies.iter().map(|d| (d.name.clone(), d.address)).collect();
+    let mut first_err = None;
+    for caller_addr in deployer_candidates(contract, opts) {
+        for strategy in strategies {
+            let ctor_args = match &ctor_override {
+                Some(args) => args.clone(),
+                None => match encode_constructor_args_with_dependencies(&contract.abi, *strategy, caller_addr, &deps) {
+                    Ok(a) => a,
+                    Err(e) => { first_err.get_or_insert(e); continue; }
+                },
+            };
+            let mut data = contract.bytecode.clone();
+            data.extend_from_slice(&ctor_args);
+            match deploy(setup_db(opts, caller_addr), &data, caller_addr, value, opts.base_gas_limit(), &opts.block) {
+                Ok((db, addr, runtime_bytecode, gas)) => {
+                    let strategy = (!ctor_params.is_empty()).then(|| match ctor_override {
+                        Some(_) => "args_file".into(),
+                        None => strategy_label(*strategy),
+                    });
+                    return Ok(Deployment { db, addr, runtime_bytecode, deployer: caller_addr, gas, strategy });
+                }
+                Err(e) => { first_err.get_or_insert(e); continue; }
+            }
+        }
+    }
+    // Report the configured caller's failure; later candidates are guesses.
+    Err(first_err.unwrap_or_else(|| eyre::eyre!("deployment failed")))
+}
+
+/// Place `contract`'s bytecode, taken as runtime code, at the caller's first
+/// CREATE address, leaving the state as a deployment would: the caller's
+/// nonce at 1 and the contract's at 1. No gas is spent.
+fn inject_runtime(contract: &CompiledContract, opts: &ExecOptions) -> Deployment {
+    let deployer = opts.caller();
+    let mut db = setup_db(opts, deployer);
+    let addr = deployer.create(0);
+    if let Some(account) = db.cache.accounts.get_mut(&deployer) {
+        account.info.nonce = 1;
+    }
+    let code = Bytecode::new_raw(Bytes::copy_from_slice(&contract.bytecode));
+    db.insert_account_info(addr, AccountInfo { nonce: 1, ..Default::default() }.with_code(code));
+    Deployment { db, addr, runtime_bytecode: contract.bytecode.clone(), deployer, gas: 0, strategy: None }
+}
+
+/// Cap on hardcoded addresses tried as deployers, so address-heavy init code
+/// doesn't multiply deployment attempts.
+const MAX_HARDCODED_DEPLOYERS: usize = 8;
+
+/// The configured caller, then the smart-default address, then addresses
+/// hardcoded in the init code, for constructors that
+/// `require(msg.sender == ADMIN)`.
+fn deployer_candidates(contract: &CompiledContract, opts: &ExecOptions) -> Vec<Address> {
+    let mut candidates = vec![opts.caller()];
+    let hardcoded = call_graph::pushed_addresses(&contract.bytecode);
+    for addr in std::iter::once(Address::with_last_byte(1)).chain(hardcoded.into_iter().take(MAX_HARDCODED_DEPLOYERS)) {
+        if !candidates.contains(&addr) {
+            candidates.push(addr);
         }
     }
-    Err(last_err.unwrap_or_else(|| eyre::eyre!("deployment failed")))
+    candidates
 }
 
-/// Try each strategy, pick best: Success > Revert > Halt. Early-exit on Success.
+/// Try each provider/strategy, pick best: Success > Revert > Halt. Early-exit on
+/// Success unless `opts.exhaustive`, which keeps the highest-gas success.
+/// `opts.fuzz_iterations` random inputs follow the strategies and likewise
+/// keep the highest-gas success, whose calldata goes into the report.
+/// With `opts.adaptive`, a best result that reverts goes on to [`adapt`].
+/// With `opts.all_strategies`, every non-fuzz attempt is recorded in the
+/// report's `strategies`. Returns the winning report together with the
+/// calldata that produced it; a reverting report keeps its raw revert data for
+/// [`decode_custom_error`].
 fn try_function(
-    db: &mut CacheDB<EmptyDB>,
+    db: &mut CacheDB<Backend>,
     addr: Address,
     func: &alloy_json_abi::Function,
     caller_addr: Address,
-) -> Result<FunctionReport> {
-    let mut best: Option<(FunctionReport, u8)> = None;
+    opts: &ExecOptions,
+) -> Result<(FunctionReport, Vec<u8>)> {
+    // Curated providers for domain-specific shapes go first, then the generic strategies.
+    let mut attempts: Vec<(String, Vec<u8>)> = Vec::new();
+    if let Some(encoded) = opts.args.as_ref().and_then(|a| a.function_args(&func.signature(), &func.inputs)) {
+        let mut cd = func.selector().to_vec();
+        cd.extend_from_slice(&encoded?);
+        attempts.push(("args_file".into(), cd));
+    }
+    if let Some(cd) = opts
+        .args
+        .as_ref()
+        .and_then(|a| a.merkle.as_ref())
+        .and_then(|spec| merkle::encode_proof_call(func, spec, caller_addr))
+    {
+        attempts.push(("merkle_proof".into(), cd));
+    }
+    if let Some(cd) = userop::encode_user_op_call(func, addr, caller_addr) {
+        attempts.push(("user_operation".into(), cd));
+    }
+    if opts.token_mode {
+        attempts.extend(token::encode_token_calls(func, caller_addr));
+    }
+    if let Some(key) = opts.signer_key {
+        attempts.extend(signer::encode_signed_calls(func, key, caller_addr));
+    }
     for strategy in &STRATEGIES {
-        let cd = match encode_calldata_with_strategy(func, *strategy, caller_addr) {
-            Ok(cd) => cd,
-            Err(_) => continue,
-        };
-        let mut report = match call(db, addr, func, &cd) {
+        if let Ok(cd) = encode_calldata_with_strategy(func, *strategy, caller_addr) {
+            attempts.push((strategy_label(*strategy), cd));
+        }
+    }
+    let fuzzing = opts.fuzz_iterations > 0;
+    let named_attempts = attempts.len();
+    let max_len = opts.fuzz_max_len.unwrap_or(FUZZ_MAX_LEN);
+    for cd in encode_fuzz_calldata(func, caller_addr, opts.fuzz_seed, opts.fuzz_iterations, max_len) {
+        attempts.push((strategy_label(CallStrategy::Fuzz), cd));
+    }
+
+    let mut best: Option<(FunctionReport, u8, Vec<u8>)> = None;
+    let mut results = Vec::new();
+    let mut target = CallTarget::new(db, opts);
+    for (i, (label, cd)) in attempts.into_iter().enumerate() {
+        let mut report = match call(&mut target, addr, func, &cd, opts) {
             Ok(r) => r,
             Err(_) => continue,
         };
-        report.strategy = Some(strategy_label(*strategy));
+        if opts.all_strategies && i < named_attempts {
+            results.push(StrategyResult {
+                strategy: label.clone(),
+                gas: report.gas,
+                status: report.status,
+                calldata: Bytes::copy_from_slice(&cd),
+                revert_reason: report.revert_reason.clone(),
+                halt_reason: report.halt_reason.clone(),
+            });
+        }
+        report.strategy = Some(label);
         let rank = status_rank(&report.status);
-        if rank == 2 { return Ok(report); }
-        if best.as_ref().map_or(true, |(_, r)| rank > *r) {
-            best = Some((report, rank));
+        if rank == 2 && !opts.exhaustive && !opts.all_strategies && !fuzzing {
+            return Ok((report, cd));
+        }
+        // Exhaustive and fuzz modes keep the most expensive success: the worst realistic path.
+        let keep_max = opts.exhaustive || fuzzing;
+        let better = match &best {
+            None => true,
+            Some((b, r, _)) => rank > *r || (keep_max && rank == 2 && *r == 2 && report.gas > b.gas),
+        };
+        if better {
+            best = Some((report, rank, cd));
+        }
+    }
+    if fuzzing && let Some((r, _, cd)) = &mut best {
+        r.calldata = Some(Bytes::copy_from_slice(cd));
+    }
+    let (mut report, cd) = match best {
+        Some((r, 1, cd)) if opts.adaptive => adapt(db, addr, func, r, cd, opts),
+        Some((r, _, cd)) => (r, cd),
+        None => return Err(eyre::eyre!("all strategies failed for {}()", func.name)),
+    };
+    report.strategies = results;
+    Ok((report, cd))
+}
+
+/// Retries `--adaptive` makes after the strategies, one remedy each.
+const ADAPTIVE_ROUNDS: usize = 4;
+
+/// Decode why `calldata` reverts, apply the remedy for that reason and call
+/// again, until the call succeeds, the reason has no remedy or its remedy was
+/// already applied. State remedies are committed to `db`. Revert reasons are
+/// read from a direct call by the caller.
+fn adapt(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    func: &alloy_json_abi::Function,
+    mut report: FunctionReport,
+    mut calldata: Vec<u8>,
+    opts: &ExecOptions,
+) -> (FunctionReport, Vec<u8>) {
+    let caller_addr = opts.caller();
+    let mut applied = Vec::new();
+    for _ in 0..ADAPTIVE_ROUNDS {
+        let gas_limit = opts.gas_limit(&report.signature);
+        let value = opts.call_value(func.state_mutability);
+        let output = transact_call_output(db, caller_addr, addr, &calldata, value, gas_limit, &opts.block).ok();
+        let revert_data = output.and_then(|(_, status, out)| out.data().filter(|_| status == ExecutionStatus::Revert));
+        let Some(reason) = revert_data.and_then(|data| adaptive::revert_reason(&data)) else {
+            break;
+        };
+        let Some(remedy) = adaptive::remedy_for(&reason).filter(|r| !applied.contains(r)) else {
+            break;
+        };
+        applied.push(remedy);
+        for setup in adaptive::setup_calls(remedy, caller_addr, addr) {
+            // Commit only setups that succeed, so a missing function costs nothing.
+            let gas_limit = opts.base_gas_limit();
+            let outcome = transact_call(db, caller_addr, addr, &setup, U256::ZERO, gas_limit, &opts.block);
+            if let Ok((_, ExecutionStatus::Success)) = outcome {
+                let _ = commit_call(db, caller_addr, addr, &setup, U256::ZERO, gas_limit, &opts.block);
+            }
+        }
+        let mut candidates = adaptive::retry_calldata(remedy, func, caller_addr);
+        if candidates.is_empty() {
+            candidates.push(calldata.clone());
         }
+        let mut next = None;
+        let mut target = CallTarget::new(db, opts);
+        for cd in candidates {
+            let Ok(mut r) = call(&mut target, addr, func, &cd, opts) else { continue };
+            r.strategy = report.strategy.clone();
+            match r.status {
+                ExecutionStatus::Success => {
+                    r.remedies = Some(applied.iter().map(|r| r.label().to_string()).collect());
+                    return (r, cd);
+                }
+                ExecutionStatus::Revert if next.is_none() => next = Some((r, cd)),
+                _ => {}
+            }
+        }
+        let Some((r, cd)) = next else { break };
+        (report, calldata) = (r, cd);
     }
-    best.map(|(r, _)| r)
-        .ok_or_else(|| eyre::eyre!("all strategies failed for {}()", func.name))
+    report.remedies = (!applied.is_empty()).then(|| applied.iter().map(|r| r.label().to_string()).collect());
+    (report, calldata)
 }
 
 fn status_rank(s: &ExecutionStatus) -> u8 {
@@ -107,29 +1126,65 @@ fn strategy_label(s: CallStrategy) -> String {
         CallStrategy::IncrementingArgs => "incrementing_args".into(),
         CallStrategy::CallerAddress => "caller_address".into(),
         CallStrategy::ZeroDefaults => "zero_defaults".into(),
+        CallStrategy::MaxDefaults => "max_defaults".into(),
+        CallStrategy::Fuzz => "fuzz".into(),
     }
 }
 
-fn setup_db() -> CacheDB<EmptyDB> {
-    let mut db = CacheDB::new(EmptyDB::new());
+fn setup_db(opts: &ExecOptions, caller: Address) -> CacheDB<Backend> {
+    let mut db = CacheDB::new(opts.backend.clone());
+    if let Some(alloc) = &opts.genesis {
+        // Each account is inserted before its storage, so no slot write looks
+        // anything up in the backend and none can fail.
+        let _ = state::apply_genesis(&mut db, alloc);
+    }
+    state::prefill_accounts(&mut db, opts.prefill_accounts);
+    for dep in &opts.dependencies {
+        let code = Bytecode::new_raw(dep.code.clone());
+        db.insert_account_info(dep.address, AccountInfo { nonce: 1, ..Default::default() }.with_code(code));
+        for &(slot, value) in &dep.storage {
+            // The account was just inserted, so no write reaches the backend.
+            let _ = db.insert_account_storage(dep.address, slot, value);
+        }
+    }
     let balance = U256::from(10_000u64) * U256::from(10u64).pow(U256::from(18u64));
-    db.insert_account_info(caller(), AccountInfo { balance, nonce: 0, ..Default::default() });
+    db.insert_account_info(caller, AccountInfo { balance, nonce: 0, ..Default::default() });
+    state::apply_account_overrides(&mut db, &opts.accounts);
+    if opts.caller_is_contract {
+        forwarder::install(&mut db);
+    }
     db
 }
 
-fn deploy(db: CacheDB<EmptyDB>, data: &[u8]) -> Result<(CacheDB<EmptyDB>, Address)> {
-    let mut evm = revm::Context::mainnet().with_db(db).build_mainnet();
-    let tx = TxEnv {
-        caller: caller(),
-        gas_limit: GAS_LIMIT,
+/// Deploy `data` with `value` under `gas_limit` and return the committed
+/// state, the new address, its runtime code and the gas used. The EIP-170
+/// size limit is lifted so oversized contracts can still be measured.
+fn deploy(
+    db: CacheDB<Backend>,
+    data: &[u8],
+    caller: Address,
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(CacheDB<Backend>, Address, Vec<u8>, u64)> {
+    let mut evm = block
+        .context(db)
+        .modify_cfg_chained(|cfg| cfg.limit_contract_code_size = Some(usize::MAX))
+        .build_mainnet();
+    let tx = block.priced(TxEnv {
+        caller,
+        gas_limit,
         kind: TxKind::Create,
         data: Bytes::copy_from_slice(data),
+        value,
         ..Default::default()
-    };
+    });
     let result = evm.transact_commit(tx).map_err(|e| eyre::eyre!("deploy error: {e:?}"))?;
     match result {
-        ExecutionResult::Success { output, .. } => match output {
-            Output::Create(_, Some(addr)) => Ok((evm.ctx.journaled_state.database, addr)),
+        ExecutionResult::Success { output, gas_used, .. } => match output {
+            Output::Create(code, Some(addr)) => {
+                Ok((evm.ctx.journaled_state.database, addr, code.to_vec(), gas_used))
+            }
             Output::Create(_, None) => bail!("CREATE succeeded but no address returned"),
             Output::Call(_) => bail!("expected CREATE output, got CALL"),
         },
@@ -138,33 +1193,639 @@ fn deploy(db: CacheDB<EmptyDB>, data: &[u8]) -> Result<(CacheDB<EmptyDB>, Addres
     }
 }
 
+/// An EVM built over a state and reused for many non-committing calls: each
+/// `transact` sets a new transaction and leaves the state as it found it.
+type CallEvm<'a> = MainnetEvm<MainnetContext<&'a mut CacheDB<Backend>>>;
+
+/// Where [`call`] runs. Plain calls share one [`CallEvm`], so a function's
+/// strategies, repeats and sweeps build the context once rather than per
+/// call; `--atomic-step` and `--caller-is-contract` calls deploy helpers
+/// around each call and build their own.
+///
+/// The EVM lives for one function's measurement, not the whole contract's:
+/// [`measure_function`] gives every function its own copy of the post-deploy
+/// state, and a context borrows the state it runs on, so each copy needs a
+/// new one. Building that context costs far less than the copy itself.
+enum CallTarget<'a> {
+    Evm(Box<CallEvm<'a>>),
+    Wrapped(&'a mut CacheDB<Backend>),
+}
+
+impl<'a> CallTarget<'a> {
+    fn new(db: &'a mut CacheDB<Backend>, opts: &ExecOptions) -> Self {
+        match opts.atomic_steps.is_empty() && !opts.caller_is_contract {
+            true => CallTarget::Evm(Box::new(opts.block.context(db).build_mainnet())),
+            false => CallTarget::Wrapped(db),
+        }
+    }
+}
+
 fn call(
-    db: &mut CacheDB<EmptyDB>,
+    target: &mut CallTarget,
     addr: Address,
     func: &alloy_json_abi::Function,
     calldata: &[u8],
+    opts: &ExecOptions,
 ) -> Result<FunctionReport> {
-    let mut evm = revm::Context::mainnet().with_db(&mut *db).build_mainnet();
-    let tx = TxEnv {
-        caller: caller(),
-        gas_limit: GAS_LIMIT,
+    let signature = func.signature();
+    let gas_limit = opts.gas_limit(&signature);
+    let value = opts.call_value(func.state_mutability);
+    let (gas, status, output) = transact_target(target, addr, calldata, value, gas_limit, opts)?;
+    let mut report = FunctionReport::new(
+        func.name.clone(),
+        format!("0x{}", hex::encode(func.selector().as_slice())),
+        signature,
+        gas,
+        status,
+    );
+    report.mutability = Some(func.state_mutability);
+    report.gas_limit = (gas_limit != opts.base_gas_limit()).then_some(gas_limit);
+    report.value = (value != U256::ZERO).then_some(value);
+    match (status, output) {
+        (_, CallOutput::Halted(reason)) => report.halt_reason = Some(format!("{reason:?}")),
+        (ExecutionStatus::Success, output) => {
+            let output = output.data();
+            report.return_values = output.as_deref().and_then(|data| abi_decode::decode_outputs(func, data));
+            report.return_data = output.filter(|data| !data.is_empty());
+        }
+        (_, output) => {
+            let output = output.data();
+            report.revert_reason = output.as_deref().and_then(|data| revert::decode_revert(data));
+            report.revert_data = output;
+        }
+    }
+    Ok(report)
+}
+
+/// Send `calldata` with `value` to `addr` through `target` and return the
+/// call's `(gas, status, output)`; wrapped calls record no output.
+fn transact_target(
+    target: &mut CallTarget,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    opts: &ExecOptions,
+) -> Result<(u64, ExecutionStatus, CallOutput)> {
+    // Atomic steps already run from the helper contract, so msg.sender has code either way.
+    Ok(match target {
+        CallTarget::Evm(evm) => transact_output(evm, opts.caller(), addr, calldata, value, gas_limit, &opts.block)?,
+        CallTarget::Wrapped(db) if !opts.atomic_steps.is_empty() => {
+            let (steps, block) = (&opts.atomic_steps, &opts.block);
+            let (gas, status) = atomic::transact_atomic(db, opts.caller(), addr, steps, calldata, gas_limit, block)?;
+            (gas, status, CallOutput::Unrecorded)
+        }
+        CallTarget::Wrapped(db) => {
+            let (gas, status) =
+                forwarder::transact_via_forwarder(db, opts.caller(), addr, calldata, value, gas_limit, &opts.block)?;
+            (gas, status, CallOutput::Unrecorded)
+        }
+    })
+}
+
+/// Run `func` with short and long dynamic inputs and classify the growth of its
+/// execution gas (calldata cost excluded) per added element. `None` when the
+/// function takes no dynamically sized input or either run does not succeed.
+fn check_unbounded(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    func: &alloy_json_abi::Function,
+    caller_addr: Address,
+    opts: &ExecOptions,
+) -> Option<bool> {
+    let (small, large) = SWEEP_LENGTHS;
+    let mut target = CallTarget::new(db, opts);
+    let mut exec_gas = |len: usize| -> Option<u64> {
+        let cd = encode_calldata_with_length(func, len, caller_addr).ok()??;
+        let r = call(&mut target, addr, func, &cd, opts).ok()?;
+        (r.status == ExecutionStatus::Success).then(|| r.gas.saturating_sub(intrinsic_gas(&cd)))
+    };
+    let (g_small, g_large) = (exec_gas(small)?, exec_gas(large)?);
+    let per_element = g_large.saturating_sub(g_small) / (large - small) as u64;
+    Some(per_element > UNBOUNDED_GAS_PER_ELEMENT)
+}
+
+/// Call `func` with every strategy's arguments and, for dynamic inputs, the
+/// sweep lengths, and report whether all successful runs used the same
+/// execution gas (calldata cost excluded). A function without inputs is
+/// constant by definition; `None` when fewer than two distinct inputs succeed.
+fn check_constant_gas(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    func: &alloy_json_abi::Function,
+    caller_addr: Address,
+    opts: &ExecOptions,
+) -> Option<bool> {
+    if func.inputs.is_empty() {
+        return Some(true);
+    }
+    let mut inputs: Vec<Vec<u8>> = STRATEGIES
+        .iter()
+        .filter_map(|&strategy| encode_calldata_with_strategy(func, strategy, caller_addr).ok())
+        .collect();
+    let (small, large) = SWEEP_LENGTHS;
+    for len in [small, large] {
+        if let Ok(Some(cd)) = encode_calldata_with_length(func, len, caller_addr) {
+            inputs.push(cd);
+        }
+    }
+    inputs.sort();
+    inputs.dedup();
+    let mut target = CallTarget::new(db, opts);
+    let exec_gas: Vec<u64> = inputs
+        .iter()
+        .filter_map(|cd| {
+            let r = call(&mut target, addr, func, cd, opts).ok()?;
+            (r.status == ExecutionStatus::Success).then(|| r.gas.saturating_sub(intrinsic_gas(cd)))
+        })
+        .collect();
+    (exec_gas.len() >= 2).then(|| exec_gas.iter().all(|&g| g == exec_gas[0]))
+}
+
+/// Solc forbids a function sharing a state variable's name, so a view function
+/// named after a storage variable can only be its generated getter.
+fn is_auto_getter(contract: &CompiledContract, func: &alloy_json_abi::Function) -> bool {
+    func.state_mutability == StateMutability::View
+        && contract.storage_variables.contains(&func.name)
+}
+
+/// Measure one call into the contract's receive/fallback entry point carrying
+/// `--fallback-value` and `--fallback-data` together.
+///
+/// Reported as `receive()` when the EVM would route it there (empty data and a
+/// `receive` function), otherwise as `fallback()`. The call is wrapped like the
+/// functions' calls, so `--caller-is-contract` sends it through the forwarder.
+/// Returns `None` when the contract has neither entry point.
+fn try_fallback(
+    db: &mut CacheDB<Backend>,
+    addr: Address,
+    contract: &CompiledContract,
+    fallback: &FallbackInput,
+    opts: &ExecOptions,
+) -> Option<Result<FunctionReport>> {
+    let name = if fallback.data.is_empty() && contract.abi.receive.is_some() {
+        "receive"
+    } else if contract.abi.fallback.is_some() {
+        "fallback"
+    } else {
+        return None;
+    };
+    let selector = format!("0x{}", hex::encode(&fallback.data[..fallback.data.len().min(4)]));
+    let mut target = CallTarget::new(db, opts);
+    let result = transact_target(&mut target, addr, &fallback.data, fallback.value, opts.base_gas_limit(), opts);
+    Some(result.map(|(gas, status, _)| FunctionReport::new(name.into(), selector, format!("{name}()"), gas, status)))
+}
+
+/// Execute `tx` without committing under `inspector` and hand the inspector back.
+pub(crate) fn inspect_call<'a, I>(
+    db: &'a mut CacheDB<Backend>,
+    tx: TxEnv,
+    block: &BlockSettings,
+    inspector: I,
+) -> Result<I>
+where
+    I: Inspector<MainnetContext<&'a mut CacheDB<Backend>>>,
+{
+    let mut evm = block.context(db).build_mainnet_with_inspector(inspector);
+    evm.inspect_one_tx(tx).map_err(|e| eyre::eyre!("call error: {e:?}"))?;
+    Ok(evm.inspector)
+}
+
+pub(crate) fn call_tx(
+    caller: Address,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    nonce: u64,
+) -> TxEnv {
+    TxEnv {
+        caller,
+        gas_limit,
         kind: TxKind::Call(addr),
         data: Bytes::copy_from_slice(calldata),
-        nonce: 1,
+        value,
+        nonce,
         ..Default::default()
-    };
+    }
+}
+
+/// `addr`'s current nonce in `db`, which the next transaction it sends must
+/// carry; 0 for an account the state does not have.
+pub(crate) fn account_nonce(db: &mut CacheDB<Backend>, addr: Address) -> u64 {
+    db.basic(addr).ok().flatten().map_or(0, |info| info.nonce)
+}
+
+/// Execute a non-committing call and return `(gas_used, status)`.
+pub(crate) fn transact_call(
+    db: &mut CacheDB<Backend>,
+    caller: Address,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus)> {
+    transact_call_output(db, caller, addr, calldata, value, gas_limit, block).map(|(gas, status, _)| (gas, status))
+}
+
+/// What a call left behind besides its gas and status.
+#[derive(Debug, PartialEq)]
+enum CallOutput {
+    /// Data the call returned, or its revert data.
+    Data(Bytes),
+    /// Why the call halted.
+    Halted(HaltReason),
+    /// Made through a helper contract, which keeps neither.
+    Unrecorded,
+}
+
+impl CallOutput {
+    fn data(self) -> Option<Bytes> {
+        match self {
+            CallOutput::Data(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// [`transact_call`], plus the call's output: the data it returned, its
+/// revert data, or why it halted.
+fn transact_call_output(
+    db: &mut CacheDB<Backend>,
+    caller: Address,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus, CallOutput)> {
+    let mut evm = block.context(&mut *db).build_mainnet();
+    transact_output(&mut evm, caller, addr, calldata, value, gas_limit, block)
+}
+
+/// [`transact_call_output`] on an already built EVM.
+fn transact_output(
+    evm: &mut CallEvm,
+    caller: Address,
+    addr: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus, CallOutput)> {
+    let nonce = account_nonce(evm.ctx.journaled_state.database, caller);
+    let tx = block.priced(call_tx(caller, addr, calldata, value, gas_limit, nonce));
     let result = evm.transact(tx).map_err(|e| eyre::eyre!("call error: {e:?}"))?;
-    let (gas, status) = match &result.result {
-        ExecutionResult::Success { gas_used, .. } => (*gas_used, ExecutionStatus::Success),
-        ExecutionResult::Revert { gas_used, .. } => (*gas_used, ExecutionStatus::Revert),
-        ExecutionResult::Halt { gas_used, .. } => (*gas_used, ExecutionStatus::Halt),
-    };
-    Ok(FunctionReport {
-        name: func.name.clone(),
-        selector: format!("0x{}", hex::encode(func.selector().as_slice())),
-        signature: func.signature(),
-        gas,
-        status,
-        strategy: None,
+    Ok(match result.result {
+        ExecutionResult::Success { gas_used, output, .. } => {
+            (gas_used, ExecutionStatus::Success, CallOutput::Data(output.into_data()))
+        }
+        ExecutionResult::Revert { gas_used, output } => (gas_used, ExecutionStatus::Revert, CallOutput::Data(output)),
+        ExecutionResult::Halt { gas_used, reason } => (gas_used, ExecutionStatus::Halt, CallOutput::Halted(reason)),
     })
 }
+
+/// Base transaction cost plus calldata pricing (4 gas per zero byte, 16 otherwise).
+pub(crate) fn intrinsic_gas(calldata: &[u8]) -> u64 {
+    21_000 + calldata.iter().map(|&b| if b == 0 { 4 } else { 16 }).sum::<u64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty state holding `code` at `target` and 1 ether on
+    /// [`DEFAULT_CALLER`], as a deployment would leave the caller.
+    fn funded_db(target: Address, code: &str) -> CacheDB<Backend> {
+        let mut db = CacheDB::new(Backend::Empty);
+        let balance = U256::from(10u64).pow(U256::from(18u64));
+        db.insert_account_info(DEFAULT_CALLER, AccountInfo { balance, nonce: 1, ..Default::default() });
+        let code = hex::decode(code).unwrap();
+        db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+        db
+    }
+
+    /// A contract with only a name and hex init code.
+    fn contract(name: &str, code: &str) -> CompiledContract {
+        CompiledContract { name: name.into(), bytecode: hex::decode(code).unwrap(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_deploys_from_hardcoded_deployer() {
+        let admin = Address::repeat_byte(0xad);
+        // if (caller() != ADMIN) revert(0, 0); stop
+        let init = format!("3373{}14601e57600080fd5b00", hex::encode(admin));
+        let contract = contract("Gated", &init);
+        let candidates = deployer_candidates(&contract, &ExecOptions::default());
+        assert_eq!(candidates, vec![DEFAULT_CALLER, Address::with_last_byte(1), admin]);
+        let deployment = deploy_best(&contract, &ExecOptions::default()).unwrap();
+        assert_eq!(deployment.deployer, admin);
+        assert!(deployment.gas > 53_000);
+    }
+
+    #[test]
+    fn test_dependency_keeps_constructor_storage() {
+        // sstore(0, 7); codecopy(0, 17, 1); return(0, 1) — runtime code is `stop`
+        let contract = contract("Oracle", "60076000556001601160003960016000f300");
+        let dep = Dependency::deploy("Oracle", &contract, &ExecOptions::default()).unwrap();
+        assert_eq!(dep.code, Bytes::from_static(&[0x00]));
+        assert_eq!(dep.storage, [(U256::ZERO, U256::from(7))]);
+        let opts = ExecOptions { dependencies: vec![dep.clone()], ..Default::default() };
+        let db = setup_db(&opts, DEFAULT_CALLER);
+        let account = &db.cache.accounts[&dep.address];
+        assert_eq!(account.storage[&U256::ZERO], U256::from(7));
+        assert_ne!(dep.address, DEFAULT_CALLER.create(0));
+    }
+
+    #[test]
+    fn test_setup_calls_commit_before_measuring() {
+        // sstore(0, sload(0) + 1); stop
+        let contract = contract("Counter", "60005460010160005500");
+        let setup = vec!["bump()".to_string(), "bump()".to_string()];
+        let opts = ExecOptions { inject_runtime: true, setup, ..Default::default() };
+        let prepared = prepare(&contract, &opts).unwrap();
+        assert_eq!(prepared.setup.len(), 2);
+        assert_eq!(prepared.db.cache.accounts[&prepared.addr].storage[&U256::ZERO], U256::from(2));
+
+        let reverting = CompiledContract { bytecode: vec![0x5f, 0x5f, 0xfd], ..contract };
+        assert!(prepare(&reverting, &opts).is_err());
+    }
+
+    #[test]
+    fn test_impersonated_account_may_have_code() {
+        // sstore(0, sload(0) + 1); stop
+        let contract = contract("Counter", "60005460010160005500");
+        let safe = Address::repeat_byte(0x5a);
+        let accounts = vec![state::AccountOverride::Code(safe, Bytes::from_static(&[0x00]))];
+        let opts = ExecOptions { inject_runtime: true, impersonate: Some(safe), accounts, ..Default::default() };
+        let Prepared { mut db, addr, opts, .. } = prepare(&contract, &opts).unwrap();
+        let default_block = BlockSettings::default();
+        assert!(commit_call(&mut db.clone(), safe, addr, &[], U256::ZERO, GAS_LIMIT, &default_block).is_err());
+        let (_, status) = commit_call(&mut db, safe, addr, &[], U256::ZERO, GAS_LIMIT, &opts.block).unwrap();
+        assert_eq!(status, ExecutionStatus::Success);
+    }
+
+    #[test]
+    fn test_reused_evm_matches_fresh_calls() {
+        // sstore(0, sload(0) + 1); stop — cold slot and account access each time
+        let target = Address::repeat_byte(0x33);
+        let mut db = funded_db(target, "60005460010160005500");
+        let block = BlockSettings::default();
+        let fresh = transact_call_output(&mut db, DEFAULT_CALLER, target, &[1], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        let mut evm = block.context(&mut db).build_mainnet();
+        for _ in 0..3 {
+            let reused = transact_output(&mut evm, DEFAULT_CALLER, target, &[1], U256::ZERO, GAS_LIMIT, &block);
+            assert_eq!(reused.unwrap(), fresh);
+        }
+        drop(evm);
+        assert_eq!(db.cache.accounts[&target].storage.get(&U256::ZERO).copied().unwrap_or_default(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_committed_calls_advance_caller_nonce() {
+        // sstore(0, sload(0) + 1); stop
+        let target = Address::repeat_byte(0x35);
+        let mut db = funded_db(target, "60005460010160005500");
+        let block = BlockSettings::default();
+        for _ in 0..3 {
+            let (_, status) = commit_call(&mut db, DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+            assert_eq!(status, ExecutionStatus::Success);
+        }
+        assert_eq!(account_nonce(&mut db, DEFAULT_CALLER), 4);
+        assert_eq!(db.cache.accounts[&target].storage[&U256::ZERO], U256::from(3));
+        let (_, status) = transact_call(&mut db, DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        assert_eq!(status, ExecutionStatus::Success);
+    }
+
+    #[test]
+    fn test_excluded_opcodes_adjust_runs_not_budget() {
+        // log0(0, 0); stop
+        let target = Address::repeat_byte(0x37);
+        let mut db = funded_db(target, "60006000a000");
+        let func = alloy_json_abi::Function::parse("emit()").unwrap();
+        let (full, calldata) = try_function(&mut db, target, &func, DEFAULT_CALLER, &ExecOptions::default()).unwrap();
+        let mut contract = contract("Emitter", "");
+        contract.gas_budgets.insert("emit()".into(), full.gas - 100);
+
+        let opts = ExecOptions { exclude_opcodes: vec![0xa0], runs: 3, ..Default::default() };
+        let (mut r, _) = try_function(&mut db, target, &func, DEFAULT_CALLER, &opts).unwrap();
+        annotate(&mut db, target, &contract, &func, &calldata, &mut r, &opts);
+        assert_eq!(r.excluded_opcode_gas, Some(375));
+        assert_eq!((r.gas, r.gas_median), (full.gas - 375, Some(full.gas - 375)));
+        assert_eq!(r.over_budget, Some(true));
+    }
+
+    #[test]
+    fn test_excluded_opcodes_metered_per_run() {
+        // sstore(0, add(sload(0), 1)); stop — zero-to-nonzero only on the cold call
+        let target = Address::repeat_byte(0x38);
+        let mut db = funded_db(target, "600160005401600055");
+        let func = alloy_json_abi::Function::parse("bump()").unwrap();
+        let measure = |db: &mut CacheDB<Backend>, opts: &ExecOptions| {
+            let (mut r, calldata) = try_function(db, target, &func, DEFAULT_CALLER, opts).unwrap();
+            annotate(db, target, &contract("Counter", ""), &func, &calldata, &mut r, opts);
+            r
+        };
+        let full = measure(&mut db, &ExecOptions { runs: 3, ..Default::default() });
+
+        let opts = ExecOptions { exclude_opcodes: vec![0x55], runs: 3, ..Default::default() };
+        let r = measure(&mut db, &opts);
+        // The cold call sets the slot (20000); each warm run only resets it (2900).
+        assert_eq!(r.excluded_opcode_gas, Some(20_000));
+        assert_eq!(r.gas, full.gas - 20_000);
+        assert_eq!(r.gas_median, Some(full.gas_median.unwrap() - 2_900));
+        assert_eq!((r.gas_min, r.gas_max), (None, None));
+    }
+
+    #[test]
+    fn test_wrapped_calls_reject_direct_measurements() {
+        let direct: [(&str, fn(&mut ExecOptions)); 9] = [
+            ("--storage-ops", |o| o.storage_ops = true),
+            ("--trace", |o| o.trace = true),
+            ("--memory-gas", |o| o.memory_gas = true),
+            ("--runs", |o| o.runs = 2),
+            ("--exclude-opcodes", |o| o.exclude_opcodes = vec![0xa0]),
+            ("--fingerprint", |o| o.fingerprint = true),
+            ("--storage-diff", |o| o.storage_diff = true),
+            ("--access-list-out", |o| o.access_list = true),
+            ("--include-events", |o| o.include_events = true),
+        ];
+        let both = ExecOptions { repeat: 2, runs: 2, ..Default::default() };
+        assert!(both.validate().is_err());
+        let atomic = ExecOptions { atomic_steps: vec![atomic::parse_step("deposit()").unwrap()], ..Default::default() };
+        let forwarded = ExecOptions { caller_is_contract: true, ..Default::default() };
+        for wrapped in [atomic, forwarded] {
+            assert!(wrapped.validate().is_ok());
+            for (flag, set) in direct {
+                let mut opts = wrapped.clone();
+                set(&mut opts);
+                let err = opts.validate().unwrap_err().to_string();
+                assert!(err.starts_with(flag), "{err}");
+                let mut direct_call = ExecOptions::default();
+                set(&mut direct_call);
+                assert!(direct_call.validate().is_ok(), "{flag}");
+            }
+            let opts = ExecOptions { trace: true, inject_runtime: true, ..wrapped };
+            assert!(execute_contract(&contract("Vault", "00"), &opts).is_err());
+        }
+    }
+
+    #[test]
+    fn test_fallback_goes_through_forwarder() {
+        // if iszero(extcodesize(caller())) { revert(0, 0) } stop
+        let target = Address::repeat_byte(0x38);
+        let mut db = funded_db(target, "333b600957600080fd5b00");
+        forwarder::install(&mut db);
+        let mut contract = contract("Receiver", "");
+        contract.abi.fallback = Some(alloy_json_abi::Fallback { state_mutability: StateMutability::NonPayable });
+        let input = FallbackInput { value: U256::ZERO, data: vec![1] };
+        let status = |opts: &ExecOptions| {
+            try_fallback(&mut db.clone(), target, &contract, &input, opts).unwrap().unwrap().status
+        };
+        assert_eq!(status(&ExecOptions::default()), ExecutionStatus::Revert);
+        assert_eq!(status(&ExecOptions { caller_is_contract: true, ..Default::default() }), ExecutionStatus::Success);
+    }
+
+    #[test]
+    fn test_halt_reason_reported() {
+        // jump(0) — not a JUMPDEST
+        let target = Address::repeat_byte(0x36);
+        let mut db = funded_db(target, "600056");
+        let func = alloy_json_abi::Function::parse("jump()").unwrap();
+        let (report, _) = try_function(&mut db, target, &func, DEFAULT_CALLER, &ExecOptions::default()).unwrap();
+        assert_eq!(report.status, ExecutionStatus::Halt);
+        assert_eq!(report.halt_reason.as_deref(), Some("InvalidJump"));
+        assert_eq!(report.revert_reason, None);
+    }
+
+    #[test]
+    fn test_all_strategies_recorded_winner_unchanged() {
+        // sstore(0, sload(0) + 1); stop
+        let target = Address::repeat_byte(0x34);
+        let mut db = funded_db(target, "60005460010160005500");
+        let func = alloy_json_abi::Function::parse("bump()").unwrap();
+
+        let opts = ExecOptions::default();
+        let (first, _) = try_function(&mut db, target, &func, DEFAULT_CALLER, &opts).unwrap();
+        assert!(first.strategies.is_empty());
+
+        let opts = ExecOptions { all_strategies: true, ..Default::default() };
+        let (report, _) = try_function(&mut db, target, &func, DEFAULT_CALLER, &opts).unwrap();
+        assert_eq!((report.strategy, report.gas), (first.strategy, first.gas));
+        let labels: Vec<String> = report.strategies.iter().map(|s| s.strategy.clone()).collect();
+        assert_eq!(labels, STRATEGIES.map(strategy_label));
+        assert!(report.strategies.iter().all(|s| s.status == ExecutionStatus::Success && s.gas == first.gas));
+    }
+
+    #[test]
+    fn test_injects_runtime_code() {
+        // sstore(0, 1); stop
+        let contract = contract("Verified", "600160005500");
+        let opts = ExecOptions { inject_runtime: true, ..Default::default() };
+        let Deployment { mut db, addr, gas, .. } = deploy_best(&contract, &opts).unwrap();
+        assert_eq!((addr, gas), (DEFAULT_CALLER.create(0), 0));
+        let (_, status) = commit_call(&mut db, DEFAULT_CALLER, addr, &[], U256::ZERO, GAS_LIMIT, &opts.block).unwrap();
+        assert_eq!(status, ExecutionStatus::Success);
+        assert_eq!(db.cache.accounts[&addr].storage[&U256::ZERO], U256::from(1));
+    }
+
+    #[test]
+    fn test_block_settings_reach_the_call() {
+        // sstore(0, timestamp); sstore(1, number); sstore(2, basefee); stop
+        let target = Address::repeat_byte(0x31);
+        let mut db = funded_db(target, "42600055436001554860025500");
+        let block = BlockSettings {
+            number: Some(19_000_000),
+            timestamp: Some(1_700_000_000),
+            basefee: Some(7),
+            ..Default::default()
+        };
+        let (_, status) = commit_call(&mut db, DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        assert_eq!(status, ExecutionStatus::Success);
+        let storage = &db.cache.accounts[&target].storage;
+        assert_eq!(storage[&U256::ZERO], U256::from(1_700_000_000u64));
+        assert_eq!(storage[&U256::from(1)], U256::from(19_000_000u64));
+        assert_eq!(storage[&U256::from(2)], U256::from(7));
+    }
+
+    #[test]
+    fn test_evm_version_selects_hardfork() {
+        // push0; stop — PUSH0 arrived in Shanghai
+        let target = Address::repeat_byte(0x32);
+        let db = funded_db(target, "5f00");
+        let status = |name| {
+            let block = BlockSettings { spec: Some(parse_evm_version(name).unwrap()), ..Default::default() };
+            commit_call(&mut db.clone(), DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap().1
+        };
+        assert_eq!(status("paris"), ExecutionStatus::Halt);
+        assert_eq!(status("Shanghai"), ExecutionStatus::Success);
+        assert!(parse_evm_version("frontier2").is_err());
+        assert_eq!(evm_version_name(SpecId::MERGE), "paris");
+    }
+
+    #[test]
+    fn test_value_only_for_payable() {
+        let mut opts = ExecOptions::default();
+        assert_eq!(opts.call_value(StateMutability::Payable), DEFAULT_VALUE);
+        assert_eq!(DEFAULT_VALUE, U256::from(10u64).pow(U256::from(18u64)));
+        opts.value = Some(U256::from(5));
+        assert_eq!(opts.call_value(StateMutability::Payable), U256::from(5));
+        assert_eq!(opts.call_value(StateMutability::NonPayable), U256::ZERO);
+    }
+
+    #[test]
+    fn test_function_filter() {
+        let func = alloy_json_abi::Function::parse("transfer(address,uint256)").unwrap();
+        let selects = |filter: &str| ExecOptions { functions: vec![filter.into()], ..Default::default() }.selects(&func);
+        assert!(ExecOptions::default().selects(&func));
+        assert!(selects("transfer"));
+        assert!(selects("transfer(address,uint256)"));
+        assert!(selects("0xA9059CBB"));
+        assert!(!selects("approve"));
+        assert!(!selects("transfer(address)"));
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![30, 10, 20]), Some(20));
+        assert_eq!(median(vec![40, 10, 20, 30]), Some(25));
+    }
+
+    #[test]
+    fn test_warm_runs_skip_the_cold_run() {
+        // sstore(0, add(sload(0), 1)); stop — zero-to-nonzero only on the first run
+        let target = Address::repeat_byte(0x30);
+        let mut db = CacheDB::new(Backend::Empty);
+        db.insert_account_info(DEFAULT_CALLER, AccountInfo { nonce: 1, ..Default::default() });
+        let code = hex::decode("600160005401600055").unwrap();
+        db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+        let block = BlockSettings::default();
+        let (cold, _) = transact_call(&mut db.clone(), DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        let tx = call_tx(DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, 1);
+        let gases = warm_runs(&db, tx.clone(), &block, 3, &[]);
+        assert_eq!(gases.len(), 3);
+        assert!(gases.iter().all(|&gas| gas == gases[0] && gas < cold));
+        assert_eq!(median(warm_runs(&db, tx, &block, 2, &[])), Some(gases[0]));
+        // The copy was warmed, not the original.
+        assert!(db.cache.accounts[&target].storage.is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_covers_logs_and_is_stable() {
+        let target = Address::repeat_byte(0x20);
+        let fingerprint = |code: &str| {
+            let mut db = CacheDB::new(Backend::Empty);
+            let code = hex::decode(code).unwrap();
+            db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+            db.insert_account_info(DEFAULT_CALLER, AccountInfo { nonce: 1, ..Default::default() });
+            let mut evm = revm::Context::mainnet().with_db(&mut db).build_mainnet();
+            let result = evm.transact(call_tx(DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, 1)).unwrap();
+            execution_fingerprint(21_000, ExecutionStatus::Success, &result.result)
+        };
+        // stop vs. log0(0, 0); stop
+        assert_eq!(fingerprint("00"), fingerprint("00"));
+        assert_ne!(fingerprint("00"), fingerprint("60006000a000"));
+    }
+}
@@ -0,0 +1,213 @@
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use revm::bytecode::Bytecode;
+use revm::database_interface::{DBErrorMarker, DatabaseRef, EmptyDB};
+use revm::primitives::{StorageKey, StorageValue, KECCAK_EMPTY};
+use revm::state::AccountInfo;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long one JSON-RPC request, connecting included, may take before the
+/// lookup fails.
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// State the local `CacheDB` falls back to for anything not written locally:
+/// nothing (the default), or a node's state at a pinned block (`--fork-url`).
+#[derive(Debug, Clone, Default)]
+pub enum Backend {
+    #[default]
+    Empty,
+    Fork(Fork),
+}
+
+/// A node's state at one block, read over JSON-RPC. Clones share the response
+/// cache, so repeated deployments and per-function state copies fetch each
+/// account and slot once.
+#[derive(Debug, Clone)]
+pub struct Fork {
+    rpc: Arc<RpcClient>,
+    /// Number of the block state is read at.
+    pub block: u64,
+    /// Timestamp of that block.
+    pub timestamp: u64,
+}
+
+impl Fork {
+    /// Connect to the node at the `http://` or `https://` `url` and pin
+    /// `block`, or its latest block.
+    pub fn connect(url: &str, block: Option<u64>) -> Result<Self, ForkError> {
+        let rpc = Arc::new(RpcClient::new(url)?);
+        let tag = match block {
+            Some(number) => format!("{number:#x}"),
+            None => "latest".into(),
+        };
+        let header = rpc.request("eth_getBlockByNumber", json!([tag, false]))?;
+        if header.is_null() {
+            return Err(ForkError(format!("block {tag} not found")));
+        }
+        let block = quantity(&header["number"])?.to();
+        let timestamp = quantity(&header["timestamp"])?.to();
+        Ok(Fork { rpc, block, timestamp })
+    }
+
+    fn tag(&self) -> String {
+        format!("{:#x}", self.block)
+    }
+
+    /// A fork at `block` that answers only the given `(method, params,
+    /// result)` requests, from its cache, without a node behind it.
+    #[cfg(test)]
+    pub(crate) fn seeded(block: u64, responses: &[(&str, Value, Value)]) -> Self {
+        let rpc = RpcClient::new("http://127.0.0.1:0").expect("valid URL");
+        if let Ok(mut cache) = rpc.cache.lock() {
+            for (method, params, result) in responses {
+                cache.insert(request_body(method, params.clone()), result.clone());
+            }
+        }
+        Fork { rpc: Arc::new(rpc), block, timestamp: 0 }
+    }
+}
+
+impl DatabaseRef for Backend {
+    type Error = ForkError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, ForkError> {
+        let Backend::Fork(fork) = self else {
+            return Ok(EmptyDB::new().basic_ref(address).unwrap_or_default());
+        };
+        let params = json!([address, fork.tag()]);
+        let balance = quantity(&fork.rpc.request("eth_getBalance", params.clone())?)?;
+        let nonce = quantity(&fork.rpc.request("eth_getTransactionCount", params.clone())?)?.to();
+        let code = bytes(&fork.rpc.request("eth_getCode", params)?)?;
+        if balance.is_zero() && nonce == 0 && code.is_empty() {
+            return Ok(None);
+        }
+        let (code_hash, code) = match code.is_empty() {
+            true => (KECCAK_EMPTY, Bytecode::default()),
+            false => (keccak256(&code), Bytecode::new_raw(code)),
+        };
+        Ok(Some(AccountInfo { balance, nonce, code_hash, code: Some(code), ..Default::default() }))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, ForkError> {
+        match self {
+            Backend::Empty => Ok(EmptyDB::new().code_by_hash_ref(code_hash).unwrap_or_default()),
+            // Code arrives with its account in `basic_ref`, which the cache keeps by hash.
+            Backend::Fork(_) => Err(ForkError(format!("code {code_hash} was not loaded with its account"))),
+        }
+    }
+
+    fn storage_ref(&self, address: Address, index: StorageKey) -> Result<StorageValue, ForkError> {
+        let Backend::Fork(fork) = self else {
+            return Ok(U256::ZERO);
+        };
+        quantity(&fork.rpc.request("eth_getStorageAt", json!([address, index, fork.tag()]))?)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, ForkError> {
+        let Backend::Fork(fork) = self else {
+            return Ok(EmptyDB::new().block_hash_ref(number).unwrap_or_default());
+        };
+        let header = fork.rpc.request("eth_getBlockByNumber", json!([format!("{number:#x}"), false]))?;
+        header["hash"]
+            .as_str()
+            .and_then(|h| h.parse().ok())
+            .ok_or_else(|| ForkError(format!("no hash for block {number}")))
+    }
+}
+
+/// A failed state lookup against the fork.
+#[derive(Debug)]
+pub struct ForkError(String);
+
+impl fmt::Display for ForkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fork: {}", self.0)
+    }
+}
+
+impl std::error::Error for ForkError {}
+impl DBErrorMarker for ForkError {}
+
+/// Minimal JSON-RPC client with a response cache.
+#[derive(Debug)]
+struct RpcClient {
+    url: String,
+    agent: ureq::Agent,
+    cache: Mutex<HashMap<String, Value>>,
+}
+
+impl RpcClient {
+    fn new(url: &str) -> Result<Self, ForkError> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ForkError(format!("{url}: expected an http:// or https:// endpoint")));
+        }
+        let agent = ureq::Agent::config_builder().timeout_global(Some(RPC_TIMEOUT)).build().into();
+        Ok(RpcClient { url: url.to_string(), agent, cache: Mutex::default() })
+    }
+
+    /// `result` of calling `method` with `params`, from the cache when the
+    /// same request was made before.
+    fn request(&self, method: &str, params: Value) -> Result<Value, ForkError> {
+        let body = request_body(method, params);
+        if let Some(cached) = self.cache.lock().ok().and_then(|c| c.get(&body).cloned()) {
+            return Ok(cached);
+        }
+        let response: Value = self
+            .agent
+            .post(&self.url)
+            .content_type("application/json")
+            .send(&body)
+            .and_then(|mut response| response.body_mut().read_json())
+            .map_err(|e| ForkError(format!("{method}: {e}")))?;
+        if let Some(error) = response.get("error") {
+            return Err(ForkError(format!("{method}: {error}")));
+        }
+        let result = response.get("result").cloned().unwrap_or(Value::Null);
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(body, result.clone());
+        }
+        Ok(result)
+    }
+}
+
+/// JSON-RPC request body for `method`, which is also its cache key.
+fn request_body(method: &str, params: Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string()
+}
+
+/// A hex `QUANTITY` (or 32-byte `DATA` word) from a JSON-RPC result.
+fn quantity(value: &Value) -> Result<U256, ForkError> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| ForkError(format!("expected a hex quantity, got {value}")))
+}
+
+/// Hex `DATA` from a JSON-RPC result.
+fn bytes(value: &Value) -> Result<Bytes, ForkError> {
+    value.as_str().and_then(|s| s.parse().ok()).ok_or_else(|| ForkError(format!("expected hex data, got {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_non_http_urls() {
+        assert!(RpcClient::new("http://127.0.0.1:8545").is_ok());
+        assert!(RpcClient::new("https://eth.example.com/v2/key").is_ok());
+        assert!(RpcClient::new("ws://127.0.0.1:8546").is_err());
+    }
+
+    #[test]
+    fn test_empty_backend_matches_empty_db() {
+        let backend = Backend::Empty;
+        assert_eq!(backend.basic_ref(Address::ZERO).unwrap(), None);
+        assert_eq!(backend.storage_ref(Address::ZERO, U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(backend.block_hash_ref(5).unwrap(), EmptyDB::new().block_hash_ref(5).unwrap());
+        assert_eq!(quantity(&json!("0x1a")).unwrap(), U256::from(26));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::atomic::{frame_gas, FrameRecorder};
+use crate::evm::BlockSettings;
+use crate::fork::Backend;
+use crate::types::ExecutionStatus;
+use alloy_primitives::{Address, Bytes, TxKind, U256};
+use eyre::{bail, Result};
+use revm::bytecode::Bytecode;
+use revm::context::TxEnv;
+use revm::database::CacheDB;
+use revm::state::AccountInfo;
+use revm::{InspectEvm, MainBuilder};
+
+/// Address the forwarder is installed at; `msg.sender` of every measured call
+/// under `--caller-is-contract`.
+pub const FORWARDER: Address = Address::new([
+    0xc0, 0x17, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+]);
+
+/// Runtime of the forwarder contract.
+///
+/// Called by the transaction origin with `target ++ payload`, it calls
+/// `target` with `payload` and the sent value, then returns or reverts with
+/// the callee's return data. Called by anyone else (a token's
+/// `onERC721Received` / `onERC1155Received` callback, say) it returns the
+/// first four bytes of calldata as a `bytes4`, accepting the callback.
+///
+/// ```text
+/// 00  caller origin eq push1 0x33 jumpi
+/// 06  mstore(0, and(calldataload(0), 0xffffffff << 224)) return(0, 32)
+/// 33  jumpdest len := sub(calldatasize, 20) calldatacopy(0, 20, len)
+/// 3e  call(gas, shr(96, calldataload(0)), callvalue, 0, len, 0, 0)
+/// 4e  returndatacopy(0, 0, returndatasize) push1 0x5b jumpi
+/// 57  revert(0, returndatasize)
+/// 5b  jumpdest return(0, returndatasize)
+/// ```
+const FORWARDER_CODE: &str = "3332146033576000357fffffffff000000000000000000000000000000000000000000000000000000001660005260206000f35b60143603806014600037600060008260003460003560601c5af13d600060003e605b573d6000fd5b3d6000f3";
+
+/// Install the forwarder so calls can be routed through it.
+pub fn install(db: &mut CacheDB<Backend>) {
+    let code = hex::decode(FORWARDER_CODE).expect("forwarder bytecode is valid hex");
+    db.insert_account_info(FORWARDER, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+}
+
+/// Call `target` with `calldata` through the installed forwarder, so
+/// `msg.sender` is a contract, and return the forwarded call's
+/// `(gas, status)`. Gas excludes the forwarder's own overhead and is priced
+/// as if `calldata` had been sent directly.
+pub fn transact_via_forwarder(
+    db: &mut CacheDB<Backend>,
+    caller: Address,
+    target: Address,
+    calldata: &[u8],
+    value: U256,
+    gas_limit: u64,
+    block: &BlockSettings,
+) -> Result<(u64, ExecutionStatus)> {
+    let mut data = target.to_vec();
+    data.extend_from_slice(calldata);
+    let nonce = crate::evm::account_nonce(db, caller);
+    let mut evm = block.context(&mut *db).build_mainnet_with_inspector(FrameRecorder::new(FORWARDER));
+    let tx = block.priced(TxEnv {
+        caller,
+        gas_limit,
+        kind: TxKind::Call(FORWARDER),
+        data: Bytes::from(data),
+        value,
+        nonce,
+        ..Default::default()
+    });
+    evm.inspect_one_tx(tx).map_err(|e| eyre::eyre!("call error: {e:?}"))?;
+    let Some(&(status, spent, refunded)) = evm.inspector.outcomes.first() else {
+        bail!("forwarder did not reach the target");
+    };
+    Ok((frame_gas(calldata, spent, refunded), status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::GAS_LIMIT;
+
+    #[test]
+    fn test_target_sees_contract_caller() {
+        let caller = Address::repeat_byte(0x10);
+        let target = Address::repeat_byte(0x20);
+        let mut db = CacheDB::new(Backend::Empty);
+        db.insert_account_info(caller, AccountInfo { nonce: 1, ..Default::default() });
+        // if iszero(extcodesize(caller())) { revert(0, 0) } stop
+        let code = hex::decode("333b600957600080fd5b00").unwrap();
+        db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+        install(&mut db);
+
+        let block = BlockSettings::default();
+        let (_, direct) = crate::evm::transact_call(&mut db, caller, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        assert_eq!(direct, ExecutionStatus::Revert);
+        let (gas, forwarded) = transact_via_forwarder(&mut db, caller, target, &[], U256::ZERO, GAS_LIMIT, &block).unwrap();
+        assert_eq!(forwarded, ExecutionStatus::Success);
+        assert!(gas > 21_000);
+    }
+}
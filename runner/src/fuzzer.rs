@@ -35,10 +35,14 @@ fn setup_db() -> CacheDB<EmptyDB> {
 }
 
 /// Fuzz all public/external functions of each compiled contract.
-pub fn fuzz_contracts(contracts: &[CompiledContract], rounds: u32) -> Vec<FuzzReport> {
+///
+/// `max_len` bounds every random dynamic array to at most that many elements
+/// and `bytes` values to `32 * max_len` bytes, so large inputs probe worst-case
+/// gas without mostly producing out-of-gas noise.
+pub fn fuzz_contracts(contracts: &[CompiledContract], rounds: u32, max_len: usize) -> Vec<FuzzReport> {
     contracts
         .iter()
-        .filter_map(|c| match fuzz_single_contract(c, rounds) {
+        .filter_map(|c| match fuzz_single_contract(c, rounds, max_len) {
             Ok(report) => Some(report),
             Err(e) => {
                 eprintln!("Warning: fuzzing {} failed — {e}", c.name);
@@ -48,7 +52,7 @@ pub fn fuzz_contracts(contracts: &[CompiledContract], rounds: u32) -> Vec<FuzzRe
         .collect()
 }
 
-fn fuzz_single_contract(contract: &CompiledContract, rounds: u32) -> Result<FuzzReport> {
+fn fuzz_single_contract(contract: &CompiledContract, rounds: u32, max_len: usize) -> Result<FuzzReport> {
     let caller_addr = caller();
     let (base_db, addr) = deploy_best(contract, caller_addr)?;
 
@@ -56,7 +60,7 @@ fn fuzz_single_contract(contract: &CompiledContract, rounds: u32) -> Result<Fuzz
 
     for func_list in contract.abi.functions.values() {
         for func in func_list {
-            let result = fuzz_function(&base_db, addr, func, caller_addr, rounds);
+            let result = fuzz_function(&base_db, addr, func, caller_addr, rounds, max_len);
             results.push(result);
         }
     }
@@ -128,6 +132,7 @@ fn fuzz_function(
     func: &Function,
     caller_addr: Address,
     rounds: u32,
+    max_len: usize,
 ) -> FuzzResult {
     let selector = format!("0x{}", hex::encode(func.selector().as_slice()));
     let mut successes: u32 = 0;
@@ -140,30 +145,26 @@ fn fuzz_function(
 
     for _ in 0..rounds {
         // Generate random calldata
-        let calldata = match generate_random_calldata(func, caller_addr, &mut rng) {
+        let calldata = match generate_random_calldata(func, caller_addr, max_len, &mut rng) {
             Ok(cd) => cd,
             Err(_) => continue,
         };
 
         // Clone the DB so each fuzz round starts from the same state
         let mut db = base_db.clone();
-        let result = call_function(&mut db, addr, &calldata);
-
-        match result {
-            Ok((gas, status)) => {
-                match status {
-                    ExecutionStatus::Success => successes += 1,
-                    ExecutionStatus::Revert => reverts += 1,
-                    ExecutionStatus::Halt => halts += 1,
-                }
-                min_gas = min_gas.min(gas);
-                max_gas = max_gas.max(gas);
-                total_gas = total_gas.saturating_add(gas);
-            }
-            Err(_) => {
-                halts += 1;
-            }
+        // An input too large to even start (its calldata alone costs more
+        // than the gas limit) is the worst case of the same kind as an
+        // out-of-gas halt, so it counts as a halt at the full limit.
+        let (gas, status) = call_function(&mut db, addr, &calldata)
+            .unwrap_or((GAS_LIMIT, ExecutionStatus::Halt));
+        match status {
+            ExecutionStatus::Success => successes += 1,
+            ExecutionStatus::Revert => reverts += 1,
+            ExecutionStatus::Halt => halts += 1,
         }
+        min_gas = min_gas.min(gas);
+        max_gas = max_gas.max(gas);
+        total_gas = total_gas.saturating_add(gas);
     }
 
     let completed = successes + reverts + halts;
@@ -218,9 +219,10 @@ fn call_function(
 }
 
 /// Generate random ABI-encoded calldata for a function.
-fn generate_random_calldata(
+pub(crate) fn generate_random_calldata(
     func: &Function,
     caller_addr: Address,
+    max_len: usize,
     rng: &mut impl Rng,
 ) -> Result<Vec<u8>> {
     let selector = func.selector();
@@ -234,7 +236,7 @@ fn generate_random_calldata(
         .iter()
         .map(|p| {
             let ty = param_to_dyn_sol_type(p)?;
-            Ok(random_value(&ty, caller_addr, rng))
+            Ok(random_value(&ty, caller_addr, max_len, rng))
         })
         .collect::<Result<Vec<_>>>()?;
 
@@ -278,7 +280,7 @@ fn param_to_dyn_sol_type(param: &Param) -> Result<DynSolType> {
 }
 
 /// Generate a random value for a given Solidity type.
-fn random_value(ty: &DynSolType, caller_addr: Address, rng: &mut impl Rng) -> DynSolValue {
+fn random_value(ty: &DynSolType, caller_addr: Address, max_len: usize, rng: &mut impl Rng) -> DynSolValue {
     match ty {
         DynSolType::Bool => DynSolValue::Bool(rng.random::<bool>()),
         DynSolType::Uint(bits) => {
@@ -300,7 +302,7 @@ fn random_value(ty: &DynSolType, caller_addr: Address, rng: &mut impl Rng) -> Dy
             }
         }
         DynSolType::Bytes => {
-            let len = rng.random_range(0..64usize);
+            let len = rng.random_range(0..=32 * max_len);
             let mut bytes = vec![0u8; len];
             rng.fill(&mut bytes[..]);
             DynSolValue::Bytes(bytes)
@@ -324,22 +326,22 @@ fn random_value(ty: &DynSolType, caller_addr: Address, rng: &mut impl Rng) -> Dy
             DynSolValue::FixedBytes(alloy_primitives::B256::from(bytes), *n)
         }
         DynSolType::Array(inner) => {
-            let len = rng.random_range(0..4usize);
+            let len = rng.random_range(0..=max_len);
             let items = (0..len)
-                .map(|_| random_value(inner, caller_addr, rng))
+                .map(|_| random_value(inner, caller_addr, max_len, rng))
                 .collect();
             DynSolValue::Array(items)
         }
         DynSolType::FixedArray(inner, n) => {
             let items = (0..*n)
-                .map(|_| random_value(inner, caller_addr, rng))
+                .map(|_| random_value(inner, caller_addr, max_len, rng))
                 .collect();
             DynSolValue::FixedArray(items)
         }
         DynSolType::Tuple(types) => {
             let items = types
                 .iter()
-                .map(|t| random_value(t, caller_addr, rng))
+                .map(|t| random_value(t, caller_addr, max_len, rng))
                 .collect();
             DynSolValue::Tuple(items)
         }
@@ -401,3 +403,26 @@ fn random_int(_bits: usize, rng: &mut impl Rng) -> I256 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_lengths_bounded_by_max_len() {
+        let ty = DynSolType::Tuple(vec![
+            DynSolType::Array(Box::new(DynSolType::Array(Box::new(DynSolType::Uint(8))))),
+            DynSolType::Bytes,
+        ]);
+        let mut rng = rand::rng();
+        for _ in 0..200 {
+            let DynSolValue::Tuple(values) = random_value(&ty, Address::ZERO, 2, &mut rng) else {
+                panic!("expected tuple");
+            };
+            let DynSolValue::Array(outer) = &values[0] else { panic!("expected array") };
+            assert!(outer.len() <= 2);
+            assert!(outer.iter().all(|inner| matches!(inner, DynSolValue::Array(v) if v.len() <= 2)));
+            assert!(matches!(&values[1], DynSolValue::Bytes(b) if b.len() <= 64));
+        }
+    }
+}
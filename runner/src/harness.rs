@@ -0,0 +1,364 @@
+use crate::compile::{self, CompileOptions};
+use crate::evm::{self, ExecOptions};
+use crate::types::FunctionReport;
+use eyre::Result;
+use std::path::Path;
+
+/// Prefix of the forwarding functions the generated harness declares.
+const EXPOSED_PREFIX: &str = "exposed_";
+
+/// An `internal` function found in the source, with enough of its header to
+/// declare an external forwarder for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternalFunction {
+    pub contract: String,
+    pub name: String,
+    /// `(type, data location)` per parameter, e.g. `("uint256[]", "memory")`.
+    pub params: Vec<(String, String)>,
+    /// Raw text inside `returns (...)`, empty when nothing is returned.
+    pub returns: String,
+}
+
+impl InternalFunction {
+    /// Storage-pointer arguments or results cannot cross an external call.
+    pub fn is_exposable(&self) -> bool {
+        self.params.iter().all(|(_, loc)| loc != "storage")
+            && !self.returns.split_whitespace().any(|t| t == "storage")
+    }
+}
+
+/// Every `internal` function with a body declared directly in a `contract`
+/// (libraries and interfaces cannot be inherited from, private functions are
+/// invisible to a derived contract).
+pub fn internal_functions(source: &str) -> Vec<InternalFunction> {
+    let src = strip_comments(source);
+    let mut found = Vec::new();
+    for (contract, body) in contract_bodies(&src) {
+        let mut rest = body;
+        while let Some(at) = find_keyword(rest, "function") {
+            rest = &rest[at + "function".len()..];
+            let Some(open) = rest.find('(') else { break };
+            let name = rest[..open].trim().to_string();
+            let Some(close) = matching(rest, open, '(', ')') else { break };
+            let params = split_top_level(&rest[open + 1..close]);
+            let header_end = rest[close..].find(['{', ';']).map_or(rest.len(), |i| close + i);
+            let header = &rest[close + 1..header_end];
+            let has_body = rest[header_end..].starts_with('{');
+            rest = &rest[header_end..];
+            if !has_body || !header.split_whitespace().any(|t| t == "internal") {
+                continue;
+            }
+            found.push(InternalFunction {
+                contract: contract.clone(),
+                name,
+                params: params.iter().map(|p| parse_param(p)).collect(),
+                returns: returns_list(header),
+            });
+        }
+    }
+    found
+}
+
+/// Compile a harness deriving from `contract` that exposes `functions`, run the
+/// forwarders, and report each under the internal function's own name. Gas
+/// includes the forwarder's dispatch and argument decoding on top of the body.
+pub fn measure_internal(
+    sol_path: &Path,
+    source: &str,
+    contract: &str,
+    functions: &[&InternalFunction],
+    compile_opts: &CompileOptions,
+    exec_opts: &ExecOptions,
+) -> Result<Vec<FunctionReport>> {
+    let file_name = sol_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| eyre::eyre!("invalid source file name: {}", sol_path.display()))?;
+    let harness_name = format!("{contract}Harness");
+    let harness_source = generate_harness(source, file_name, contract, functions);
+    let compiled = compile::compile_harness(
+        sol_path,
+        &format!("{harness_name}.sol"),
+        &harness_source,
+        compile_opts,
+    )?;
+    let mut harness = compiled
+        .into_iter()
+        .find(|c| c.name == harness_name)
+        .ok_or_else(|| eyre::eyre!("harness {harness_name} produced no bytecode"))?;
+    harness.abi.functions.retain(|name, _| name.starts_with(EXPOSED_PREFIX));
+
+    let mut reports = evm::execute_contract(&harness, exec_opts)?.functions;
+    for r in &mut reports {
+        r.name = r.name.trim_start_matches(EXPOSED_PREFIX).to_string();
+        r.signature = r.signature.trim_start_matches(EXPOSED_PREFIX).to_string();
+        r.internal = true;
+    }
+    Ok(reports)
+}
+
+/// Solidity source for `contract <C>Harness is <C>` with one external
+/// `exposed_<name>` forwarder per function, forwarding constructor arguments
+/// to the base when it takes any.
+fn generate_harness(
+    source: &str,
+    file_name: &str,
+    contract: &str,
+    functions: &[&InternalFunction],
+) -> String {
+    let src = strip_comments(source);
+    let pragma = src
+        .find("pragma solidity")
+        .and_then(|at| src[at..].find(';').map(|end| src[at..=at + end].to_string()))
+        .unwrap_or_else(|| "pragma solidity ^0.8.0;".into());
+
+    let mut out = format!(
+        "// SPDX-License-Identifier: UNLICENSED\n{pragma}\n\nimport \"./{file_name}\";\n\n\
+         contract {contract}Harness is {contract} {{\n"
+    );
+    if let Some((params, payable)) = constructor_params(&src, contract) {
+        let args = arg_names(params.len());
+        out.push_str(&format!(
+            "    constructor({}){} {contract}({}) {{}}\n\n",
+            declare_params(&params),
+            if payable { " payable" } else { "" },
+            args.join(", ")
+        ));
+    }
+    for f in functions {
+        let args = arg_names(f.params.len()).join(", ");
+        let (returns, ret) = if f.returns.is_empty() {
+            (String::new(), "")
+        } else {
+            (format!(" returns ({})", f.returns), "return ")
+        };
+        out.push_str(&format!(
+            "    function {EXPOSED_PREFIX}{}({}) external{returns} {{\n        {ret}{}({args});\n    }}\n\n",
+            f.name,
+            declare_params(&f.params),
+            f.name,
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn arg_names(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("arg{i}")).collect()
+}
+
+fn declare_params(params: &[(String, String)]) -> String {
+    params
+        .iter()
+        .zip(arg_names(params.len()))
+        .map(|((ty, loc), name)| {
+            if loc.is_empty() { format!("{ty} {name}") } else { format!("{ty} {loc} {name}") }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parameters of `contract`'s own constructor and whether it is payable;
+/// `None` when it declares no constructor or one without parameters.
+fn constructor_params(src: &str, contract: &str) -> Option<(Vec<(String, String)>, bool)> {
+    let (_, body) = contract_bodies(src).into_iter().find(|(name, _)| name == contract)?;
+    let at = find_keyword(body, "constructor")?;
+    let rest = &body[at..];
+    let open = rest.find('(')?;
+    let close = matching(rest, open, '(', ')')?;
+    let params = split_top_level(&rest[open + 1..close]);
+    if params.is_empty() {
+        return None;
+    }
+    let header = &rest[close + 1..rest[close..].find('{').map_or(rest.len(), |i| close + i)];
+    let payable = header.split_whitespace().any(|t| t == "payable");
+    Some((params.iter().map(|p| parse_param(p)).collect(), payable))
+}
+
+/// `(name, body)` for each `contract` declaration (abstract ones included).
+fn contract_bodies(src: &str) -> Vec<(String, &str)> {
+    let mut bodies = Vec::new();
+    let mut offset = 0;
+    while let Some(at) = find_keyword(&src[offset..], "contract") {
+        let start = offset + at + "contract".len();
+        let Some(open) = src[start..].find('{').map(|i| start + i) else { break };
+        let name = src[start..open].split_whitespace().next().unwrap_or_default().to_string();
+        let Some(close) = matching(src, open, '{', '}') else { break };
+        bodies.push((name, &src[open + 1..close]));
+        offset = close;
+    }
+    bodies
+}
+
+/// Split `(type, location)` out of a parameter declaration, dropping its name.
+fn parse_param(decl: &str) -> (String, String) {
+    let mut tokens: Vec<&str> = decl.split_whitespace().collect();
+    let is_name = |t: &str| {
+        !matches!(t, "payable" | "memory" | "storage" | "calldata")
+            && t.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    };
+    if tokens.len() > 1 && is_name(tokens[tokens.len() - 1]) {
+        tokens.pop();
+    }
+    let loc = match tokens.last() {
+        Some(&l @ ("memory" | "storage" | "calldata")) if tokens.len() > 1 => {
+            tokens.pop();
+            l.to_string()
+        }
+        _ => String::new(),
+    };
+    (tokens.join(" "), loc)
+}
+
+fn returns_list(header: &str) -> String {
+    let Some(at) = find_keyword(header, "returns") else { return String::new() };
+    let rest = &header[at..];
+    match rest.find('(').and_then(|open| Some((open, matching(rest, open, '(', ')')?))) {
+        Some((open, close)) => rest[open + 1..close].trim().to_string(),
+        None => String::new(),
+    }
+}
+
+/// Comma-separated items of a parameter list, ignoring commas nested in parens.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(list[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = list[start..].trim();
+    if !last.is_empty() {
+        items.push(last.to_string());
+    }
+    items
+}
+
+/// Index of the bracket closing the one at `open`.
+fn matching(s: &str, open: usize, open_c: char, close_c: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s[open..].char_indices() {
+        if c == open_c {
+            depth += 1;
+        } else if c == close_c {
+            depth -= 1;
+            if depth == 0 {
+                return Some(open + i);
+            }
+        }
+    }
+    None
+}
+
+/// First occurrence of `word` as a whole identifier.
+fn find_keyword(s: &str, word: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let mut from = 0;
+    while let Some(i) = s[from..].find(word) {
+        let at = from + i;
+        let before = s[..at].chars().next_back();
+        let after = s[at + word.len()..].chars().next();
+        if !before.is_some_and(is_ident) && !after.is_some_and(is_ident) {
+            return Some(at);
+        }
+        from = at + word.len();
+    }
+    None
+}
+
+/// Blank out comments and string contents (keeping byte offsets) so braces and
+/// keywords inside them are not mistaken for code.
+fn strip_comments(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        let blank_until = |out: &mut Vec<u8>, from: usize, to: usize| {
+            for b in &mut out[from..to] {
+                if *b != b'\n' {
+                    *b = b' ';
+                }
+            }
+        };
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(bytes.len(), |e| i + e);
+                blank_until(&mut out, i, end);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..].find("*/").map_or(bytes.len(), |e| i + 2 + e + 2);
+                blank_until(&mut out, i, end);
+                i = end;
+            }
+            q @ (b'"' | b'\'') => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != q {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                blank_until(&mut out, i + 1, j.min(bytes.len()));
+                i = j + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    String::from_utf8(out).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VAULT: &str = r#"
+        pragma solidity ^0.8.20;
+        library Math { function add(uint a, uint b) internal pure returns (uint) { return a + b; } }
+        contract Vault {
+            struct Position { uint256 size; }
+            mapping(address => Position) positions;
+            constructor(address owner_, uint256[] memory caps) payable {}
+            // function ignored(uint x) internal {}
+            function _accrue(uint256 amount, address payable to) internal returns (uint256 fee, bool ok) { return (amount, true); }
+            function _load(address who) internal view returns (Position storage) { return positions[who]; }
+            function _hook() internal virtual;
+            function _secret() private {}
+            function deposit(bytes calldata data) external { string memory s = "function _fake() internal {}"; }
+        }
+    "#;
+
+    #[test]
+    fn test_internal_functions_parsed() {
+        let fns = internal_functions(VAULT);
+        let names: Vec<&str> = fns.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["_accrue", "_load"]);
+        assert_eq!(
+            fns[0].params,
+            [("uint256".into(), String::new()), ("address payable".into(), String::new())]
+        );
+        assert_eq!(fns[0].returns, "uint256 fee, bool ok");
+        assert!(fns[0].is_exposable());
+        assert!(!fns[1].is_exposable());
+    }
+
+    #[test]
+    fn test_harness_source() {
+        let fns = internal_functions(VAULT);
+        let out = generate_harness(VAULT, "Vault.sol", "Vault", &[&fns[0]]);
+        assert!(out.contains("pragma solidity ^0.8.20;"));
+        assert!(out.contains("import \"./Vault.sol\";"));
+        assert!(out.contains("contract VaultHarness is Vault {"));
+        assert!(out.contains(
+            "constructor(address arg0, uint256[] memory arg1) payable Vault(arg0, arg1) {}"
+        ));
+        assert!(out.contains(
+            "function exposed__accrue(uint256 arg0, address payable arg1) external \
+             returns (uint256 fee, bool ok) {\n        return _accrue(arg0, arg1);"
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+use eyre::{bail, Result};
+use revm::bytecode::opcode::{
+    OpCode, CALL, CALLCODE, CREATE, CREATE2, DELEGATECALL, SLOAD, SSTORE, STATICCALL,
+};
+use revm::interpreter::gas::memory_gas;
+use revm::interpreter::interpreter_types::{Jumps, MemoryTr};
+use revm::interpreter::Interpreter;
+use revm::Inspector;
+use std::collections::BTreeMap;
+
+/// Counts SLOAD/SSTORE executed across every frame of a call, so a gas change
+/// can be attributed to storage access (e.g. after repacking struct fields).
+#[derive(Debug, Default)]
+pub struct StorageOpCounter {
+    pub sloads: u64,
+    pub sstores: u64,
+}
+
+impl<CTX> Inspector<CTX> for StorageOpCounter {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        match interp.bytecode.opcode() {
+            SLOAD => self.sloads += 1,
+            SSTORE => self.sstores += 1,
+            _ => {}
+        }
+    }
+}
+
+/// How many times each opcode ran across every frame of a call (`--trace`).
+#[derive(Debug, Default)]
+pub struct OpcodeCounter {
+    pub counts: BTreeMap<u8, u64>,
+}
+
+impl OpcodeCounter {
+    /// Counts keyed by mnemonic, e.g. `SLOAD`; opcodes revm doesn't define
+    /// appear as hex.
+    pub fn by_name(&self) -> BTreeMap<String, u64> {
+        self.counts
+            .iter()
+            .map(|(&op, &count)| {
+                let name = OpCode::new(op).map_or_else(|| format!("{op:#04x}"), |o| o.as_str().to_string());
+                (name, count)
+            })
+            .collect()
+    }
+}
+
+impl<CTX> Inspector<CTX> for OpcodeCounter {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        *self.counts.entry(interp.bytecode.opcode()).or_default() += 1;
+    }
+}
+
+/// Sums the gas charged for memory growth across every frame of a call: the
+/// difference in `3·words + words²/512` each time an opcode enlarges memory.
+#[derive(Debug, Default)]
+pub struct MemoryExpansionMeter {
+    pub gas: u64,
+    size_before_step: usize,
+}
+
+impl<CTX> Inspector<CTX> for MemoryExpansionMeter {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        self.size_before_step = interp.memory.size();
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let size = interp.memory.size();
+        if size > self.size_before_step {
+            let words = |bytes: usize| bytes.div_ceil(32);
+            self.gas += memory_gas(words(size), 3, 512)
+                - memory_gas(words(self.size_before_step), 3, 512);
+        }
+    }
+}
+
+/// Gas charged by each opcode across every frame of a call, keyed by opcode.
+///
+/// An opcode's charge is the gas remaining in its frame before it runs minus
+/// after, so memory expansion it triggers is included. Call and create
+/// opcodes are not meaningful here, since their charge includes the gas
+/// forwarded to the child frame.
+#[derive(Debug, Default)]
+pub struct OpcodeGasMeter {
+    pub gas: BTreeMap<u8, u64>,
+    step: Option<(u8, u64)>,
+}
+
+impl OpcodeGasMeter {
+    /// Total gas charged by the given opcodes.
+    pub fn gas_of(&self, opcodes: &[u8]) -> u64 {
+        opcodes.iter().filter_map(|op| self.gas.get(op)).sum()
+    }
+}
+
+impl<CTX> Inspector<CTX> for OpcodeGasMeter {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        self.step = Some((interp.bytecode.opcode(), interp.gas.remaining()));
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        if let Some((opcode, remaining)) = self.step.take() {
+            *self.gas.entry(opcode).or_default() += remaining.saturating_sub(interp.gas.remaining());
+        }
+    }
+}
+
+/// Parse an opcode mnemonic such as `LOG2` (case-insensitive) for
+/// `--exclude-opcodes`, rejecting call/create opcodes whose gas cannot be
+/// separated from the child frame's.
+pub fn parse_opcode(name: &str) -> Result<u8> {
+    let name = name.trim();
+    let Some(op) = (0..=u8::MAX).filter_map(OpCode::new).find(|op| op.as_str().eq_ignore_ascii_case(name))
+    else {
+        bail!("unknown opcode '{name}'");
+    };
+    if matches!(op.get(), CALL | CALLCODE | DELEGATECALL | STATICCALL | CREATE | CREATE2) {
+        bail!("cannot exclude {}: its gas includes the gas forwarded to the callee", op.as_str());
+    }
+    Ok(op.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::{BlockSettings, DEFAULT_CALLER, GAS_LIMIT, call_tx};
+    use crate::fork::Backend;
+    use alloy_primitives::{Address, U256};
+    use revm::bytecode::Bytecode;
+    use revm::context::TxEnv;
+    use revm::database::CacheDB;
+    use revm::state::AccountInfo;
+
+    /// A state with `code` deployed, and a transaction calling it.
+    fn db_with_code(code: &str) -> (CacheDB<Backend>, TxEnv) {
+        let mut db = CacheDB::new(Backend::Empty);
+        db.insert_account_info(DEFAULT_CALLER, AccountInfo { nonce: 1, ..Default::default() });
+        let target = Address::repeat_byte(0x42);
+        let code = hex::decode(code).unwrap();
+        db.insert_account_info(target, AccountInfo::default().with_code(Bytecode::new_raw(code.into())));
+        (db, call_tx(DEFAULT_CALLER, target, &[], U256::ZERO, GAS_LIMIT, 1))
+    }
+
+    #[test]
+    fn test_counts_storage_ops() {
+        // sstore(0, 1); sload(0); sload(0); stop
+        let (mut db, tx) = db_with_code("600160005560005460005400");
+        let counter = crate::evm::inspect_call(&mut db, tx, &BlockSettings::default(), StorageOpCounter::default()).unwrap();
+        assert_eq!((counter.sloads, counter.sstores), (2, 1));
+    }
+
+    #[test]
+    fn test_counts_opcodes() {
+        // sstore(0, 1); sload(0); sload(0); stop
+        let (mut db, tx) = db_with_code("600160005560005460005400");
+        let counter = crate::evm::inspect_call(&mut db, tx, &BlockSettings::default(), OpcodeCounter::default()).unwrap();
+        let counts = counter.by_name();
+        assert_eq!(counts["PUSH1"], 4);
+        assert_eq!(counts["SLOAD"], 2);
+        assert_eq!(counts["SSTORE"], 1);
+        assert_eq!(counts["STOP"], 1);
+    }
+
+    #[test]
+    fn test_memory_expansion_gas() {
+        // mstore(992, 1); mstore(0, 1); stop — grows to 32 words once
+        let (mut db, tx) = db_with_code("60016103e052600160005200");
+        let meter = crate::evm::inspect_call(&mut db, tx, &BlockSettings::default(), MemoryExpansionMeter::default()).unwrap();
+        assert_eq!(meter.gas, 3 * 32 + 32 * 32 / 512);
+    }
+
+    #[test]
+    fn test_opcode_gas_histogram() {
+        // log0(0, 0); stop
+        let (mut db, tx) = db_with_code("60006000a000");
+        let meter = crate::evm::inspect_call(&mut db, tx, &BlockSettings::default(), OpcodeGasMeter::default()).unwrap();
+        let log0 = parse_opcode("log0").unwrap();
+        assert_eq!(meter.gas_of(&[log0]), 375);
+        assert_eq!(meter.gas_of(&[parse_opcode("PUSH1").unwrap()]), 6);
+        assert!(parse_opcode("CALL").is_err());
+        assert!(parse_opcode("NOPE").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Compile Solidity, deploy it into an in-memory EVM and measure the gas of
+//! every public function.
+//!
+//! [`scan`] runs the whole pipeline with default settings. For control over
+//! compilation or execution, call [`compile::compile_with_options`] and
+//! [`report_contract`] (or [`evm::execute_contract`] directly) yourself:
+//!
+//! ```no_run
+//! let reports = sigscan::scan(std::path::Path::new("src/Token.sol"))?;
+//! for report in &reports {
+//!     for f in &report.functions {
+//!         println!("{}.{}: {} gas", report.contract, f.signature, f.gas);
+//!     }
+//! }
+//! # Ok::<(), eyre::Report>(())
+//! ```
+//!
+//! The `compile`, `evm`, `calldata` and `types` modules are the supported
+//! API. The other public modules back the `sigscan-runner` binary and may
+//! change between releases.
+
+pub mod calldata;
+pub mod compile;
+pub mod evm;
+pub mod types;
+
+#[doc(hidden)]
+pub mod abi_decode;
+mod adaptive;
+#[doc(hidden)]
+pub mod args;
+#[doc(hidden)]
+pub mod atomic;
+#[doc(hidden)]
+pub mod call_graph;
+#[doc(hidden)]
+pub mod cfg;
+#[doc(hidden)]
+pub mod diff;
+mod events;
+#[doc(hidden)]
+pub mod fork;
+mod forwarder;
+#[doc(hidden)]
+pub mod fuzzer;
+#[doc(hidden)]
+pub mod harness;
+#[doc(hidden)]
+pub mod inspector;
+#[doc(hidden)]
+pub mod lockfile;
+mod merkle;
+#[doc(hidden)]
+pub mod output;
+mod revert;
+#[doc(hidden)]
+pub mod sequence;
+#[doc(hidden)]
+pub mod signature_db;
+#[doc(hidden)]
+pub mod signer;
+#[doc(hidden)]
+pub mod state;
+#[doc(hidden)]
+pub mod storage_layout;
+#[doc(hidden)]
+pub mod token;
+mod userop;
+
+pub use compile::{compile, compile_with_options, CompileOptions};
+pub use evm::{execute_contract, ExecOptions};
+pub use types::{CompiledContract, ContractReport, ExecutionStatus, FunctionReport};
+
+use eyre::Result;
+use std::path::Path;
+
+/// Compile `sol_path` and measure every contract in it with default options.
+pub fn scan(sol_path: &Path) -> Result<Vec<ContractReport>> {
+    scan_with_options(sol_path, &CompileOptions::default(), &ExecOptions::default())
+}
+
+/// [`scan`] with explicit compile and execution options.
+pub fn scan_with_options(
+    sol_path: &Path,
+    compile_opts: &CompileOptions,
+    opts: &ExecOptions,
+) -> Result<Vec<ContractReport>> {
+    let contracts = compile_with_options(sol_path, compile_opts)?;
+    Ok(contracts.iter().map(|contract| report_contract(contract, opts).0).collect())
+}
+
+/// Measure one compiled contract and assemble its report, returning the
+/// runtime code its functions ran against alongside. A contract that cannot
+/// be deployed gets a report without functions and a warning on stderr.
+pub fn report_contract(contract: &CompiledContract, opts: &ExecOptions) -> (ContractReport, Option<Vec<u8>>) {
+    let mut report = ContractReport::new(contract.name.clone(), Vec::new());
+    let exec = match execute_contract(contract, opts) {
+        Ok(exec) => Some(exec),
+        Err(e) => {
+            eprintln!("Warning: {} - {e}", contract.name);
+            report.deploy_error = Some(e.to_string());
+            None
+        }
+    };
+    let mut runtime_bytecode = None;
+    if let Some(exec) = exec {
+        report.functions = exec.functions;
+        report.unsupported_types = exec.unsupported;
+        report.initializer = exec.initializer;
+        report.deploy_gas = Some(exec.deploy_gas).filter(|_| !opts.inject_runtime);
+        report.constructor_strategy = exec.constructor_strategy;
+        report.setup = exec.setup;
+        report.deployer = Some(exec.deployer).filter(|&d| d != opts.caller());
+        let size = exec.runtime_bytecode.len();
+        report.runtime_size = Some(size);
+        report.metadata_size = Some(call_graph::metadata_len(&exec.runtime_bytecode));
+        report.exceeds_size_limit = size > evm::MAX_RUNTIME_SIZE;
+        if report.exceeds_size_limit {
+            eprintln!(
+                "Warning: {} runtime is {size} bytes, over the {}-byte EIP-170 limit",
+                contract.name,
+                evm::MAX_RUNTIME_SIZE
+            );
+        }
+        runtime_bytecode = Some(exec.runtime_bytecode);
+    }
+    if !opts.sequence.is_empty() {
+        match evm::execute_sequence(contract, opts) {
+            Ok(steps) => report.sequence = steps.unwrap_or_default(),
+            Err(e) => eprintln!("Warning: {} sequence - {e}", contract.name),
+        }
+    }
+    report.compiler_version = contract.compiler.solc.clone();
+    report.evm_version = Some(evm::evm_version_name(opts.block.spec.unwrap_or_default()));
+    report.impersonated = opts.impersonate;
+    report.caller_is_contract = opts.caller_is_contract;
+    if opts.token_mode {
+        report.token_standards = token::detect(&contract.abi);
+    }
+    report.prefilled_accounts = (opts.prefill_accounts > 0).then_some(opts.prefill_accounts);
+    (report, runtime_bytecode)
+}
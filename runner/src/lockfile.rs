@@ -0,0 +1,158 @@
+use crate::compile::CompileOptions;
+use crate::types::CompiledContract;
+use eyre::{bail, Result, WrapErr};
+use serde::Deserialize;
+use std::path::Path;
+
+/// revm version the runner is built against, pinned exactly in Cargo.toml.
+/// `test_revm_version_matches_cargo_toml` fails when a revm bump leaves it behind.
+const REVM_VERSION: &str = "34.0.0";
+
+/// Versions and settings that determine a run's gas numbers, as recorded in
+/// `sigscan.lock`:
+///
+/// ```toml
+/// sigscan = "0.1.0"
+/// revm = "34.0.0"
+/// solc = "0.8.26+commit.8a97fa7a"
+/// optimizer = true
+/// optimizer_runs = 200
+/// evm_version = "cancun"
+/// via_ir = false
+/// metadata = true
+/// ```
+///
+/// Compiler fields are absent when the artifacts carry no metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Lock {
+    pub sigscan: String,
+    pub revm: String,
+    pub solc: Option<String>,
+    pub optimizer: Option<bool>,
+    pub optimizer_runs: Option<u64>,
+    pub evm_version: Option<String>,
+    pub via_ir: Option<bool>,
+    pub metadata: bool,
+}
+
+impl Lock {
+    /// The lock for a run over `contracts`, compiled with `opts`. Settings come
+    /// from the first contract; one build uses one compiler configuration.
+    pub fn current(contracts: &[CompiledContract], opts: &CompileOptions) -> Self {
+        let compiler = contracts.first().map(|c| c.compiler.clone()).unwrap_or_default();
+        Lock {
+            sigscan: env!("CARGO_PKG_VERSION").into(),
+            revm: REVM_VERSION.into(),
+            solc: compiler.solc,
+            optimizer: compiler.optimizer,
+            optimizer_runs: compiler.optimizer_runs,
+            evm_version: compiler.evm_version,
+            via_ir: compiler.via_ir,
+            metadata: !opts.no_metadata,
+        }
+    }
+
+    /// Render as TOML, one `key = value` line per field.
+    pub fn render(&self) -> String {
+        let mut out = String::from("# Written by sigscan-runner --write-lock; checked by --locked.\n");
+        out.push_str(&format!("sigscan = {:?}\nrevm = {:?}\n", self.sigscan, self.revm));
+        if let Some(solc) = &self.solc {
+            out.push_str(&format!("solc = {solc:?}\n"));
+        }
+        if let Some(optimizer) = self.optimizer {
+            out.push_str(&format!("optimizer = {optimizer}\n"));
+        }
+        if let Some(runs) = self.optimizer_runs {
+            out.push_str(&format!("optimizer_runs = {runs}\n"));
+        }
+        if let Some(evm_version) = &self.evm_version {
+            out.push_str(&format!("evm_version = {evm_version:?}\n"));
+        }
+        if let Some(via_ir) = self.via_ir {
+            out.push_str(&format!("via_ir = {via_ir}\n"));
+        }
+        out.push_str(&format!("metadata = {}\n", self.metadata));
+        out
+    }
+
+    /// Fail listing every field where `current` differs from this lock.
+    pub fn check(&self, current: &Lock) -> Result<()> {
+        let fields = [
+            ("sigscan", Some(self.sigscan.clone()), Some(current.sigscan.clone())),
+            ("revm", Some(self.revm.clone()), Some(current.revm.clone())),
+            ("solc", self.solc.clone(), current.solc.clone()),
+            ("optimizer", self.optimizer.map(|v| v.to_string()), current.optimizer.map(|v| v.to_string())),
+            (
+                "optimizer_runs",
+                self.optimizer_runs.map(|v| v.to_string()),
+                current.optimizer_runs.map(|v| v.to_string()),
+            ),
+            ("evm_version", self.evm_version.clone(), current.evm_version.clone()),
+            ("via_ir", self.via_ir.map(|v| v.to_string()), current.via_ir.map(|v| v.to_string())),
+            ("metadata", Some(self.metadata.to_string()), Some(current.metadata.to_string())),
+        ];
+        let mismatches: Vec<String> = fields
+            .into_iter()
+            .filter(|(_, locked, now)| locked != now)
+            .map(|(name, locked, now)| {
+                let show = |v: Option<String>| v.unwrap_or_else(|| "unset".into());
+                format!("{name}: locked {}, now {}", show(locked), show(now))
+            })
+            .collect();
+        if !mismatches.is_empty() {
+            bail!("environment does not match the lockfile:\n  {}", mismatches.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+pub fn load_lock(path: &Path) -> Result<Lock> {
+    let raw = std::fs::read_to_string(path).wrap_err_with(|| format!("reading lockfile {}", path.display()))?;
+    toml::from_str(&raw).wrap_err_with(|| format!("parsing lockfile {}", path.display()))
+}
+
+pub fn write_lock(path: &Path, lock: &Lock) -> Result<()> {
+    std::fs::write(path, lock.render()).wrap_err_with(|| format!("writing lockfile {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock() -> Lock {
+        Lock {
+            sigscan: "0.1.0".into(),
+            revm: REVM_VERSION.into(),
+            solc: Some("0.8.26+commit.8a97fa7a".into()),
+            optimizer: Some(true),
+            optimizer_runs: Some(200),
+            evm_version: Some("cancun".into()),
+            via_ir: None,
+            metadata: true,
+        }
+    }
+
+    #[test]
+    fn test_render_round_trips() {
+        let parsed: Lock = toml::from_str(&lock().render()).unwrap();
+        assert_eq!(parsed, lock());
+    }
+
+    #[test]
+    fn test_check_lists_mismatches() {
+        assert!(lock().check(&lock()).is_ok());
+        let drifted = Lock { solc: Some("0.8.28+commit.7893614a".into()), via_ir: Some(true), ..lock() };
+        let err = lock().check(&drifted).unwrap_err().to_string();
+        assert!(err.contains("solc: locked 0.8.26+commit.8a97fa7a, now 0.8.28+commit.7893614a"));
+        assert!(err.contains("via_ir: locked unset, now true"));
+        assert!(!err.contains("optimizer"));
+    }
+
+    #[test]
+    fn test_revm_version_matches_cargo_toml() {
+        let manifest = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")).unwrap();
+        let manifest = manifest.parse::<toml::Table>().unwrap();
+        let pinned = manifest["dependencies"]["revm"]["version"].as_str();
+        assert_eq!(pinned, Some(format!("={REVM_VERSION}").as_str()));
+    }
+}
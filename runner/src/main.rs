@@ -1,16 +1,20 @@
-mod abi_decode;
-mod call_graph;
-mod calldata;
-mod cfg;
-mod compile;
-mod evm;
-mod fuzzer;
-mod signature_db;
-mod storage_layout;
-mod types;
-
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+mod serve;
+
+use sigscan::{
+    abi_decode, args, atomic, call_graph, cfg, compile, diff, evm, fork, fuzzer, harness, inspector, lockfile, output,
+    sequence, signature_db, signer, state, storage_layout, types,
+};
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use clap::{Args, Parser, Subcommand};
+use eyre::WrapErr;
+use output::OutputFormat;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use types::ContractReport;
 
 #[derive(Parser)]
@@ -22,17 +26,633 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Legacy: path to .sol file (equivalent to `gas <file>`)
+    /// Legacy: .sol files or directories (equivalent to `gas <files>`)
     #[arg(global = false)]
-    sol_file: Option<PathBuf>,
+    sol_files: Vec<PathBuf>,
+
+    /// Legacy: options for the implied `gas` command
+    #[command(flatten)]
+    gas: GasArgs,
+}
+
+/// Options for the `gas` command (also accepted in legacy mode, and as the
+/// `options` object of a `serve` request, keyed by field name).
+#[derive(Args, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GasArgs {
+    /// Output format for the gas report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Include structured input/output parameters for each function
+    #[arg(long)]
+    include_signature_detail: bool,
+
+    /// Add a rollup of total gas, success/revert/halt counts and each
+    /// contract's most and least expensive function, under `summary` in JSON
+    /// output and as a section in Markdown
+    #[arg(long)]
+    summary: bool,
+
+    /// Exit non-zero if any function exceeds its `@custom:gas-budget` natspec tag
+    #[arg(long)]
+    fail_on_budget: bool,
+
+    /// Exit non-zero if any function halts (out of gas, invalid opcode);
+    /// reverts do not count
+    #[arg(long)]
+    fail_on_halt: bool,
+
+    /// Exit non-zero if any function reverts or halts, or a contract fails to
+    /// deploy; the report is still printed first
+    #[arg(long)]
+    fail_on_revert: bool,
+
+    /// Seed N funded dummy accounts into the state before deployment
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    prefill_accounts: u64,
+
+    /// Try all strategies and report the most expensive success instead of the first
+    #[arg(long)]
+    exhaustive: bool,
+
+    /// Call every calldata strategy and list each one's gas, status and
+    /// revert reason under the function's `strategies`
+    #[arg(long)]
+    all_strategies: bool,
+
+    /// Wei to send in a call through receive/fallback (decimal or 0x-hex)
+    #[arg(long, value_name = "WEI")]
+    fallback_value: Option<U256>,
+
+    /// Calldata to send through fallback, hex-encoded (combined with --fallback-value)
+    #[arg(long, value_name = "HEX")]
+    fallback_data: Option<Bytes>,
+
+    /// Add init and runtime bytecode (hex) to each contract in the report
+    #[arg(long)]
+    include_bytecode: bool,
+
+    /// JSON of address -> slot -> value written into the state after deployment
+    #[arg(long, value_name = "FILE")]
+    storage_snapshot: Option<PathBuf>,
+
+    /// Write a slot of the deployed contract before any call, as `SLOT:VALUE`
+    /// (32-byte hex), e.g. to clear a paused flag; repeatable
+    #[arg(long = "storage", value_name = "SLOT:VALUE", value_parser = parse_storage_override)]
+    #[serde(deserialize_with = "deserialize_storage_overrides")]
+    storage: Vec<(U256, U256)>,
+
+    /// Flag functions whose gas grows per element of their array/bytes/string inputs
+    #[arg(long)]
+    check_unbounded: bool,
+
+    /// Mark functions whose execution gas is the same for every input tried
+    /// (all calldata strategies, plus short and long dynamic inputs)
+    #[arg(long)]
+    constant_gas: bool,
+
+    /// Measure prebuilt forge or hardhat artifacts instead of compiling: one
+    /// artifact JSON, or a directory such as `out/` or `artifacts/`. Forge is
+    /// not run; takes the place of the .sol inputs
+    #[arg(long, value_name = "PATH")]
+    #[serde(skip)]
+    artifact: Option<PathBuf>,
+
+    /// Measure raw bytecode instead of compiling: 0x-prefixed hex, or a file
+    /// holding it. Creation code unless --deployed; needs --abi
+    #[arg(long, value_name = "HEX|FILE", requires = "abi", conflicts_with = "artifact")]
+    #[serde(skip)]
+    bytecode: Option<String>,
+
+    /// ABI JSON file for --bytecode; the contract is named after it
+    #[arg(long, value_name = "FILE", requires = "bytecode")]
+    #[serde(skip)]
+    abi: Option<PathBuf>,
+
+    /// --bytecode is runtime code: place it at the deployment address
+    /// instead of running it as a constructor
+    #[arg(long, requires = "bytecode")]
+    #[serde(skip)]
+    deployed: bool,
+
+    /// Link an external library at an address, as `NAME=ADDR` where NAME is
+    /// the library or `<source path>:<Library>`; unlinked libraries get the
+    /// zero address. Repeatable
+    #[arg(long = "link", value_name = "NAME=ADDR", value_parser = parse_link)]
+    #[serde(deserialize_with = "deserialize_links")]
+    links: Vec<(String, Address)>,
+
+    /// Compile every contract in this .sol file, deploy it into the state at
+    /// an address derived from its name and link it there; repeatable, later
+    /// files linking against earlier ones
+    #[arg(long = "deploy-lib", value_name = "FILE")]
+    #[serde(skip)]
+    deploy_libs: Vec<PathBuf>,
+
+    /// Deploy contract NAME from FILE before the measured contracts, as
+    /// `NAME=FILE`; its address goes to the `address` constructor parameter
+    /// named like it, else to the next unclaimed one. Repeatable, in order
+    #[arg(long = "dep", value_name = "NAME=FILE", value_parser = parse_dependency)]
+    #[serde(skip)]
+    deps: Vec<(String, PathBuf)>,
+
+    /// `--dep` contracts once deployed.
+    #[arg(skip)]
+    #[serde(skip)]
+    dependencies: Vec<evm::Dependency>,
+
+    /// Name contracts `FILE:NAME`, as runs over several files report them.
+    #[arg(skip)]
+    #[serde(skip)]
+    qualify_contracts: bool,
+
+    /// foundry.toml whose compiler settings standalone files are built with
+    /// (default: the nearest ancestor foundry.toml, if any)
+    #[arg(long, value_name = "FILE")]
+    foundry_config: Option<PathBuf>,
+
+    /// Compile without the CBOR metadata hash appended to bytecode
+    #[arg(long)]
+    no_metadata: bool,
+
+    /// Rebuild with `forge build --force` even when the source and settings
+    /// match a cached build
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Solc version to build with, e.g. 0.8.26 (default: the foundry.toml
+    /// setting, else the newest installed one the pragma allows)
+    #[arg(long, value_name = "X.Y.Z")]
+    solc_version: Option<String>,
+
+    /// Build with the optimizer enabled. Without this or --optimizer-runs the
+    /// project's or inherited foundry.toml decides; standalone files with no
+    /// config compile unoptimized, as before
+    #[arg(long)]
+    optimize: bool,
+
+    /// Optimizer runs, overriding foundry.toml
+    #[arg(long, value_name = "N")]
+    optimizer_runs: Option<u64>,
+
+    /// Compile through the Yul IR pipeline (via-ir)
+    #[arg(long)]
+    via_ir: bool,
+
+    /// Hardfork to compile for and run under: paris, shanghai, cancun, prague,
+    /// ... (default: forge's EVM version for compilation, revm's latest
+    /// mainnet fork for execution)
+    #[arg(long, value_name = "NAME")]
+    evm_version: Option<String>,
+
+    /// Report each contract's runtime size with and without its metadata trailer
+    #[arg(long)]
+    code_size: bool,
+
+    /// Measure an internal function through a generated harness contract that
+    /// exposes it externally; repeatable
+    #[arg(long, value_name = "NAME")]
+    expose_internal: Vec<String>,
+
+    /// Count SLOAD/SSTORE executed by each function's reported call
+    #[arg(long)]
+    storage_ops: bool,
+
+    /// Count how many times each opcode runs in each function's reported call
+    /// (`opcode_counts`)
+    #[arg(long)]
+    trace: bool,
+
+    /// Report how much of each function's gas goes to memory expansion
+    #[arg(long)]
+    memory_gas: bool,
+
+    /// Earlier JSON report, or a `.sol` file measured with the same options, to
+    /// compare against; prints gas and storage-op deltas per function instead
+    /// of the report, and a table of changed functions on stderr (implies
+    /// --storage-ops)
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Exit with an error when a function's gas grew by more than this
+    /// percentage over --baseline
+    #[arg(long, value_name = "PCT", requires = "baseline")]
+    threshold: Option<f64>,
+
+    /// Record the solc, optimizer, EVM and tool versions of this run in the
+    /// lockfile
+    #[arg(long, conflicts_with = "locked")]
+    write_lock: bool,
+
+    /// Fail before measuring if the compiler settings or tool versions differ
+    /// from the lockfile
+    #[arg(long)]
+    locked: bool,
+
+    /// Lockfile read by --locked and written by --write-lock (default: sigscan.lock)
+    #[arg(long, value_name = "FILE")]
+    lockfile: Option<PathBuf>,
+
+    /// Measure only this contract of the file, skipping the others; repeatable
+    #[arg(long = "contract", value_name = "NAME")]
+    contracts: Vec<String>,
+
+    /// Measure only functions matching this name, signature
+    /// (`transfer(address,uint256)`) or selector (`0xa9059cbb`); repeatable
+    #[arg(long = "function", value_name = "NAME|SIG|SELECTOR")]
+    functions: Vec<String>,
+
+    /// Contracts in the file to compare side by side; prints each shared
+    /// signature's gas per contract instead of the report
+    #[arg(long, value_name = "A,B", value_delimiter = ',', num_args = 1.., conflicts_with = "baseline")]
+    compare_contracts: Vec<String>,
+
+    /// Deploy and call from a fixed dev key and pass valid ECDSA signatures to
+    /// functions taking a message hash plus `bytes` or `v, r, s` signature
+    #[arg(long, conflicts_with_all = ["caller", "impersonate"])]
+    signatures: bool,
+
+    /// Private key (hex) of the account that deploys and calls; functions
+    /// taking signatures get ones made with it, as with `--signatures`
+    #[arg(long, value_name = "HEX", conflicts_with_all = ["caller", "impersonate"])]
+    caller_key: Option<B256>,
+
+    /// Address that deploys the contract and makes every call, e.g. the owner
+    /// an access-controlled contract expects [default: 0x1000000000000000000000000000000000000001]
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_address)]
+    caller: Option<Address>,
+
+    /// Make the measured calls from this existing account without its key
+    /// (e.g. an admin from --genesis); the contract is still deployed by the caller
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_address)]
+    impersonate: Option<Address>,
+
+    /// Call each function N times and report whether gas was identical across
+    /// runs, with min/max when it was not
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    repeat: Option<u32>,
+
+    /// Execute each function N times in a row after one uncounted warm-up
+    /// run, each run against the state the previous one left, and report the
+    /// median gas with min/max. Not available with --atomic-step or
+    /// --caller-is-contract
+    #[arg(long, value_name = "N", conflicts_with = "repeat", value_parser = clap::value_parser!(u32).range(1..))]
+    runs: Option<u32>,
+
+    /// Add an `execution_fingerprint` per function: a hash of its gas, status,
+    /// return data and logs, for golden tests that assert behavior is unchanged
+    #[arg(long)]
+    fingerprint: bool,
+
+    /// Report the contract storage slots each function changes, with their
+    /// values before and after the call
+    #[arg(long)]
+    storage_diff: bool,
+
+    /// File of `SIG[=ARGS][@WEI]` lines called in order against one shared,
+    /// committed state, each step measured (omitted arguments use smart defaults)
+    #[arg(long, value_name = "FILE")]
+    sequence: Option<PathBuf>,
+
+    /// Call committed after deployment and before measuring, as
+    /// `SIG[=ARGS][@WEI]` like a --sequence line, e.g. `mint(uint256)=(100)`;
+    /// repeatable, run in order. A call that does not succeed fails the contract
+    #[arg(long = "setup", value_name = "SIG[=ARGS][@WEI]")]
+    setup: Vec<String>,
+
+    /// Subtract the gas charged by these opcodes (e.g. LOG0,LOG1) from each
+    /// reported total, `--repeat`/`--runs` min, max and median included, to
+    /// compare logic that differs only in event emission. Gas budgets are
+    /// still checked against the full cost. Not available with --atomic-step
+    /// or --caller-is-contract
+    #[arg(long, value_name = "OPCODES", value_delimiter = ',')]
+    exclude_opcodes: Vec<String>,
+
+    /// Selector database (JSON map or `<selector> <signature>` lines) used to
+    /// name and measure dispatched functions missing from the ABI
+    #[arg(long, value_name = "FILE")]
+    selectors: Option<PathBuf>,
+
+    /// JSON file of explicit arguments: function signatures mapped to value
+    /// lists (or `{"args": [...], "gas_limit": N}` to also cap the
+    /// transaction's gas), plus a `constructors` object keyed by contract name
+    /// and an optional `merkle` tree whose root is seeded into storage;
+    /// functions not listed keep the generated strategy values
+    #[arg(long, visible_alias = "inputs", value_name = "FILE")]
+    args: Option<PathBuf>,
+
+    /// Call functions through a minimal forwarder contract so `msg.sender` is
+    /// a contract rather than an EOA (deployment still comes from the caller)
+    #[arg(long)]
+    caller_is_contract: bool,
+
+    /// Geth genesis JSON whose `alloc` accounts (balances, code, storage) are
+    /// loaded before deployment, e.g. an L2's predeploys
+    #[arg(long, value_name = "FILE")]
+    genesis: Option<PathBuf>,
+
+    /// Set an account's balance before deployment, as `ADDR:WEI` (decimal or
+    /// 0x-hex), e.g. to fund the contract's future address; repeatable
+    #[arg(long = "account", value_name = "ADDR:WEI", value_parser = parse_balance_override)]
+    #[serde(deserialize_with = "deserialize_balance_overrides")]
+    accounts: Vec<state::AccountOverride>,
+
+    /// Place runtime bytecode at an address before deployment, as `ADDR:HEX`,
+    /// so external and delegate call targets exist; repeatable
+    #[arg(long = "code", value_name = "ADDR:HEX", value_parser = parse_code_override)]
+    #[serde(deserialize_with = "deserialize_code_overrides")]
+    codes: Vec<state::AccountOverride>,
+
+    /// Stop measuring further contracts once this many seconds have passed;
+    /// contracts not reached are listed with `"truncated": true`
+    #[arg(long, value_name = "SECONDS")]
+    max_runtime: Option<u64>,
+
+    /// Recognize ERC-20/721/1155 contracts, call their standard functions with
+    /// holder-style arguments and compare gas to typical ranges
+    #[arg(long)]
+    token_mode: bool,
+
+    /// Call the contract's `initialize` function once after deployment, as
+    /// upgradeable contracts require, and report its gas separately
+    #[arg(long)]
+    initialize: bool,
+
+    /// When a function reverts with a recognized reason (missing allowance or
+    /// balance, paused, expired deadline), correct the state or arguments and
+    /// retry; corrections apply to that function only
+    #[arg(long)]
+    adaptive: bool,
+
+    /// Write each function's EIP-2930 access list (everything its call
+    /// touches that is worth pre-warming) to this JSON file, and report the
+    /// gas the list saves
+    #[arg(long, value_name = "FILE")]
+    access_list_out: Option<PathBuf>,
+
+    /// Also write successful calls to this file in forge's `.gas-snapshot`
+    /// format (`Contract:sig() (gas: N)`), whatever --format prints
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+
+    /// Transaction gas limit for deployment and every call, e.g. `60_000_000`
+    /// to profile loops beyond the block limit or lower to catch runaway
+    /// functions [default: 30_000_000]
+    #[arg(long, value_name = "GAS", value_parser = parse_gas_limit)]
+    gas_limit: Option<u64>,
+
+    /// Constructor arguments as Solidity literals, in order (e.g.
+    /// `--constructor-args 0xabc.. 1000 "[1,2]"`), for contracts whose
+    /// constructor takes parameters; guessed arguments are the fallback
+    #[arg(long, value_name = "ARG", num_args = 1..)]
+    constructor_args: Vec<String>,
+
+    /// Also call each function with N random inputs and report the most
+    /// expensive successful input with its calldata
+    #[arg(long, value_name = "N")]
+    fuzz: Option<u32>,
+
+    /// Seed for the `--fuzz` inputs, making a run reproducible
+    #[arg(long, value_name = "U64", requires = "fuzz", default_value_t = 0)]
+    seed: u64,
+
+    /// Most elements in a `--fuzz` dynamic array (`bytes` get up to 32x
+    /// this many bytes) [default: 3]
+    #[arg(long, value_name = "N", requires = "fuzz")]
+    fuzz_max_len: Option<usize>,
+
+    /// `block.number` for the deployment and every call [default: 0]
+    #[arg(long, value_name = "N")]
+    block_number: Option<u64>,
+
+    /// `block.timestamp` for the deployment and every call, in Unix seconds
+    /// [default: 1]
+    #[arg(long, value_name = "SECONDS")]
+    block_timestamp: Option<u64>,
+
+    /// `block.basefee` in wei; transactions are priced at it [default: 0]
+    #[arg(long, value_name = "WEI")]
+    basefee: Option<u64>,
+
+    /// JSON-RPC endpoint (http:// or https://) whose state backs the run:
+    /// accounts and storage not set up locally are read from it, e.g. to call
+    /// into deployed tokens or oracles. Block number and timestamp default to
+    /// the fork block's
+    #[arg(long, value_name = "URL")]
+    fork_url: Option<String>,
+
+    /// Block to fork state at [default: latest]
+    #[arg(long, value_name = "N", requires = "fork_url")]
+    fork_block: Option<u64>,
+
+    /// `--fork-url` once connected, so every part of a run reads state at
+    /// the same block through one response cache.
+    #[arg(skip)]
+    #[serde(skip)]
+    fork: Option<fork::Fork>,
+
+    /// Wei sent to payable functions and payable constructors (decimal or
+    /// 0x-hex) [default: 1 ether]
+    #[arg(long, value_name = "WEI")]
+    value: Option<U256>,
+
+    /// Decode the events each function's call emits against the ABI and list
+    /// them in its report; unrecognized logs keep their raw topics and data
+    #[arg(long)]
+    include_events: bool,
+
+    /// Setup call to run in the same transaction before each measured call,
+    /// as `SIG[=ARGS][@WEI]` (e.g. `deposit(uint256)=(100)@1000`); repeatable
+    #[arg(long = "atomic-step", value_name = "SPEC", value_parser = parse_atomic_step)]
+    atomic_steps: Vec<atomic::AtomicStep>,
+}
+
+impl GasArgs {
+    /// Whether contract reports are printed as NDJSON lines as they finish,
+    /// rather than rendered together once the run is done. Diff output
+    /// (`--baseline`, `--compare-contracts`) replaces the reports.
+    fn streams(&self) -> bool {
+        self.format == OutputFormat::Ndjson && self.baseline.is_none() && self.compare_contracts.is_empty()
+    }
+
+    fn compile_options(&self) -> compile::CompileOptions {
+        compile::CompileOptions {
+            foundry_config: self.foundry_config.clone(),
+            no_metadata: self.no_metadata,
+            no_cache: self.no_cache,
+            solc_version: self.solc_version.clone(),
+            optimize: self.optimize,
+            optimizer_runs: self.optimizer_runs,
+            via_ir: self.via_ir,
+            evm_version: self.evm_version.clone(),
+            links: self.links.clone(),
+        }
+    }
+
+    fn exec_options(&self) -> eyre::Result<evm::ExecOptions> {
+        let signer_key = self.caller_key.or(self.signatures.then_some(signer::DEFAULT_KEY));
+        let fork = match &self.fork {
+            Some(fork) => Some(fork.clone()),
+            None => self
+                .fork_url
+                .as_deref()
+                .map(|url| fork::Fork::connect(url, self.fork_block).wrap_err_with(|| format!("forking {url}")))
+                .transpose()?,
+        };
+        for spec in &self.setup {
+            sequence::step_signature(spec).wrap_err("invalid --setup")?;
+        }
+        let opts = evm::ExecOptions {
+            caller: signer_key.map(signer::signer_address).transpose().wrap_err("invalid --caller-key")?.or(self.caller),
+            signer_key,
+            include_signature_detail: self.include_signature_detail,
+            prefill_accounts: self.prefill_accounts,
+            exhaustive: self.exhaustive,
+            all_strategies: self.all_strategies,
+            fallback: (self.fallback_value.is_some() || self.fallback_data.is_some()).then(|| {
+                evm::FallbackInput {
+                    value: self.fallback_value.unwrap_or_default(),
+                    data: self.fallback_data.clone().unwrap_or_default().to_vec(),
+                }
+            }),
+            atomic_steps: self.atomic_steps.clone(),
+            check_unbounded: self.check_unbounded,
+            constant_gas: self.constant_gas,
+            storage_ops: self.storage_ops || self.baseline.is_some(),
+            trace: self.trace,
+            memory_gas: self.memory_gas,
+            repeat: self.repeat.unwrap_or(1),
+            runs: self.runs.unwrap_or(1),
+            fingerprint: self.fingerprint,
+            storage_diff: self.storage_diff,
+            sequence: self.sequence.as_deref().map(sequence::load_sequence).transpose()?.unwrap_or_default(),
+            setup: self.setup.clone(),
+            exclude_opcodes: self
+                .exclude_opcodes
+                .iter()
+                .map(|name| inspector::parse_opcode(name))
+                .collect::<eyre::Result<_>>()?,
+            selector_names: self.selectors.as_deref().map(signature_db::load_selector_file).transpose()?,
+            args: self.args.as_deref().map(args::load_args_file).transpose()?,
+            caller_is_contract: self.caller_is_contract,
+            genesis: self.genesis.as_deref().map(state::load_genesis).transpose()?,
+            accounts: self.accounts.iter().chain(&self.codes).cloned().collect(),
+            token_mode: self.token_mode,
+            initialize: self.initialize,
+            adaptive: self.adaptive,
+            access_list: self.access_list_out.is_some(),
+            include_events: self.include_events,
+            default_gas_limit: self.gas_limit,
+            block: evm::BlockSettings {
+                number: self.block_number.or(fork.as_ref().map(|f| f.block)),
+                timestamp: self.block_timestamp.or(fork.as_ref().map(|f| f.timestamp)),
+                basefee: self.basefee,
+                spec: self.evm_version.as_deref().map(evm::parse_evm_version).transpose()?,
+                ..Default::default()
+            },
+            value: self.value,
+            constructor_args: self.constructor_args.clone(),
+            fuzz_iterations: self.fuzz.unwrap_or(0),
+            fuzz_seed: self.seed,
+            fuzz_max_len: self.fuzz_max_len,
+            functions: self.functions.clone(),
+            inject_runtime: self.deployed,
+            dependencies: self.dependencies.clone(),
+            backend: fork.map(fork::Backend::Fork).unwrap_or_default(),
+            impersonate: self.impersonate,
+            storage: self.storage.clone(),
+            storage_snapshot: self
+                .storage_snapshot
+                .as_deref()
+                .map(state::load_storage_snapshot)
+                .transpose()?,
+        };
+        // Fail before compiling anything rather than once per contract.
+        opts.validate()?;
+        Ok(opts)
+    }
+}
+
+fn parse_atomic_step(spec: &str) -> Result<atomic::AtomicStep, String> {
+    atomic::parse_step(spec).map_err(|e| e.to_string())
+}
+
+fn parse_balance_override(spec: &str) -> Result<state::AccountOverride, String> {
+    state::parse_balance_override(spec).map_err(|e| e.to_string())
+}
+
+fn parse_code_override(spec: &str) -> Result<state::AccountOverride, String> {
+    state::parse_code_override(spec).map_err(|e| e.to_string())
+}
+
+fn parse_storage_override(spec: &str) -> Result<(U256, U256), String> {
+    state::parse_storage_override(spec).map_err(|e| e.to_string())
+}
+
+fn parse_dependency(spec: &str) -> Result<(String, PathBuf), String> {
+    match spec.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok((name.to_string(), PathBuf::from(path))),
+        _ => Err(format!("expected NAME=FILE, got {spec:?}")),
+    }
+}
+
+fn parse_link(spec: &str) -> Result<(String, Address), String> {
+    let Some((name, address)) = spec.rsplit_once('=') else {
+        return Err(format!("expected NAME=ADDR, got {spec:?}"));
+    };
+    Ok((name.to_string(), parse_address(address)?))
+}
+
+/// `serve` options give repeatable `ADDR:...`/`SLOT:...` flags as lists of
+/// the same strings.
+fn deserialize_specs<'de, D, T>(d: D, parse: fn(&str) -> Result<T, String>) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(d)?.iter().map(|s| parse(s).map_err(serde::de::Error::custom)).collect()
+}
+
+fn deserialize_balance_overrides<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> Result<Vec<state::AccountOverride>, D::Error> {
+    deserialize_specs(d, parse_balance_override)
+}
+
+fn deserialize_code_overrides<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> Result<Vec<state::AccountOverride>, D::Error> {
+    deserialize_specs(d, parse_code_override)
+}
+
+fn deserialize_storage_overrides<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<(U256, U256)>, D::Error> {
+    deserialize_specs(d, parse_storage_override)
+}
+
+fn deserialize_links<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<(String, Address)>, D::Error> {
+    deserialize_specs(d, parse_link)
+}
+
+/// A 20-byte hex address, `0x` prefix optional.
+fn parse_address(s: &str) -> Result<Address, String> {
+    Address::from_str(s).map_err(|e| format!("invalid address {s:?} (expected 40 hex digits): {e}"))
+}
+
+/// A gas amount, with optional `_` digit separators (`30_000_000`).
+fn parse_gas_limit(s: &str) -> Result<u64, String> {
+    s.replace('_', "").parse().map_err(|e| format!("invalid gas limit {s:?}: {e}"))
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Compile and execute for gas estimation (default)
     Gas {
-        /// Path to the .sol file
-        sol_file: PathBuf,
+        /// .sol files, or directories scanned for them; contracts from several
+        /// files are reported as `<file>:<Contract>`
+        #[arg(required_unless_present_any = ["artifact", "bytecode"])]
+        sol_files: Vec<PathBuf>,
+        #[command(flatten)]
+        args: Box<GasArgs>,
     },
     /// Analyze storage layout from bytecode
     StorageLayout {
@@ -62,6 +682,9 @@ enum Commands {
         /// 4-byte selector (e.g. "0xa9059cbb") or function name for reverse lookup
         selector: String,
     },
+    /// Answer newline-delimited JSON gas requests on stdin, keeping compiled
+    /// artifacts cached between requests
+    Serve,
     /// Fuzz test contract functions with random inputs
     Fuzz {
         /// Path to the .sol file
@@ -69,6 +692,10 @@ enum Commands {
         /// Number of fuzz rounds per function
         #[arg(short, long, default_value = "50")]
         rounds: u32,
+        /// Most elements in a random dynamic array (`bytes` get up to 32x
+        /// this many bytes)
+        #[arg(long, value_name = "N", default_value = "3")]
+        fuzz_max_len: usize,
     },
 }
 
@@ -82,10 +709,13 @@ fn main() -> eyre::Result<()> {
         Some(cmd) => run_command(cmd),
         None => {
             // Legacy mode: treat positional arg as `gas <file>`
-            match cli.sol_file {
-                Some(path) => run_command(Commands::Gas { sol_file: path }),
-                None => {
-                    eprintln!("Usage: sigscan-runner <sol_file>");
+            match cli.sol_files.is_empty() && cli.gas.artifact.is_none() && cli.gas.bytecode.is_none() {
+                false => run_command(Commands::Gas {
+                    sol_files: cli.sol_files,
+                    args: Box::new(cli.gas),
+                }),
+                true => {
+                    eprintln!("Usage: sigscan-runner <sol_file>...");
                     eprintln!("       sigscan-runner <command> [args]");
                     eprintln!();
                     eprintln!("Run `sigscan-runner --help` for more information.");
@@ -98,13 +728,14 @@ fn main() -> eyre::Result<()> {
 
 fn run_command(cmd: Commands) -> eyre::Result<()> {
     match cmd {
-        Commands::Gas { sol_file } => cmd_gas(&sol_file),
+        Commands::Gas { sol_files, args } => cmd_gas(&sol_files, &args),
         Commands::StorageLayout { sol_file } => cmd_storage_layout(&sol_file),
         Commands::Cfg { sol_file } => cmd_cfg(&sol_file),
         Commands::CallGraph { sol_file } => cmd_call_graph(&sol_file),
         Commands::AbiDecode { data, types } => cmd_abi_decode(&data, types.as_deref()),
         Commands::SigDb { selector } => cmd_sig_db(&selector),
-        Commands::Fuzz { sol_file, rounds } => cmd_fuzz(&sol_file, rounds),
+        Commands::Fuzz { sol_file, rounds, fuzz_max_len } => cmd_fuzz(&sol_file, rounds, fuzz_max_len),
+        Commands::Serve => serve::run(),
     }
 }
 
@@ -112,36 +743,413 @@ fn run_command(cmd: Commands) -> eyre::Result<()> {
 // Subcommand implementations
 // ---------------------------------------------------------------------------
 
-/// Gas estimation (original behavior).
-fn cmd_gas(sol_file: &PathBuf) -> eyre::Result<()> {
-    validate_sol_file(sol_file)?;
+/// `args` with every contract of the `--deploy-lib` files deployed: its
+/// runtime code placed at [`library_address`] (as `--code`) and linked there
+/// (as `--link`). Each file is linked against the ones before it.
+fn deploy_libraries(args: &GasArgs) -> eyre::Result<GasArgs> {
+    let mut linked = GasArgs { deploy_libs: Vec::new(), deployed: false, ..args.clone() };
+    for path in &args.deploy_libs {
+        validate_sol_file(path)?;
+        let libraries = compile::compile_with_options(path, &linked.compile_options())?;
+        let opts = linked.exec_options()?;
+        for library in &libraries {
+            let runtime = evm::deploy_runtime(library, &opts)
+                .wrap_err_with(|| format!("deploying library {} from {}", library.name, path.display()))?;
+            let address = library_address(&library.name);
+            linked.codes.push(state::AccountOverride::Code(address, runtime.into()));
+            linked.links.push((library.name.clone(), address));
+        }
+    }
+    linked.deployed = args.deployed;
+    Ok(linked)
+}
 
-    let contracts = compile::compile(sol_file)?;
-    if contracts.is_empty() {
-        println!("[]");
-        return Ok(());
-    }
-
-    let mut reports = Vec::new();
-    for contract in &contracts {
-        let functions = match evm::execute_contract(contract) {
-            Ok(funcs) => funcs,
-            Err(e) => {
-                eprintln!("Warning: {} - {e}", contract.name);
-                Vec::new()
+/// `args` with every `--dep` contract compiled and deployed, in order, so
+/// each can take the ones before it as constructor arguments.
+fn deploy_dependencies(args: &GasArgs) -> eyre::Result<GasArgs> {
+    let mut with_deps = GasArgs { deployed: false, ..args.clone() };
+    for (name, path) in &args.deps {
+        validate_sol_file(path)?;
+        let contracts = compile::compile_with_options(path, &with_deps.compile_options())?;
+        let Some(contract) = contracts.iter().find(|c| &c.name == name) else {
+            let available: Vec<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+            eyre::bail!("no contract named {name} in {}; available: {}", path.display(), available.join(", "));
+        };
+        let dependency = evm::Dependency::deploy(name, contract, &with_deps.exec_options()?)
+            .wrap_err_with(|| format!("deploying dependency {name}"))?;
+        eprintln!("Note: deployed dependency {name} at {}", dependency.address);
+        with_deps.dependencies.push(dependency);
+    }
+    with_deps.deployed = args.deployed;
+    Ok(with_deps)
+}
+
+/// Where `--deploy-lib` places the library `name`: stable across runs and
+/// clear of the caller's CREATE addresses.
+fn library_address(name: &str) -> Address {
+    Address::from_word(alloy_primitives::keccak256(format!("sigscan:library:{name}")))
+}
+
+/// Gas estimation (original behavior). With several files, each is compiled
+/// and measured on its own and contract names are prefixed with their file.
+fn cmd_gas(inputs: &[PathBuf], args: &GasArgs) -> eyre::Result<()> {
+    // Pin the fork's block up front: libraries, dependencies, each input and
+    // a `.sol` baseline would otherwise each resolve `latest` on their own.
+    let connected;
+    let args = match (&args.fork_url, &args.fork) {
+        (Some(url), None) => {
+            let fork = fork::Fork::connect(url, args.fork_block).wrap_err_with(|| format!("forking {url}"))?;
+            connected = GasArgs { fork: Some(fork), ..args.clone() };
+            &connected
+        }
+        _ => args,
+    };
+    let deployed;
+    let args = match args.deploy_libs.is_empty() && args.deps.is_empty() {
+        true => args,
+        false => {
+            deployed = deploy_dependencies(&deploy_libraries(args)?)?;
+            &deployed
+        }
+    };
+    let compile_opts = args.compile_options();
+    if !inputs.is_empty() && (args.artifact.is_some() || args.bytecode.is_some()) {
+        eyre::bail!("--artifact and --bytecode replace .sol inputs; pass one or the other");
+    }
+    // An artifact or ABI path stands in for the source in reports and output.
+    let (sol_files, mut compiled) = match (&args.artifact, &args.bytecode, &args.abi) {
+        (Some(artifact), _, _) => (vec![artifact.clone()], vec![compile::load_artifacts(artifact)?]),
+        (None, Some(bytecode), Some(abi)) => (vec![abi.clone()], vec![vec![compile::load_bytecode(bytecode, abi)?]]),
+        _ => {
+            let sol_files = expand_sol_inputs(inputs)?;
+            if sol_files.is_empty() {
+                let inputs: Vec<String> = inputs.iter().map(|p| p.display().to_string()).collect();
+                eyre::bail!("no .sol files found in {}", inputs.join(", "));
             }
+            let mut compiled = Vec::new();
+            for path in &sol_files {
+                validate_sol_file(path)?;
+                compiled.push(compile::compile_with_options(path, &compile_opts)?);
+            }
+            (sol_files, compiled)
+        }
+    };
+    // Compiled contracts are linked already; artifacts and raw bytecode are not.
+    for contract in compiled.iter_mut().flatten() {
+        compile::link_libraries(contract, &args.links);
+    }
+    let sol_file = &sol_files[0];
+    let multi = sol_files.len() > 1;
+    if multi && args.format == OutputFormat::Sarif {
+        eyre::bail!("--format sarif takes a single .sol file");
+    }
+    let lock_path = args.lockfile.as_deref().unwrap_or(Path::new("sigscan.lock"));
+    let first_compiled = compiled.iter().find(|c| !c.is_empty()).map_or(&[][..], Vec::as_slice);
+    let lock = lockfile::Lock::current(first_compiled, &compile_opts);
+    if args.locked {
+        lockfile::load_lock(lock_path)?.check(&lock)?;
+    }
+    let reports = if multi {
+        // `--contract` names a contract in any of the files, bare or prefixed.
+        let qualified = |path: &Path, c: &types::CompiledContract| format!("{}:{}", path.display(), c.name);
+        let named = |path: &Path, c: &types::CompiledContract, name: &str| c.name == name || qualified(path, c) == name;
+        let all: Vec<(&PathBuf, &types::CompiledContract)> =
+            sol_files.iter().zip(&compiled).flat_map(|(p, cs)| cs.iter().map(move |c| (p, c))).collect();
+        if let Some(unknown) = args.contracts.iter().find(|n| !all.iter().any(|(p, c)| named(p, c, n))) {
+            let available: Vec<String> = all.iter().map(|(p, c)| qualified(p, c)).collect();
+            eyre::bail!("no contract named {unknown}; available: {}", available.join(", "));
+        }
+        let file_args = GasArgs { contracts: Vec::new(), qualify_contracts: true, ..args.clone() };
+        let mut reports = Vec::new();
+        for (path, contracts) in sol_files.iter().zip(compiled) {
+            let selected: Vec<types::CompiledContract> = contracts
+                .into_iter()
+                .filter(|c| args.contracts.is_empty() || args.contracts.iter().any(|n| named(path, c, n)))
+                .collect();
+            reports.extend(gas_reports(path, &selected, &file_args)?);
+        }
+        reports
+    } else {
+        gas_reports(sol_file, first_compiled, args)?
+    };
+    if args.write_lock {
+        lockfile::write_lock(lock_path, &lock)?;
+    }
+    if let Some(path) = &args.access_list_out {
+        let lists = serde_json::to_string_pretty(&output::access_lists(&reports))?;
+        std::fs::write(path, lists).wrap_err_with(|| format!("writing {}", path.display()))?;
+    }
+    if let Some(path) = &args.snapshot {
+        let snapshot = output::render(&reports, OutputFormat::GasSnapshot, sol_file, None)? + "\n";
+        std::fs::write(path, snapshot).wrap_err_with(|| format!("writing {}", path.display()))?;
+    }
+
+    if let Some(path) = &args.baseline {
+        let baseline = if path.extension().is_some_and(|ext| ext == "sol") {
+            validate_sol_file(path)?;
+            let contracts = compile::compile_with_options(path, &compile_opts)?;
+            diff::baseline_from_reports(&gas_reports(path, &contracts, args)?)
+        } else {
+            diff::load_baseline(path)?
         };
-        reports.push(ContractReport {
-            contract: contract.name.clone(),
-            functions,
-        });
+        let diffs = diff::diff(&baseline, &reports);
+        println!("{}", serde_json::to_string_pretty(&diffs)?);
+        let table = diff::render_table(&diffs);
+        if !table.is_empty() {
+            eprintln!("{table}");
+        }
+        if let Some(threshold) = args.threshold {
+            let regressed: Vec<String> = diff::regressions(&diffs, threshold)
+                .iter()
+                .map(|d| format!("{}.{}", d.contract, d.signature))
+                .collect();
+            if !regressed.is_empty() {
+                eprintln!("Gas regressions over {threshold}%: {}", regressed.join(", "));
+                std::process::exit(1);
+            }
+        }
+    } else if !args.compare_contracts.is_empty() {
+        let rows = diff::compare_contracts(&reports, &args.compare_contracts)?;
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else if !args.streams() {
+        let summary = args.summary.then(|| output::summarize(&reports));
+        println!("{}", output::render(&reports, args.format, sol_file, summary.as_ref())?);
     }
 
-    let json = serde_json::to_string_pretty(&reports)?;
-    println!("{json}");
+    if args.fail_on_budget || args.fail_on_halt || args.fail_on_revert {
+        eprintln!("{}", output::status_summary(&reports));
+    }
+    if args.fail_on_budget {
+        let over: Vec<String> = reports
+            .iter()
+            .flat_map(|r| {
+                r.functions
+                    .iter()
+                    .filter(|f| f.over_budget == Some(true))
+                    .map(move |f| format!("{}.{}", r.contract, f.signature))
+            })
+            .collect();
+        if !over.is_empty() {
+            eprintln!("Gas budget exceeded: {}", over.join(", "));
+            std::process::exit(1);
+        }
+    }
+    if args.fail_on_halt {
+        let halted: Vec<String> = reports
+            .iter()
+            .flat_map(|r| {
+                r.functions
+                    .iter()
+                    .filter(|f| f.status == types::ExecutionStatus::Halt)
+                    .map(move |f| format!("{}.{}", r.contract, f.signature))
+            })
+            .collect();
+        if !halted.is_empty() {
+            eprintln!("Functions halted: {}", halted.join(", "));
+            std::process::exit(1);
+        }
+    }
+    if args.fail_on_revert {
+        let failed: Vec<String> = reports
+            .iter()
+            .flat_map(|r| {
+                let deploy = r.deploy_error.as_ref().map(|_| format!("{} (deployment)", r.contract));
+                let calls = r
+                    .functions
+                    .iter()
+                    .filter(|f| f.status != types::ExecutionStatus::Success)
+                    .map(move |f| format!("{}.{}", r.contract, f.signature));
+                deploy.into_iter().chain(calls)
+            })
+            .collect();
+        if !failed.is_empty() {
+            eprintln!("Did not succeed: {}", failed.join(", "));
+            std::process::exit(1);
+        }
+    }
     Ok(())
 }
 
+/// The `.sol` files named on the command line, with directories replaced by
+/// the `.sol` files under them, sorted. Dependency, build-output and hidden
+/// directories below a given directory are not searched.
+fn expand_sol_inputs(inputs: &[PathBuf]) -> eyre::Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> eyre::Result<()> {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+            .wrap_err_with(|| format!("reading {}", dir.display()))?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        entries.sort();
+        for path in entries {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if path.is_dir() {
+                if !name.starts_with('.') && !["node_modules", "lib", "out", "cache"].contains(&name) {
+                    walk(&path, out)?;
+                }
+            } else if path.extension().is_some_and(|ext| ext == "sol") {
+                out.push(path);
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            walk(input, &mut files)?;
+        } else {
+            files.push(input.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// Execute every compiled contract and assemble the per-contract reports.
+fn gas_reports(
+    sol_file: &Path,
+    contracts: &[types::CompiledContract],
+    args: &GasArgs,
+) -> eyre::Result<Vec<ContractReport>> {
+    let contracts = &select_contracts(contracts, &args.contracts)?;
+    let opts = args.exec_options()?;
+    let deadline = args.max_runtime.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let out_of_time = || deadline.is_some_and(|d| Instant::now() >= d);
+    // Contracts are independent (each deploys into its own state), so workers
+    // pull them off a shared counter; reports keep compile order.
+    let source = sol_file.display().to_string();
+    let finish = |report: ContractReport| ContractReport {
+        contract: match args.qualify_contracts {
+            true => format!("{source}:{}", report.contract),
+            false => report.contract.clone(),
+        },
+        source: source.clone(),
+        ..report
+    };
+    // Internal functions join their contract's report after the loop, so
+    // with them the reports can only be streamed at the end.
+    let stream_each = args.streams() && args.expose_internal.is_empty();
+    let next = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(contracts.len()).max(1);
+    let measured: eyre::Result<Vec<Vec<(usize, ContractReport)>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(contract) = contracts.get(i) else { break };
+                        let report = finish(if out_of_time() {
+                            ContractReport { truncated: true, ..ContractReport::new(contract.name.clone(), Vec::new()) }
+                        } else {
+                            contract_report(contract, args, &opts)
+                        });
+                        if stream_each {
+                            print_ndjson(&report)?;
+                        }
+                        done.push((i, report));
+                    }
+                    Ok(done)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().expect("contract worker panicked")).collect()
+    });
+    let mut measured: Vec<(usize, ContractReport)> = measured?.into_iter().flatten().collect();
+    measured.sort_by_key(|(i, _)| *i);
+    let mut reports: Vec<ContractReport> = measured.into_iter().map(|(_, r)| r).collect();
+    let truncated = reports.iter().filter(|r| r.truncated).count();
+    if truncated > 0 {
+        eprintln!("Warning: --max-runtime reached; {truncated} of {} contracts not measured", contracts.len());
+    } else if !args.expose_internal.is_empty() && !out_of_time() {
+        let compile_opts = args.compile_options();
+        for (contract, measured) in measure_internal_functions(sol_file, &args.expose_internal, &compile_opts, &opts)? {
+            // Named as `finish` named the measured reports, `FILE:NAME` included.
+            let internal = finish(ContractReport::new(contract, measured));
+            match reports.iter_mut().find(|r| r.contract == internal.contract) {
+                Some(report) => report.functions.extend(internal.functions),
+                None => reports.push(internal),
+            }
+        }
+    }
+    if args.streams() && !stream_each {
+        reports.iter().try_for_each(print_ndjson)?;
+    }
+    Ok(reports)
+}
+
+/// Print `report` as one NDJSON line and flush, so a consumer sees each
+/// contract as soon as it is measured (`--format ndjson`).
+fn print_ndjson(report: &ContractReport) -> eyre::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", serde_json::to_string(report)?)?;
+    Ok(stdout.flush()?)
+}
+
+/// The contracts named by `--contract`, in compile order; all of them when
+/// none are named.
+fn select_contracts<'a>(
+    contracts: &'a [types::CompiledContract],
+    names: &[String],
+) -> eyre::Result<Vec<&'a types::CompiledContract>> {
+    let available: Vec<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+    if let Some(unknown) = names.iter().find(|name| !available.contains(&name.as_str())) {
+        eyre::bail!("no contract named {unknown}; available: {}", available.join(", "));
+    }
+    Ok(contracts.iter().filter(|c| names.is_empty() || names.contains(&c.name)).collect())
+}
+
+/// Measure one compiled contract and attach the report fields set by `args`.
+fn contract_report(contract: &types::CompiledContract, args: &GasArgs, opts: &evm::ExecOptions) -> ContractReport {
+    let (mut report, runtime_bytecode) = sigscan::report_contract(contract, opts);
+    if args.code_size
+        && let (Some(runtime), Some(metadata)) = (report.runtime_size, report.metadata_size)
+    {
+        report.code_size = Some(types::CodeSize { runtime, runtime_without_metadata: runtime - metadata });
+    }
+    if args.include_bytecode {
+        report.bytecode = Some(format!("0x{}", hex::encode(&contract.bytecode)));
+        report.runtime_bytecode = runtime_bytecode.map(|code| format!("0x{}", hex::encode(code)));
+    }
+    report
+}
+
+/// `--expose-internal` measurements, grouped under the bare name of each
+/// defining contract.
+fn measure_internal_functions(
+    sol_file: &Path,
+    names: &[String],
+    compile_opts: &compile::CompileOptions,
+    opts: &evm::ExecOptions,
+) -> eyre::Result<Vec<(String, Vec<types::FunctionReport>)>> {
+    let source = std::fs::read_to_string(sol_file)?;
+    let mut by_contract: Vec<(String, Vec<&harness::InternalFunction>)> = Vec::new();
+    let internals = harness::internal_functions(&source);
+    for name in names {
+        let matches: Vec<_> = internals.iter().filter(|f| &f.name == name).collect();
+        if matches.is_empty() {
+            eprintln!("Warning: no internal function named {name} in {}", sol_file.display());
+        }
+        for f in matches {
+            if !f.is_exposable() {
+                eprintln!("Warning: {}.{name} takes or returns a storage pointer; cannot expose", f.contract);
+                continue;
+            }
+            match by_contract.iter_mut().find(|(c, _)| *c == f.contract) {
+                Some((_, fns)) => fns.push(f),
+                None => by_contract.push((f.contract.clone(), vec![f])),
+            }
+        }
+    }
+
+    let mut measured = Vec::new();
+    for (contract, fns) in by_contract {
+        match harness::measure_internal(sol_file, &source, &contract, &fns, compile_opts, opts) {
+            Ok(reports) => measured.push((contract, reports)),
+            Err(e) => eprintln!("Warning: {contract} harness - {e}"),
+        }
+    }
+    Ok(measured)
+}
+
 /// Storage layout analysis.
 fn cmd_storage_layout(sol_file: &PathBuf) -> eyre::Result<()> {
     validate_sol_file(sol_file)?;
@@ -200,10 +1208,10 @@ fn cmd_sig_db(selector: &str) -> eyre::Result<()> {
 }
 
 /// Fuzz testing.
-fn cmd_fuzz(sol_file: &PathBuf, rounds: u32) -> eyre::Result<()> {
+fn cmd_fuzz(sol_file: &PathBuf, rounds: u32, max_len: usize) -> eyre::Result<()> {
     validate_sol_file(sol_file)?;
     let contracts = compile::compile(sol_file)?;
-    let reports = fuzzer::fuzz_contracts(&contracts, rounds);
+    let reports = fuzzer::fuzz_contracts(&contracts, rounds, max_len);
     let json = serde_json::to_string_pretty(&reports)?;
     println!("{json}");
     Ok(())
@@ -222,3 +1230,36 @@ fn validate_sol_file(sol_file: &PathBuf) -> eyre::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_functions_join_qualified_reports() {
+        if std::process::Command::new("forge").arg("--version").output().is_err() {
+            eprintln!("forge not installed; skipping");
+            return;
+        }
+        // Two files, as a multi-file run measures them: each on its own with
+        // contract names qualified by their file.
+        let dirs: Vec<_> = (0..2).map(|_| tempfile::tempdir().unwrap()).collect();
+        let args = GasArgs { expose_internal: vec!["_double".into()], qualify_contracts: true, ..Default::default() };
+        for (i, dir) in dirs.iter().enumerate() {
+            let path = dir.path().join("Math.sol");
+            let source = format!(
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\ncontract Math{i} {{\n    \
+                 function _double(uint256 x) internal pure returns (uint256) {{ return 2 * x; }}\n    \
+                 function id() external pure returns (uint256) {{ return {i}; }}\n}}\n"
+            );
+            std::fs::write(&path, source).unwrap();
+            let contracts = compile::compile_with_options(&path, &args.compile_options()).unwrap();
+            let reports = gas_reports(&path, &contracts, &args).unwrap();
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].contract, format!("{}:Math{i}", path.display()));
+            let internal: Vec<&str> =
+                reports[0].functions.iter().filter(|f| f.internal).map(|f| f.name.as_str()).collect();
+            assert_eq!(internal, ["_double"]);
+        }
+    }
+}
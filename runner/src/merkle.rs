@@ -0,0 +1,136 @@
+use crate::calldata::smart_param_value;
+use alloy_dyn_abi::DynSolValue;
+use alloy_json_abi::Function;
+use alloy_primitives::{keccak256, Address, B256, U256};
+use serde::Deserialize;
+
+/// Merkle tree described in an `--args` file's `merkle` section:
+///
+/// ```json
+/// "merkle": { "leaves": ["0x…", "0x…", "0x…"], "prove": 1, "root_slot": "3" }
+/// ```
+///
+/// `leaves` are the already-hashed leaves, in whatever encoding the contract
+/// hashes claims with; `prove` picks the leaf whose proof is supplied. The
+/// root is written to `root_slot` of the measured contract after deployment
+/// and is available to constructor arguments as `"$merkle_root"`; the proof
+/// is available to any argument as `"$merkle_proof"`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MerkleSpec {
+    pub leaves: Vec<B256>,
+    #[serde(default)]
+    pub prove: usize,
+    #[serde(default)]
+    pub root_slot: Option<U256>,
+}
+
+impl MerkleSpec {
+    pub fn root(&self) -> B256 {
+        root(&self.leaves)
+    }
+
+    pub fn proof(&self) -> Vec<B256> {
+        proof(&self.leaves, self.prove)
+    }
+}
+
+/// OpenZeppelin `MerkleProof` pair hash: keccak of the two nodes in sorted order.
+fn hash_pair(a: B256, b: B256) -> B256 {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([lo.as_slice(), hi.as_slice()].concat())
+}
+
+/// Tree layers from the leaves up to the root. An unpaired last node moves up
+/// unchanged.
+fn layers(leaves: &[B256]) -> Vec<Vec<B256>> {
+    let mut layers = vec![leaves.to_vec()];
+    while layers.last().is_some_and(|l| l.len() > 1) {
+        let next = layers
+            .last()
+            .map(|layer| {
+                layer.chunks(2).map(|pair| if let [a, b] = pair { hash_pair(*a, *b) } else { pair[0] }).collect()
+            })
+            .unwrap_or_default();
+        layers.push(next);
+    }
+    layers
+}
+
+pub fn root(leaves: &[B256]) -> B256 {
+    layers(leaves).last().and_then(|l| l.first().copied()).unwrap_or_default()
+}
+
+/// Sibling hashes from leaf `index` up to the root, as `MerkleProof.verify`
+/// takes them. Empty for an out-of-range index.
+pub fn proof(leaves: &[B256], mut index: usize) -> Vec<B256> {
+    if index >= leaves.len() {
+        return Vec::new();
+    }
+    let mut proof = Vec::new();
+    for layer in layers(leaves).iter().take_while(|l| l.len() > 1) {
+        if let Some(&sibling) = layer.get(index ^ 1) {
+            proof.push(sibling);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+/// Calldata for a claim-style function: its `bytes32[]` input named like a
+/// proof gets `spec`'s proof, every other input a smart default.
+pub fn encode_proof_call(func: &Function, spec: &MerkleSpec, caller: Address) -> Option<Vec<u8>> {
+    let is_proof = |p: &alloy_json_abi::Param| p.ty == "bytes32[]" && p.name.to_lowercase().contains("proof");
+    if !func.inputs.iter().any(is_proof) {
+        return None;
+    }
+    let values = func
+        .inputs
+        .iter()
+        .map(|p| {
+            if is_proof(p) {
+                Some(DynSolValue::Array(spec.proof().into_iter().map(|h| DynSolValue::FixedBytes(h, 32)).collect()))
+            } else {
+                smart_param_value(p, caller).ok()
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let mut calldata = func.selector().to_vec();
+    calldata.extend_from_slice(&DynSolValue::Tuple(values).abi_encode_params());
+    Some(calldata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `MerkleProof.processProof`.
+    fn process(leaf: B256, proof: &[B256]) -> B256 {
+        proof.iter().fold(leaf, |node, &sibling| hash_pair(node, sibling))
+    }
+
+    #[test]
+    fn test_every_leaf_proves_against_root() {
+        for n in 1..=7u8 {
+            let leaves: Vec<B256> = (0..n).map(|i| keccak256([i])).collect();
+            let root = root(&leaves);
+            for (i, &leaf) in leaves.iter().enumerate() {
+                assert_eq!(process(leaf, &proof(&leaves, i)), root, "{n} leaves, leaf {i}");
+            }
+        }
+        assert!(proof(&[B256::ZERO], 3).is_empty());
+    }
+
+    #[test]
+    fn test_spec_from_json() {
+        let spec: MerkleSpec = serde_json::from_str(&format!(
+            r#"{{"leaves": ["{}", "{}"], "root_slot": "0x3"}}"#,
+            B256::repeat_byte(1),
+            B256::repeat_byte(2)
+        ))
+        .unwrap();
+        assert_eq!(spec.prove, 0);
+        assert_eq!(spec.root_slot, Some(U256::from(3)));
+        assert_eq!(spec.proof(), vec![B256::repeat_byte(2)]);
+        assert_eq!(spec.root(), hash_pair(B256::repeat_byte(1), B256::repeat_byte(2)));
+    }
+}
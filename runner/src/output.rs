@@ -0,0 +1,629 @@
+use crate::evm::MAX_RUNTIME_SIZE;
+use crate::types::{ContractReport, ContractSummary, ExecutionStatus, FunctionGas, FunctionReport, RunSummary};
+use clap::ValueEnum;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// Serialization format for the `gas` command's report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Pretty-printed JSON object holding `schema_version` and the contract
+    /// reports under `contracts` (default)
+    #[default]
+    Json,
+    /// One compact JSON contract report per line, printed as each contract
+    /// finishes, in completion order
+    Ndjson,
+    /// Prometheus / OpenMetrics text exposition, one sample per function
+    Prometheus,
+    /// SARIF 2.1.0 log of gas findings, for code-scanning annotations
+    Sarif,
+    /// One `<signature> <gas>` line per function, for grep/awk/diff
+    Flat,
+    /// Forge `.gas-snapshot` lines, diffable with `forge snapshot --diff`
+    GasSnapshot,
+    /// CSV with a `contract,function,selector,signature,gas,status` header
+    Csv,
+    /// GitHub-flavored Markdown table per contract, for PR descriptions
+    Markdown,
+}
+
+/// Version of the JSON report's shape, emitted as `schema_version`. Bump it
+/// whenever a field is renamed, removed or changes meaning, so consumers can
+/// reject output they do not understand; purely additive fields keep it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Top level of the JSON report.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    contracts: &'a [ContractReport],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a RunSummary>,
+}
+
+/// Render the collected reports in the requested format. `sol_file` is the
+/// scanned source, which SARIF findings point into. A `summary` is added to
+/// the JSON object and appended to Markdown as a section; other formats
+/// leave it out.
+pub fn render(
+    reports: &[ContractReport],
+    format: OutputFormat,
+    sol_file: &Path,
+    summary: Option<&RunSummary>,
+) -> Result<String> {
+    match format {
+        OutputFormat::Json => {
+            let report = JsonReport { schema_version: SCHEMA_VERSION, contracts: reports, summary };
+            Ok(serde_json::to_string_pretty(&report)?)
+        }
+        OutputFormat::Ndjson => {
+            let lines = reports.iter().map(serde_json::to_string).collect::<serde_json::Result<Vec<_>>>()?;
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Prometheus => Ok(render_prometheus(reports)),
+        OutputFormat::Flat => Ok(render_flat(reports)),
+        OutputFormat::GasSnapshot => Ok(render_gas_snapshot(reports)),
+        OutputFormat::Csv => Ok(render_csv(reports)),
+        OutputFormat::Markdown => {
+            let mut out = render_markdown(reports);
+            if let Some(summary) = summary {
+                out.push_str("\n\n");
+                out.push_str(&render_summary_markdown(summary));
+            }
+            Ok(out)
+        }
+        OutputFormat::Sarif => {
+            let source = std::fs::read_to_string(sol_file).unwrap_or_default();
+            Ok(serde_json::to_string_pretty(&render_sarif(reports, sol_file, &source))?)
+        }
+    }
+}
+
+/// One-line outcome count across all measured functions, with halts (out of
+/// gas, invalid opcode) counted apart from reverts:
+///
+/// ```text
+/// 12 functions: 9 succeeded, 2 reverted, 1 halted
+/// ```
+pub fn status_summary(reports: &[ContractReport]) -> String {
+    let functions: Vec<_> = reports.iter().flat_map(|r| &r.functions).collect();
+    let count = |status| functions.iter().filter(|f| f.status == status).count();
+    format!(
+        "{} functions: {} succeeded, {} reverted, {} halted",
+        functions.len(),
+        count(ExecutionStatus::Success),
+        count(ExecutionStatus::Revert),
+        count(ExecutionStatus::Halt)
+    )
+}
+
+/// Total gas, outcome counts and, per contract, the most and least expensive
+/// successful function (`--summary`).
+pub fn summarize(reports: &[ContractReport]) -> RunSummary {
+    let contracts: Vec<ContractSummary> = reports.iter().map(summarize_contract).collect();
+    RunSummary {
+        total_gas: contracts.iter().map(|c| c.total_gas).sum(),
+        functions: contracts.iter().map(|c| c.functions).sum(),
+        succeeded: contracts.iter().map(|c| c.succeeded).sum(),
+        reverted: contracts.iter().map(|c| c.reverted).sum(),
+        halted: contracts.iter().map(|c| c.halted).sum(),
+        contracts,
+    }
+}
+
+fn summarize_contract(report: &ContractReport) -> ContractSummary {
+    let count = |status| report.functions.iter().filter(|f| f.status == status).count();
+    let succeeded = || report.functions.iter().filter(|f| f.status == ExecutionStatus::Success);
+    let function_gas = |f: &FunctionReport| FunctionGas { signature: f.signature.clone(), gas: f.gas };
+    ContractSummary {
+        contract: report.contract.clone(),
+        total_gas: report.functions.iter().map(|f| f.gas).sum(),
+        functions: report.functions.len(),
+        succeeded: count(ExecutionStatus::Success),
+        reverted: count(ExecutionStatus::Revert),
+        halted: count(ExecutionStatus::Halt),
+        // Ties go to the function listed first.
+        most_expensive: succeeded().rev().max_by_key(|f| f.gas).map(function_gas),
+        least_expensive: succeeded().min_by_key(|f| f.gas).map(function_gas),
+    }
+}
+
+/// `--access-list-out` file: each measured function's access list and the
+/// gas it saves, keyed by contract and then signature:
+///
+/// ```json
+/// { "Vault": { "deposit(uint256)": { "accessList": [...], "gasSaved": 100 } } }
+/// ```
+pub fn access_lists(reports: &[ContractReport]) -> serde_json::Value {
+    let contracts: serde_json::Map<String, serde_json::Value> = reports
+        .iter()
+        .map(|r| {
+            let functions: serde_json::Map<String, serde_json::Value> = r
+                .functions
+                .iter()
+                .filter_map(|f| {
+                    let list = f.access_list.as_ref()?;
+                    Some((f.signature.clone(), json!({ "accessList": list, "gasSaved": f.access_list_gas_saved })))
+                })
+                .collect();
+            (r.contract.clone(), functions.into())
+        })
+        .collect();
+    contracts.into()
+}
+
+// ---------------------------------------------------------------------------
+// Prometheus text format
+// ---------------------------------------------------------------------------
+
+const METRIC_NAME: &str = "sigscan_function_gas";
+
+/// Emit a `sigscan_function_gas` gauge with one sample per measured function:
+///
+/// ```text
+/// sigscan_function_gas{contract="Foo",function="bar(uint256)",status="success"} 45231
+/// ```
+fn render_prometheus(reports: &[ContractReport]) -> String {
+    let mut lines = vec![
+        format!("# HELP {METRIC_NAME} Gas used by a single call to a contract function."),
+        format!("# TYPE {METRIC_NAME} gauge"),
+    ];
+    for report in reports {
+        for func in &report.functions {
+            lines.push(format!(
+                "{METRIC_NAME}{{contract=\"{}\",function=\"{}\",status=\"{}\"}} {}",
+                escape_label(&report.contract),
+                escape_label(&func.signature),
+                func.status.as_str(),
+                func.gas
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Escape a label value per the exposition format: backslash, double quote
+/// and newline are the only characters that need escaping.
+fn escape_label(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Flat text
+// ---------------------------------------------------------------------------
+
+/// One line per function across all contracts. Calls that did not succeed get
+/// their status as a third column so they stand out from real measurements:
+///
+/// ```text
+/// transfer(address,uint256) 51234
+/// mint(uint256) 23512 revert
+/// ```
+fn render_flat(reports: &[ContractReport]) -> String {
+    let mut lines = Vec::new();
+    for func in reports.iter().flat_map(|r| &r.functions) {
+        let mut line = format!("{} {}", func.signature, func.gas);
+        if func.status != ExecutionStatus::Success {
+            line.push(' ');
+            line.push_str(func.status.as_str());
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// Forge gas snapshot
+// ---------------------------------------------------------------------------
+
+/// Lines in forge's `.gas-snapshot` format, sorted as forge writes them:
+///
+/// ```text
+/// Token:approve(address,uint256) (gas: 46123)
+/// Token:transfer(address,uint256) (gas: 51234)
+/// ```
+///
+/// Forge only snapshots passing tests, so calls that did not succeed are left
+/// out rather than recorded with a misleading number.
+fn render_gas_snapshot(reports: &[ContractReport]) -> String {
+    let mut lines: Vec<String> = reports
+        .iter()
+        .flat_map(|r| {
+            r.functions
+                .iter()
+                .filter(|f| f.status == ExecutionStatus::Success)
+                .map(move |f| format!("{}:{} (gas: {})", r.contract, f.signature, f.gas))
+        })
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// CSV
+// ---------------------------------------------------------------------------
+
+/// One row per function under a fixed header. Signatures contain commas, so
+/// fields are quoted per RFC 4180 where needed:
+///
+/// ```text
+/// contract,function,selector,signature,gas,status
+/// Token,transfer,0xa9059cbb,"transfer(address,uint256)",51234,success
+/// ```
+fn render_csv(reports: &[ContractReport]) -> String {
+    let mut lines = vec!["contract,function,selector,signature,gas,status".to_string()];
+    for report in reports {
+        for func in &report.functions {
+            let fields = [
+                csv_field(&report.contract),
+                csv_field(&func.name),
+                csv_field(&func.selector),
+                csv_field(&func.signature),
+                func.gas.to_string(),
+                func.status.as_str().to_string(),
+            ];
+            lines.push(fields.join(","));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Quote a field containing a comma, quote or line break, doubling its quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Markdown
+// ---------------------------------------------------------------------------
+
+/// A heading and table per contract, functions in report order:
+///
+/// ```text
+/// ### Token
+///
+/// | Function | Selector | Gas | Status |
+/// | --- | --- | ---: | --- |
+/// | `transfer(address,uint256)` | `0xa9059cbb` | 51234 | success |
+/// ```
+fn render_markdown(reports: &[ContractReport]) -> String {
+    let sections: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let mut lines = vec![
+                format!("### {}", report.contract),
+                String::new(),
+                "| Function | Selector | Gas | Status |".to_string(),
+                "| --- | --- | ---: | --- |".to_string(),
+            ];
+            for func in &report.functions {
+                lines.push(format!(
+                    "| `{}` | `{}` | {} | {} |",
+                    func.signature,
+                    func.selector,
+                    func.gas,
+                    func.status.as_str()
+                ));
+            }
+            lines.join("\n")
+        })
+        .collect();
+    sections.join("\n\n")
+}
+
+/// `--summary` section: run totals, then one row per contract.
+///
+/// ```text
+/// ### Summary
+///
+/// 3 functions, 120234 gas: 2 succeeded, 1 reverted, 0 halted
+///
+/// | Contract | Gas | Succeeded | Reverted | Halted | Most expensive | Least expensive |
+/// | --- | ---: | ---: | ---: | ---: | --- | --- |
+/// | Token | 120234 | 2 | 1 | 0 | `transfer(address,uint256)` (51234) | `approve(address,uint256)` (46123) |
+/// ```
+fn render_summary_markdown(summary: &RunSummary) -> String {
+    let extreme = |f: &Option<FunctionGas>| match f {
+        Some(f) => format!("`{}` ({})", f.signature, f.gas),
+        None => "-".to_string(),
+    };
+    let mut lines = vec![
+        "### Summary".to_string(),
+        String::new(),
+        format!(
+            "{} functions, {} gas: {} succeeded, {} reverted, {} halted",
+            summary.functions, summary.total_gas, summary.succeeded, summary.reverted, summary.halted
+        ),
+        String::new(),
+        "| Contract | Gas | Succeeded | Reverted | Halted | Most expensive | Least expensive |".to_string(),
+        "| --- | ---: | ---: | ---: | ---: | --- | --- |".to_string(),
+    ];
+    for c in &summary.contracts {
+        lines.push(format!(
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            c.contract,
+            c.total_gas,
+            c.succeeded,
+            c.reverted,
+            c.halted,
+            extreme(&c.most_expensive),
+            extreme(&c.least_expensive)
+        ));
+    }
+    lines.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// SARIF
+// ---------------------------------------------------------------------------
+
+/// `(rule id, level, description)` for each finding kind.
+const SARIF_RULES: [(&str, &str, &str); 3] = [
+    ("gas-budget-exceeded", "error", "Function gas exceeds its @custom:gas-budget"),
+    ("function-never-succeeds", "warning", "No calldata strategy made the function succeed"),
+    ("contract-size-limit", "error", "Runtime bytecode exceeds the EIP-170 size limit"),
+];
+
+/// Map sigScan findings to a SARIF log: over-budget functions, functions that
+/// never succeeded, and contracts over the size limit. Findings are located
+/// at the line declaring the function (or contract) in `source`.
+fn render_sarif(reports: &[ContractReport], sol_file: &Path, source: &str) -> serde_json::Value {
+    let uri = sol_file.to_string_lossy().replace('\\', "/");
+    let contract_line = |name: &str| declaration_line(source, "contract", name).unwrap_or(1);
+    let result = |rule: usize, message: String, line: usize| {
+        let (id, level, _) = SARIF_RULES[rule];
+        json!({
+            "ruleId": id,
+            "level": level,
+            "message": { "text": message },
+            "locations": [{ "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+                "region": { "startLine": line }
+            }}]
+        })
+    };
+
+    let mut results = Vec::new();
+    for report in reports {
+        for f in &report.functions {
+            let line = declaration_line(source, "function", &f.name)
+                .unwrap_or_else(|| contract_line(&report.contract));
+            if let (Some(true), Some(budget)) = (f.over_budget, f.gas_budget) {
+                let msg = format!("{}.{} uses {} gas, over its budget of {budget}", report.contract, f.signature, f.gas);
+                results.push(result(0, msg, line));
+            }
+            if f.status != ExecutionStatus::Success {
+                let msg = format!("{}.{} ends in {} under every strategy", report.contract, f.signature, f.status.as_str());
+                results.push(result(1, msg, line));
+            }
+        }
+        if let Some(size) = report.runtime_size.filter(|&s| s > MAX_RUNTIME_SIZE) {
+            let mut msg = format!("{} runtime is {size} bytes, over the {MAX_RUNTIME_SIZE}-byte limit", report.contract);
+            if let Some(metadata) = report.metadata_size.filter(|&m| m > 0) {
+                msg.push_str(&format!(" ({} without the {metadata}-byte metadata trailer)", size - metadata));
+            }
+            results.push(result(2, msg, contract_line(&report.contract)));
+        }
+    }
+
+    let rules: Vec<_> = SARIF_RULES
+        .iter()
+        .map(|(id, level, text)| json!({
+            "id": id,
+            "shortDescription": { "text": text },
+            "defaultConfiguration": { "level": level }
+        }))
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": { "driver": {
+                "name": "sigscan-runner",
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules
+            }},
+            "results": results
+        }]
+    })
+}
+
+/// 1-based line of the first `<keyword> <name>` declaration in `source`.
+fn declaration_line(source: &str, keyword: &str, name: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    source.lines().position(|line| {
+        line.split(keyword).skip(1).any(|rest| {
+            rest.starts_with(char::is_whitespace)
+                && rest.trim_start().strip_prefix(name).is_some_and(|after| !after.starts_with(is_ident))
+        })
+    })
+    .map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(contract: &str, signature: &str, gas: u64, status: ExecutionStatus) -> ContractReport {
+        ContractReport::new(
+            contract.into(),
+            vec![FunctionReport::new(
+                signature.split('(').next().unwrap_or_default().into(),
+                "0x00000000".into(),
+                signature.into(),
+                gas,
+                status,
+            )],
+        )
+    }
+
+    #[test]
+    fn test_prometheus_sample() {
+        let out = render_prometheus(&[report("Foo", "bar(uint256)", 45231, ExecutionStatus::Success)]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "# HELP sigscan_function_gas Gas used by a single call to a contract function.");
+        assert_eq!(lines[1], "# TYPE sigscan_function_gas gauge");
+        assert_eq!(
+            lines[2],
+            "sigscan_function_gas{contract=\"Foo\",function=\"bar(uint256)\",status=\"success\"} 45231"
+        );
+    }
+
+    #[test]
+    fn test_prometheus_revert_status() {
+        let out = render_prometheus(&[report("Foo", "baz()", 21000, ExecutionStatus::Revert)]);
+        assert!(out.ends_with("status=\"revert\"} 21000"));
+    }
+
+    #[test]
+    fn test_flat_lines() {
+        let reports = [
+            report("Foo", "transfer(address,uint256)", 51234, ExecutionStatus::Success),
+            report("Bar", "mint(uint256)", 23512, ExecutionStatus::Revert),
+        ];
+        assert_eq!(render_flat(&reports), "transfer(address,uint256) 51234\nmint(uint256) 23512 revert");
+    }
+
+    #[test]
+    fn test_gas_snapshot_lines() {
+        let reports = [
+            report("Token", "transfer(address,uint256)", 51234, ExecutionStatus::Success),
+            report("Token", "approve(address,uint256)", 46123, ExecutionStatus::Success),
+            report("Token", "burn(uint256)", 23000, ExecutionStatus::Revert),
+        ];
+        assert_eq!(
+            render_gas_snapshot(&reports),
+            "Token:approve(address,uint256) (gas: 46123)\nToken:transfer(address,uint256) (gas: 51234)"
+        );
+    }
+
+    #[test]
+    fn test_csv_quotes_signatures() {
+        let reports = [
+            report("Token", "transfer(address,uint256)", 51234, ExecutionStatus::Success),
+            report("Token", "burn()", 23000, ExecutionStatus::Revert),
+        ];
+        assert_eq!(
+            render_csv(&reports),
+            "contract,function,selector,signature,gas,status\n\
+             Token,transfer,0x00000000,\"transfer(address,uint256)\",51234,success\n\
+             Token,burn,0x00000000,burn(),23000,revert"
+        );
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn test_markdown_table_per_contract() {
+        let reports = [
+            report("Foo", "bar(uint256)", 45231, ExecutionStatus::Success),
+            report("Baz", "qux()", 21000, ExecutionStatus::Revert),
+        ];
+        let out = render_markdown(&reports);
+        assert!(out.starts_with("### Foo\n\n| Function | Selector | Gas | Status |\n| --- | --- | ---: | --- |\n"));
+        assert!(out.contains("| `bar(uint256)` | `0x00000000` | 45231 | success |\n\n### Baz"));
+        assert!(out.ends_with("| `qux()` | `0x00000000` | 21000 | revert |"));
+    }
+
+    #[test]
+    fn test_status_summary_counts_halts_apart() {
+        let reports = [
+            report("Foo", "a()", 21_000, ExecutionStatus::Success),
+            report("Foo", "b()", 21_000, ExecutionStatus::Revert),
+            report("Bar", "c()", 30_000_000, ExecutionStatus::Halt),
+        ];
+        assert_eq!(status_summary(&reports), "3 functions: 1 succeeded, 1 reverted, 1 halted");
+    }
+
+    #[test]
+    fn test_json_versioned_object() {
+        let reports = [report("Foo", "bar()", 21_000, ExecutionStatus::Success)];
+        let out = render(&reports, OutputFormat::Json, Path::new("Foo.sol"), None).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["contracts"][0]["contract"], "Foo");
+        assert!(value.get("summary").is_none());
+    }
+
+    #[test]
+    fn test_ndjson_line_per_contract() {
+        let reports = [
+            report("Foo", "bar()", 21_000, ExecutionStatus::Success),
+            report("Baz", "qux()", 23_000, ExecutionStatus::Revert),
+        ];
+        let out = render(&reports, OutputFormat::Ndjson, Path::new("Foo.sol"), None).unwrap();
+        let contracts: Vec<String> = out
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["contract"].to_string())
+            .collect();
+        assert_eq!(contracts, ["\"Foo\"", "\"Baz\""]);
+    }
+
+    #[test]
+    fn test_summary_extremes_among_successes() {
+        let mut token = report("Token", "transfer(address,uint256)", 51_234, ExecutionStatus::Success);
+        for (signature, gas, status) in [
+            ("approve(address,uint256)", 46_123, ExecutionStatus::Success),
+            ("burn(uint256)", 90_000, ExecutionStatus::Revert),
+            ("mint(uint256)", 21_000, ExecutionStatus::Halt),
+        ] {
+            let func = FunctionReport::new(signature.into(), "0x00000000".into(), signature.into(), gas, status);
+            token.functions.push(func);
+        }
+        let reports = [token, report("Vault", "pause()", 23_000, ExecutionStatus::Revert)];
+        let summary = summarize(&reports);
+        assert_eq!(summary.total_gas, 231_357);
+        assert_eq!((summary.functions, summary.succeeded, summary.reverted, summary.halted), (5, 2, 2, 1));
+        let token = &summary.contracts[0];
+        assert_eq!(token.most_expensive.as_ref().map(|f| f.gas), Some(51_234));
+        assert_eq!(token.least_expensive.as_ref().map(|f| f.signature.as_str()), Some("approve(address,uint256)"));
+        assert_eq!(summary.contracts[1].most_expensive, None);
+
+        let out = render_summary_markdown(&summary);
+        assert!(out.starts_with("### Summary\n\n5 functions, 231357 gas: 2 succeeded, 2 reverted, 1 halted\n"));
+        assert!(out.ends_with("| Vault | 23000 | 0 | 1 | 0 | - | - |"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("plain"), "plain");
+        assert_eq!(escape_label("a\"b"), "a\\\"b");
+        assert_eq!(escape_label("a\\b"), "a\\\\b");
+        assert_eq!(escape_label("a\nb"), "a\\nb");
+    }
+
+    #[test]
+    fn test_sarif_findings_located() {
+        let source = "pragma solidity ^0.8.0;\ncontract Foo {\n    function barX() external {}\n    \
+                      function bar(uint256 x) external {}\n}\n";
+        let mut reports = vec![report("Foo", "bar(uint256)", 60_000, ExecutionStatus::Revert)];
+        reports[0].functions[0].gas_budget = Some(50_000);
+        reports[0].functions[0].over_budget = Some(true);
+        reports[0].runtime_size = Some(30_000);
+
+        let sarif = render_sarif(&reports, Path::new("src/Foo.sol"), source);
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        let rules: Vec<&str> = results.iter().map(|r| r["ruleId"].as_str().unwrap()).collect();
+        assert_eq!(rules, ["gas-budget-exceeded", "function-never-succeeds", "contract-size-limit"]);
+        let line = |i: usize| &results[i]["locations"][0]["physicalLocation"]["region"]["startLine"];
+        assert_eq!(line(0), 4);
+        assert_eq!(line(2), 2);
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "src/Foo.sol");
+    }
+}
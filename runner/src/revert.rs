@@ -0,0 +1,153 @@
+use crate::abi_decode::format_sol_value;
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{JsonAbi, Param};
+use alloy_primitives::U256;
+
+/// Selector of the `Error(string)` revert `require(cond, "msg")` produces.
+const ERROR_STRING: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of the `Panic(uint256)` revert from failed asserts and checked math.
+const PANIC: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Human-readable reason for a call's revert data:
+///
+/// ```text
+/// revert: insufficient balance          Error(string)
+/// panic: arithmetic overflow (0x11)     Panic(uint256)
+/// 0x82b42900                            anything else, as raw hex
+/// ```
+///
+/// `None` for an empty revert (`revert()`, `require(cond)`).
+pub fn decode_revert(output: &[u8]) -> Option<String> {
+    if output.is_empty() {
+        return None;
+    }
+    if let Some(message) = error_message(output) {
+        return Some(format!("revert: {message}"));
+    }
+    if let Some(code) = panic_code(output) {
+        return Some(match u8::try_from(code).ok().and_then(panic_description) {
+            Some(description) => format!("panic: {description} ({code:#04x})"),
+            None => format!("panic: code {code:#x}"),
+        });
+    }
+    Some(format!("0x{}", hex::encode(output)))
+}
+
+/// A custom error the contract's `abi` declares, with its decoded arguments:
+/// `InsufficientBalance(address account=0x.., uint256 needed=5)`. `None`
+/// when the selector matches no declared error.
+pub fn decode_custom_error(abi: &JsonAbi, output: &[u8]) -> Option<String> {
+    let (selector, data) = output.split_first_chunk::<4>()?;
+    let error = abi.errors().find(|e| e.selector() == selector)?;
+    if error.inputs.is_empty() {
+        return Some(format!("{}()", error.name));
+    }
+    Some(match error.abi_decode_input(data) {
+        Ok(values) => format_error(&error.name, &error.inputs, &values),
+        Err(_) => format!("{}(0x{})", error.name, hex::encode(data)),
+    })
+}
+
+/// `Name(type name=value, ...)`, leaving out names the ABI doesn't give.
+fn format_error(name: &str, params: &[Param], values: &[DynSolValue]) -> String {
+    let args: Vec<String> = params
+        .iter()
+        .zip(values)
+        .map(|(param, value)| {
+            let ty = param.selector_type();
+            match param.name.as_str() {
+                "" => format!("{ty} {}", format_sol_value(value)),
+                name => format!("{ty} {name}={}", format_sol_value(value)),
+            }
+        })
+        .collect();
+    format!("{name}({})", args.join(", "))
+}
+
+/// The message of an `Error(string)` revert.
+fn error_message(output: &[u8]) -> Option<String> {
+    let data = output.strip_prefix(&ERROR_STRING[..])?;
+    let word = |at: usize| data.get(at..at.checked_add(32)?).map(U256::from_be_slice);
+    let offset = usize::try_from(word(0)?).ok()?;
+    let len = usize::try_from(word(offset)?).ok()?;
+    let start = offset.checked_add(32)?;
+    let message = data.get(start..start.checked_add(len)?)?;
+    Some(String::from_utf8_lossy(message).into_owned())
+}
+
+/// The code of a `Panic(uint256)` revert.
+fn panic_code(output: &[u8]) -> Option<U256> {
+    let data = output.strip_prefix(&PANIC[..])?;
+    data.get(..32).map(U256::from_be_slice)
+}
+
+/// What solc's panic codes mean.
+fn panic_description(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "corrupt storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to zero-initialized function",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_string(message: &str) -> Vec<u8> {
+        let mut out = ERROR_STRING.to_vec();
+        out.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+        out.extend_from_slice(&U256::from(message.len()).to_be_bytes::<32>());
+        let mut padded = message.as_bytes().to_vec();
+        padded.resize(message.len().div_ceil(32) * 32, 0);
+        out.extend_from_slice(&padded);
+        out
+    }
+
+    fn panic(code: u64) -> Vec<u8> {
+        let mut out = PANIC.to_vec();
+        out.extend_from_slice(&U256::from(code).to_be_bytes::<32>());
+        out
+    }
+
+    #[test]
+    fn test_decodes_error_string_and_panic() {
+        assert_eq!(decode_revert(&error_string("insufficient balance")).unwrap(), "revert: insufficient balance");
+        assert_eq!(decode_revert(&panic(0x11)).unwrap(), "panic: arithmetic overflow (0x11)");
+        assert_eq!(decode_revert(&panic(0x01)).unwrap(), "panic: assertion failed (0x01)");
+        assert_eq!(decode_revert(&panic(0x99)).unwrap(), "panic: code 0x99");
+    }
+
+    #[test]
+    fn test_falls_back_to_raw_hex() {
+        assert_eq!(decode_revert(&[]), None);
+        assert_eq!(decode_revert(&[0x82, 0xb4, 0x29, 0x00]).unwrap(), "0x82b42900");
+        // A truncated Error(string) is kept whole rather than half-decoded.
+        assert_eq!(decode_revert(&ERROR_STRING).unwrap(), "0x08c379a0");
+    }
+
+    #[test]
+    fn test_custom_errors_from_abi() {
+        let abi = JsonAbi::parse(["error Unauthorized()"]).unwrap();
+        assert_eq!(decode_custom_error(&abi, &[0x82, 0xb4, 0x29, 0x00]).unwrap(), "Unauthorized()");
+        assert_eq!(decode_custom_error(&abi, &[0xde, 0xad, 0xbe, 0xef]), None);
+
+        let params = [
+            Param { ty: "uint256".into(), name: "a".into(), ..Default::default() },
+            Param { ty: "address".into(), ..Default::default() },
+        ];
+        let values = [DynSolValue::Uint(U256::from(1), 256), DynSolValue::Address(alloy_primitives::Address::ZERO)];
+        assert_eq!(
+            format_error("MyError", &params, &values),
+            format!("MyError(uint256 a=1, address {})", alloy_primitives::Address::ZERO)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use crate::atomic::{parse_step, AtomicStep};
+use crate::calldata::{encode_calldata_with_strategy, CallStrategy};
+use alloy_json_abi::Function;
+use alloy_primitives::{Address, U256};
+use eyre::{Result, WrapErr};
+use std::path::Path;
+
+/// Read a `--sequence` file: one `SIG[=ARGS][@WEI]` step per line, in call
+/// order. Blank lines and `#` comments are skipped.
+///
+/// ```text
+/// approve(address,uint256)=(0x00000000000000000000000000000000000000aa,1000)
+/// deposit(uint256)
+/// withdraw(uint256)=(500)
+/// ```
+pub fn load_sequence(path: &Path) -> Result<Vec<String>> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading sequence file {}", path.display()))?;
+    let steps = parse_sequence(&raw);
+    for spec in &steps {
+        step_signature(spec).wrap_err_with(|| format!("in sequence file {}", path.display()))?;
+    }
+    Ok(steps)
+}
+
+fn parse_sequence(raw: &str) -> Vec<String> {
+    raw.lines()
+        .map(|line| line.split_once('#').map_or(line, |(step, _)| step).trim())
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Canonical signature of the function a step calls.
+pub fn step_signature(spec: &str) -> Result<String> {
+    let call = spec.rsplit_once('@').map_or(spec, |(call, _)| call);
+    let sig = call.split_once('=').map_or(call, |(sig, _)| sig).trim();
+    let func = Function::parse(sig).map_err(|e| eyre::eyre!("invalid step signature '{sig}': {e}"))?;
+    Ok(func.signature())
+}
+
+/// Encode a step like an `--atomic-step`, except that omitted arguments fall
+/// back to smart defaults (with `caller` for caller-valued parameters)
+/// instead of being an error.
+pub fn parse_sequence_step(spec: &str, caller: Address) -> Result<AtomicStep> {
+    let (call, value) = match spec.rsplit_once('@') {
+        Some((call, wei)) => (call, wei.trim().parse::<U256>()?),
+        None => (spec, U256::ZERO),
+    };
+    if !call.contains('=') {
+        let func = Function::parse(call.trim()).map_err(|e| eyre::eyre!("invalid step signature '{call}': {e}"))?;
+        if !func.inputs.is_empty() {
+            let calldata = encode_calldata_with_strategy(&func, CallStrategy::SmartDefaults, caller)?;
+            return Ok(AtomicStep { signature: func.signature(), calldata, value });
+        }
+    }
+    parse_step(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sequence_skips_comments_and_blanks() {
+        let raw = "# journey\napprove(address,uint256)=(0xaa,1)\n\n  deposit() @ 5 # funds\n";
+        assert_eq!(parse_sequence(raw), vec!["approve(address,uint256)=(0xaa,1)", "deposit() @ 5"]);
+    }
+
+    #[test]
+    fn test_step_signature_and_value() {
+        assert_eq!(step_signature("transfer(address to, uint256 amount)=(0xaa,1)@0").unwrap(), "transfer(address,uint256)");
+        let step = parse_sequence_step("deposit()@7", Address::ZERO).unwrap();
+        assert_eq!(step.signature, "deposit()");
+        assert_eq!(step.value, U256::from(7u64));
+        assert!(step_signature("not a signature").is_err());
+    }
+}
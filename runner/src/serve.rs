@@ -0,0 +1,184 @@
+use crate::types::{CompiledContract, ContractReport};
+use crate::{compile, gas_reports, validate_sol_file, GasArgs, OutputFormat};
+use eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+/// One line of input: `{"id": 1, "file": "src/Vault.sol", "options": {"exhaustive": true}}`.
+///
+/// `options` takes the `gas` flags by field name; output-shaping flags
+/// (`format`, `baseline`, `threshold`, `compare_contracts`, `lockfile` and
+/// the `fail_on_*` checks) have no effect since every response is a single
+/// JSON line. `format` is reset to JSON so `ndjson` cannot stream reports
+/// between responses. Flags that write files or change the report beyond
+/// the measurements (`locked`, `write_lock`, `access_list_out`, `snapshot`,
+/// `summary`) are rejected rather than silently skipped.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    /// Echoed back so clients can match responses to requests.
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    file: PathBuf,
+    #[serde(default)]
+    options: GasArgs,
+    /// Bypass the artifact cache, e.g. after editing a file the target imports.
+    #[serde(default)]
+    recompile: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reports: Option<Vec<ContractReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Compiled contracts for one file, valid while its contents and the
+/// settings it was compiled with stay the same.
+struct CacheEntry {
+    source_hash: u64,
+    compile_options: compile::CompileOptions,
+    contracts: Vec<CompiledContract>,
+}
+
+/// Serve requests from stdin until EOF, one JSON response line per request.
+pub fn run() -> Result<()> {
+    serve(io::stdin().lock(), &mut io::stdout().lock(), &mut HashMap::new())
+}
+
+/// Answer each request line of `input` with one response line on `stdout`.
+fn serve(input: impl BufRead, stdout: &mut impl Write, cache: &mut HashMap<PathBuf, CacheEntry>) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(req) => {
+                let id = req.id.clone();
+                match handle(&req, cache) {
+                    Ok(reports) => Response { id, reports: Some(reports), error: None },
+                    Err(e) => Response { id, reports: None, error: Some(format!("{e:#}")) },
+                }
+            }
+            Err(e) => Response { id: None, reports: None, error: Some(format!("invalid request: {e}")) },
+        };
+        writeln!(stdout, "{}", serde_json::to_string(&response)?)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn handle(req: &Request, cache: &mut HashMap<PathBuf, CacheEntry>) -> Result<Vec<ContractReport>> {
+    reject_unsupported(&req.options)?;
+    validate_sol_file(&req.file)?;
+    let path = std::fs::canonicalize(&req.file)?;
+    let source_hash = hash_source(&std::fs::read(&path)?);
+    let compile_options = req.options.compile_options();
+
+    let fresh = cache
+        .get(&path)
+        .is_some_and(|e| e.source_hash == source_hash && e.compile_options == compile_options);
+    if req.recompile || !fresh {
+        let contracts = compile::compile_with_options(&path, &compile_options)?;
+        cache.insert(path.clone(), CacheEntry { source_hash, compile_options, contracts });
+    }
+    gas_reports(&path, &cache[&path].contracts, &gas_options(req))
+}
+
+/// The request's `gas` flags, minus the format: reports belong in the
+/// response line, never printed on their own.
+fn gas_options(req: &Request) -> GasArgs {
+    GasArgs { format: OutputFormat::Json, ..req.options.clone() }
+}
+
+/// Fail on flags whose work `cmd_gas` does after measuring, which a request
+/// never reaches.
+fn reject_unsupported(options: &GasArgs) -> Result<()> {
+    let unsupported = [
+        ("locked", options.locked),
+        ("write_lock", options.write_lock),
+        ("access_list_out", options.access_list_out.is_some()),
+        ("snapshot", options.snapshot.is_some()),
+        ("summary", options.summary),
+    ];
+    let set: Vec<&str> = unsupported.iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect();
+    if !set.is_empty() {
+        bail!("not supported in requests: {}", set.join(", "));
+    }
+    Ok(())
+}
+
+fn hash_source(source: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_options_by_field_name() {
+        let req: Request = serde_json::from_str(
+            r#"{"id": 7, "file": "Vault.sol", "options": {"exhaustive": true, "prefill_accounts": 3, "atomic_steps": ["deposit()@1"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(req.id, Some(serde_json::json!(7)));
+        assert!(req.options.exhaustive && !req.options.include_signature_detail);
+        assert_eq!(req.options.prefill_accounts, 3);
+        assert_eq!(req.options.atomic_steps[0].signature, "deposit()");
+
+        let typo = serde_json::from_str::<Request>(r#"{"file": "Vault.sol", "options": {"exhaustiv": true}}"#);
+        assert!(typo.is_err());
+    }
+
+    #[test]
+    fn test_file_writing_options_rejected() {
+        let req: Request =
+            serde_json::from_str(r#"{"file": "Vault.sol", "options": {"summary": true, "snapshot": "gas.snap"}}"#)
+                .unwrap();
+        let err = handle(&req, &mut HashMap::new()).unwrap_err();
+        assert_eq!(err.to_string(), "not supported in requests: snapshot, summary");
+        assert!(reject_unsupported(&GasArgs { format: OutputFormat::Markdown, ..Default::default() }).is_ok());
+    }
+
+    #[test]
+    fn test_ndjson_request_answers_with_one_line() {
+        let dir = std::env::temp_dir().join(format!("sigscan-serve-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("Empty.sol");
+        std::fs::write(&file, "contract Empty {}").unwrap();
+        let path = std::fs::canonicalize(&file).unwrap();
+        // return(0, 0): deploys empty runtime code
+        let bytecode = vec![0x5f, 0x5f, 0xf3];
+        let contract = CompiledContract { name: "Empty".into(), bytecode, ..Default::default() };
+        let mut cache = HashMap::from([(
+            path.clone(),
+            CacheEntry {
+                source_hash: hash_source(&std::fs::read(&path).unwrap()),
+                compile_options: GasArgs::default().compile_options(),
+                contracts: vec![contract],
+            },
+        )]);
+        let request = serde_json::json!({"id": 1, "file": file, "options": {"format": "ndjson"}}).to_string();
+        assert!(!gas_options(&serde_json::from_str(&request).unwrap()).streams());
+        let mut out = Vec::new();
+        serve(request.as_bytes(), &mut out, &mut cache).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 1);
+        let response: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(response["reports"][0]["contract"], "Empty");
+    }
+}
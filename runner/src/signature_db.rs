@@ -1,7 +1,13 @@
 use crate::types::SignatureMatch;
+use alloy_json_abi::Function;
+use eyre::{Result, WrapErr};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::LazyLock;
 
+/// Selector -> candidate signatures, keyed by normalized selector.
+pub type SelectorNames = HashMap<String, Vec<String>>;
+
 /// Bundled signature database loaded from data/signatures.json at compile time.
 static SIGNATURES: LazyLock<HashMap<String, Vec<String>>> = LazyLock::new(|| {
     let data = include_str!("../data/signatures.json");
@@ -54,6 +60,65 @@ pub fn reverse_lookup(query: &str) -> Vec<SignatureMatch> {
     results
 }
 
+/// Load a user-supplied selector database (`--selectors`).
+///
+/// Accepts either a JSON object mapping selectors to a signature or a list of
+/// signatures (the bundled database's format), or 4byte-style text with one
+/// `<selector> <signature>` pair per line (`,` or `:` separators also work,
+/// `#` starts a comment).
+pub fn load_selector_file(path: &Path) -> Result<SelectorNames> {
+    let text = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read selector file {}", path.display()))?;
+    parse_selector_file(&text).wrap_err_with(|| format!("invalid selector file {}", path.display()))
+}
+
+fn parse_selector_file(text: &str) -> Result<SelectorNames> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let mut names = SelectorNames::new();
+    if text.trim_start().starts_with('{') {
+        let entries: HashMap<String, Entry> = serde_json::from_str(text)?;
+        for (selector, entry) in entries {
+            let sigs = match entry {
+                Entry::One(sig) => vec![sig],
+                Entry::Many(sigs) => sigs,
+            };
+            names.entry(normalize_selector(&selector)).or_default().extend(sigs);
+        }
+        return Ok(names);
+    }
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((selector, sig)) = line.split_once([' ', '\t', ',', ':']) else {
+            eyre::bail!("line {}: expected '<selector> <signature>'", i + 1);
+        };
+        names.entry(normalize_selector(selector)).or_default().push(sig.trim().to_string());
+    }
+    Ok(names)
+}
+
+/// Resolve a selector against `names`, then the bundled database, keeping the
+/// first candidate whose keccak selector actually matches.
+pub fn resolve(names: &SelectorNames, selector: &str) -> Option<Function> {
+    let normalized = normalize_selector(selector);
+    let bundled = SIGNATURES.get(&normalized);
+    names
+        .get(&normalized)
+        .into_iter()
+        .chain(bundled)
+        .flatten()
+        .filter_map(|sig| Function::parse(sig).ok())
+        .find(|func| format!("0x{}", hex::encode(func.selector())) == normalized)
+}
+
 /// Normalize a selector to lowercase with 0x prefix.
 fn normalize_selector(selector: &str) -> String {
     let stripped = selector.strip_prefix("0x").unwrap_or(selector);
@@ -83,6 +148,27 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[test]
+    fn test_selector_file_formats() {
+        let json = parse_selector_file(r#"{"0xABCDEF01": "foo(uint256)", "0x12345678": ["a()", "b()"]}"#).unwrap();
+        assert_eq!(json["0xabcdef01"], vec!["foo(uint256)"]);
+        assert_eq!(json["0x12345678"].len(), 2);
+
+        let text = parse_selector_file("# exported\n0xa9059cbb transfer(address,uint256)\n\n12345678,bar()\n").unwrap();
+        assert_eq!(text["0xa9059cbb"], vec!["transfer(address,uint256)"]);
+        assert_eq!(text["0x12345678"], vec!["bar()"]);
+        assert!(parse_selector_file("0x12345678").is_err());
+    }
+
+    #[test]
+    fn test_resolve_checks_selector() {
+        let names = parse_selector_file("0x12345678 transfer(address,uint256)\n0xa9059cbb transfer(address,uint256)").unwrap();
+        // A mislabeled entry is ignored rather than trusted.
+        assert!(resolve(&names, "0x12345678").is_none());
+        let func = resolve(&names, "0xA9059CBB").unwrap();
+        assert_eq!(func.signature(), "transfer(address,uint256)");
+    }
+
     #[test]
     fn test_normalize() {
         assert_eq!(normalize_selector("0xA9059CBB"), "0xa9059cbb");
@@ -0,0 +1,182 @@
+use crate::calldata::smart_param_value;
+use alloy_dyn_abi::DynSolValue;
+use alloy_json_abi::{Function, Param};
+use alloy_primitives::{b256, keccak256, Address, B256, U256};
+use eyre::Result;
+use k256::ecdsa::SigningKey;
+
+/// Deterministic signing key (the first well-known Anvil/Hardhat dev account),
+/// so contracts that store the deployer as signer accept its signatures.
+/// `--caller-key` replaces it.
+pub const DEFAULT_KEY: B256 =
+    b256!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+
+/// Where a function takes its signed message and signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureLayout {
+    /// `bytes32 hash` plus a 65-byte `bytes signature` (r ++ s ++ v).
+    Packed { hash: usize, sig: usize },
+    /// `bytes32 hash` plus split `uint8 v, bytes32 r, bytes32 s`.
+    Split { hash: usize, v: usize },
+}
+
+/// Address controlled by `key`.
+pub fn signer_address(key: B256) -> Result<Address> {
+    let signing_key = SigningKey::from_slice(key.as_slice())?;
+    let point = signing_key.verifying_key().to_encoded_point(false);
+    Ok(Address::from_raw_public_key(&point.as_bytes()[1..]))
+}
+
+/// Sign a 32-byte digest, returning `(v, r, s)` with `v` in `{27, 28}` as
+/// `ecrecover` expects.
+pub fn sign_hash(key: B256, hash: B256) -> Result<(u8, B256, B256)> {
+    let signing_key = SigningKey::from_slice(key.as_slice())?;
+    let (sig, recid) = signing_key.sign_prehash_recoverable(hash.as_slice())?;
+    let (r, s) = sig.split_bytes();
+    Ok((27 + recid.to_byte(), B256::from_slice(&r), B256::from_slice(&s)))
+}
+
+/// `keccak256("\x19Ethereum Signed Message:\n32" ++ hash)`, the digest
+/// `ECDSA.toEthSignedMessageHash` and `eth_sign` produce.
+pub fn eth_signed_message_hash(hash: B256) -> B256 {
+    let mut data = b"\x19Ethereum Signed Message:\n32".to_vec();
+    data.extend_from_slice(hash.as_slice());
+    keccak256(data)
+}
+
+/// Calldata for functions that verify an ECDSA signature over a hash they
+/// receive as an argument, one candidate per common digest convention: the
+/// hash signed directly (`"signature_raw"`) and wrapped as an Ethereum signed
+/// message (`"signature_eth_message"`).
+///
+/// Returns an empty list when the function takes no recognizable signature,
+/// including permit-style functions whose digest is computed on-chain from
+/// EIP-712 domain data; those are not reproduced here.
+pub fn encode_signed_calls(func: &Function, key: B256, caller: Address) -> Vec<(String, Vec<u8>)> {
+    let Some(layout) = signature_layout(func) else {
+        return Vec::new();
+    };
+    let message = keccak256("sigscan.message");
+    [("signature_raw", message), ("signature_eth_message", eth_signed_message_hash(message))]
+        .into_iter()
+        .filter_map(|(label, digest)| {
+            let signature = sign_hash(key, digest).ok()?;
+            let cd = encode_signed(func, layout, message, signature, caller)?;
+            Some((label.to_string(), cd))
+        })
+        .collect()
+}
+
+fn encode_signed(
+    func: &Function,
+    layout: SignatureLayout,
+    message: B256,
+    (v, r, s): (u8, B256, B256),
+    caller: Address,
+) -> Option<Vec<u8>> {
+    let (hash_at, vrs_at) = match layout {
+        SignatureLayout::Packed { hash, .. } => (hash, None),
+        SignatureLayout::Split { hash, v } => (hash, Some(v)),
+    };
+    let values = func
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, p)| match (layout, vrs_at.and_then(|at| i.checked_sub(at))) {
+            _ if i == hash_at => Some(DynSolValue::FixedBytes(message, 32)),
+            (SignatureLayout::Packed { sig, .. }, _) if i == sig => {
+                let mut packed = [r.as_slice(), s.as_slice()].concat();
+                packed.push(v);
+                Some(DynSolValue::Bytes(packed))
+            }
+            (_, Some(0)) => Some(DynSolValue::Uint(U256::from(v), 8)),
+            (_, Some(1)) => Some(DynSolValue::FixedBytes(r, 32)),
+            (_, Some(2)) => Some(DynSolValue::FixedBytes(s, 32)),
+            _ => smart_param_value(p, caller).ok(),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let mut calldata = func.selector().to_vec();
+    calldata.extend_from_slice(&DynSolValue::Tuple(values).abi_encode_params());
+    Some(calldata)
+}
+
+/// Detect a signed-hash parameter alongside either a `bytes` signature (named
+/// like `sig`/`signature`, or the only `bytes` input) or a `uint8, bytes32,
+/// bytes32` run read as `v, r, s`.
+fn signature_layout(func: &Function) -> Option<SignatureLayout> {
+    let inputs = &func.inputs;
+    let is = |p: &Param, ty: &str| p.ty == ty;
+
+    let split_v = inputs.windows(3).position(|w| is(&w[0], "uint8") && is(&w[1], "bytes32") && is(&w[2], "bytes32"));
+    let hash = |excluded: &dyn Fn(usize) -> bool| {
+        let candidates: Vec<usize> =
+            (0..inputs.len()).filter(|&i| is(&inputs[i], "bytes32") && !excluded(i)).collect();
+        let named = candidates.iter().copied().find(|&i| {
+            let name = inputs[i].name.to_lowercase();
+            ["hash", "digest", "message", "msg"].iter().any(|k| name.contains(k))
+        });
+        named.or_else(|| candidates.first().copied())
+    };
+
+    let bytes_params: Vec<usize> = (0..inputs.len()).filter(|&i| is(&inputs[i], "bytes")).collect();
+    let sig = bytes_params
+        .iter()
+        .copied()
+        .find(|&i| inputs[i].name.to_lowercase().contains("sig"))
+        .or_else(|| (bytes_params.len() == 1).then(|| bytes_params[0]));
+    if let Some(sig) = sig
+        && let Some(hash) = hash(&|_| false)
+    {
+        return Some(SignatureLayout::Packed { hash, sig });
+    }
+
+    let v = split_v?;
+    let hash = hash(&|i| i == v + 1 || i == v + 2)?;
+    Some(SignatureLayout::Split { hash, v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    #[test]
+    fn test_default_key_address() {
+        assert_eq!(
+            signer_address(DEFAULT_KEY).unwrap(),
+            address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266")
+        );
+    }
+
+    #[test]
+    fn test_signature_recovers_signer() {
+        let hash = eth_signed_message_hash(keccak256("sigscan.message"));
+        let (v, r, s) = sign_hash(DEFAULT_KEY, hash).unwrap();
+        assert!(v == 27 || v == 28);
+        let sig = Signature::from_scalars(r.0, s.0).unwrap();
+        let recid = RecoveryId::from_byte(v - 27).unwrap();
+        let key = VerifyingKey::recover_from_prehash(hash.as_slice(), &sig, recid).unwrap();
+        let point = key.to_encoded_point(false);
+        assert_eq!(Address::from_raw_public_key(&point.as_bytes()[1..]), signer_address(DEFAULT_KEY).unwrap());
+    }
+
+    #[test]
+    fn test_signature_layout_detection() {
+        let layout = |sig: &str| signature_layout(&Function::parse(sig).unwrap());
+        assert_eq!(
+            layout("function execute(address to, bytes data, bytes32 hash, bytes signature)"),
+            Some(SignatureLayout::Packed { hash: 2, sig: 3 })
+        );
+        assert_eq!(
+            layout("function verify(bytes32 digest, uint8 v, bytes32 r, bytes32 s)"),
+            Some(SignatureLayout::Split { hash: 0, v: 1 })
+        );
+        // EIP-2612 permit hashes on-chain; there is no digest argument to sign.
+        assert_eq!(
+            layout("function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)"),
+            None
+        );
+        assert_eq!(layout("function store(bytes data, bytes extra)"), None);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::fork::Backend;
+use crate::types::{AccessListEntry, StorageChange};
+use alloy_primitives::{Address, B256, Bytes, U256};
+use eyre::{Result, WrapErr, bail};
+use revm::bytecode::Bytecode;
+use revm::database::CacheDB;
+use revm::state::{AccountInfo, EvmState};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Leading byte of prefilled account addresses: `0xac00..00<index>`.
+const PREFILL_PREFIX: u8 = 0xac;
+
+/// Insert `count` funded EOAs so the world state resembles a chain that has
+/// been in use, rather than an empty one holding only the caller.
+///
+/// Addresses are deterministic (`0xac` followed by the big-endian index) so
+/// they can be referenced from args or storage overrides.
+pub fn prefill_accounts(db: &mut CacheDB<Backend>, count: u64) {
+    let balance = U256::from(10u64).pow(U256::from(18u64));
+    for i in 0..count {
+        db.insert_account_info(
+            prefill_address(i),
+            AccountInfo { balance, nonce: 1, ..Default::default() },
+        );
+    }
+}
+
+fn prefill_address(index: u64) -> Address {
+    let mut bytes = [0u8; 20];
+    bytes[0] = PREFILL_PREFIX;
+    bytes[12..].copy_from_slice(&index.to_be_bytes());
+    Address::new(bytes)
+}
+
+/// Frozen storage exported from a live chain: address -> slot -> value.
+pub type StorageSnapshot = BTreeMap<Address, BTreeMap<U256, U256>>;
+
+/// Read a `--storage-snapshot` file, e.g.
+/// `{"0xA0b8...": {"0x0": "0x1", "0x5": "0xde0b6b3a7640000"}}`.
+pub fn load_storage_snapshot(path: &Path) -> Result<StorageSnapshot> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading storage snapshot {}", path.display()))?;
+    parse_storage_snapshot(&raw).wrap_err_with(|| format!("parsing storage snapshot {}", path.display()))
+}
+
+fn parse_storage_snapshot(raw: &str) -> Result<StorageSnapshot> {
+    Ok(serde_json::from_str(raw)?)
+}
+
+/// Parse `--storage SLOT:VALUE`; both are 32-byte hex (or any U256 literal).
+pub fn parse_storage_override(spec: &str) -> Result<(U256, U256)> {
+    let Some((slot, value)) = spec.split_once(':') else {
+        bail!("expected SLOT:VALUE, got {spec:?}");
+    };
+    let word = |s: &str| s.parse::<U256>().map_err(|e| eyre::eyre!("invalid storage word {s:?}: {e}"));
+    Ok((word(slot)?, word(value)?))
+}
+
+/// Write every snapshot slot into `db`, overriding what is already there.
+/// Addresses not yet in the state are created as empty accounts.
+pub fn apply_storage_snapshot(db: &mut CacheDB<Backend>, snapshot: &StorageSnapshot) -> Result<()> {
+    for (&address, slots) in snapshot {
+        for (&slot, &value) in slots {
+            db.insert_account_storage(address, slot, value)
+                .map_err(|e| eyre::eyre!("storage snapshot {address}: {e:?}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Ready `account` to send the measured calls without its key by loading it
+/// into the local state, from the fork under `--fork-url`, so an unreachable
+/// node fails here rather than on the first call. Nothing is changed: the
+/// balance, nonce, code and storage it has stay as they are.
+pub fn prepare_impersonation(db: &mut CacheDB<Backend>, account: Address) -> Result<()> {
+    db.load_account(account).map_err(|e| eyre::eyre!("impersonating {account}: {e}"))?;
+    Ok(())
+}
+
+/// A change to one account's starting state, from `--account ADDR:WEI` or
+/// `--code ADDR:HEX`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountOverride {
+    Balance(Address, U256),
+    Code(Address, Bytes),
+}
+
+/// Parse `--account ADDR:WEI`, the balance decimal or 0x-hex.
+pub fn parse_balance_override(spec: &str) -> Result<AccountOverride> {
+    let (address, balance) = split_override(spec, "WEI")?;
+    let balance = balance.parse().map_err(|e| eyre::eyre!("invalid balance {balance:?}: {e}"))?;
+    Ok(AccountOverride::Balance(address, balance))
+}
+
+/// Parse `--code ADDR:HEX`.
+pub fn parse_code_override(spec: &str) -> Result<AccountOverride> {
+    let (address, code) = split_override(spec, "HEX")?;
+    let code = code.parse().map_err(|e| eyre::eyre!("invalid code {code:?}: {e}"))?;
+    Ok(AccountOverride::Code(address, code))
+}
+
+fn split_override<'a>(spec: &'a str, value: &str) -> Result<(Address, &'a str)> {
+    let Some((address, rest)) = spec.split_once(':') else {
+        bail!("expected ADDR:{value}, got {spec:?}");
+    };
+    let address = address.parse().map_err(|e| eyre::eyre!("invalid address {address:?}: {e}"))?;
+    Ok((address, rest))
+}
+
+/// Apply each override on top of what the state already holds for the
+/// account, so `--account` and `--code` for one address combine, and a
+/// balance override keeps code loaded from a genesis or fork.
+pub fn apply_account_overrides(db: &mut CacheDB<Backend>, overrides: &[AccountOverride]) {
+    for o in overrides {
+        let address = match o {
+            AccountOverride::Balance(address, _) | AccountOverride::Code(address, _) => *address,
+        };
+        let info = db.load_account(address).map(|a| a.info.clone()).unwrap_or_default();
+        let info = match o {
+            AccountOverride::Balance(_, balance) => AccountInfo { balance: *balance, ..info },
+            AccountOverride::Code(_, code) => info.with_code(Bytecode::new_raw(code.clone())),
+        };
+        db.insert_account_info(address, info);
+    }
+}
+
+/// One account of a geth genesis `alloc` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GenesisAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default)]
+    pub nonce: Option<U256>,
+    #[serde(default)]
+    pub code: Option<Bytes>,
+    #[serde(default)]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Genesis allocation: address -> account.
+pub type GenesisAlloc = BTreeMap<Address, GenesisAccount>;
+
+/// Read a `--genesis` file: a geth genesis JSON (only `alloc` is used) or a
+/// bare `alloc` object.
+pub fn load_genesis(path: &Path) -> Result<GenesisAlloc> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading genesis {}", path.display()))?;
+    parse_genesis(&raw).wrap_err_with(|| format!("parsing genesis {}", path.display()))
+}
+
+fn parse_genesis(raw: &str) -> Result<GenesisAlloc> {
+    let mut value: serde_json::Value = serde_json::from_str(raw)?;
+    let alloc = match value.get_mut("alloc") {
+        Some(alloc) => alloc.take(),
+        None => value,
+    };
+    Ok(serde_json::from_value(alloc)?)
+}
+
+/// Insert every genesis account (balance, nonce, code, storage) into `db`.
+pub fn apply_genesis(db: &mut CacheDB<Backend>, alloc: &GenesisAlloc) -> Result<()> {
+    for (&address, account) in alloc {
+        let mut info = AccountInfo {
+            balance: account.balance,
+            nonce: account.nonce.map(|n| n.saturating_to()).unwrap_or_default(),
+            ..Default::default()
+        };
+        if let Some(code) = account.code.as_ref().filter(|c| !c.is_empty()) {
+            info = info.with_code(Bytecode::new_raw(code.clone()));
+        }
+        db.insert_account_info(address, info);
+        for (&slot, &value) in &account.storage {
+            db.insert_account_storage(address, slot, value)
+                .map_err(|e| eyre::eyre!("genesis storage {address}: {e:?}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// EIP-2930 charges per access-list entry, and the EIP-2929 surcharges an
+/// entry spares: a slot costs 1900 to list and saves 2000 on first access,
+/// an account costs 2400 and saves 2500.
+const LIST_ADDRESS_GAS: u64 = 2_400;
+const LIST_SLOT_NET_SAVING: u64 = 100;
+
+/// Access list for the accounts and slots a transaction's resulting `state`
+/// loaded, sorted. Accounts created by the transaction, precompiles and
+/// `warm` accounts (sender, recipient, coinbase) are already warm; a warm
+/// account is still listed for its slots once those save more than the
+/// address entry costs.
+pub fn access_list(state: &EvmState, warm: &[Address]) -> Vec<AccessListEntry> {
+    let is_precompile = |a: &Address| a.0[..19] == [0; 19] && (1..=0x11).contains(&a.0[19]);
+    let mut list: Vec<AccessListEntry> = state
+        .iter()
+        .filter(|(address, account)| !account.is_created() && !is_precompile(address))
+        .filter_map(|(&address, account)| {
+            let mut storage_keys: Vec<B256> = account.storage.keys().map(|&slot| slot.into()).collect();
+            storage_keys.sort();
+            let pays_off = storage_keys.len() as u64 * LIST_SLOT_NET_SAVING > LIST_ADDRESS_GAS;
+            (!warm.contains(&address) || pays_off).then_some(AccessListEntry { address, storage_keys })
+        })
+        .collect();
+    list.sort_by_key(|e| e.address);
+    list
+}
+
+/// Slots of `address` whose value a transaction's resulting `state` changed,
+/// ordered by slot. Slots written back to their original value don't count.
+pub fn storage_changes(state: &EvmState, address: Address) -> Vec<StorageChange> {
+    let Some(account) = state.get(&address) else {
+        return Vec::new();
+    };
+    let mut changes: Vec<StorageChange> = account
+        .storage
+        .iter()
+        .filter(|(_, slot)| slot.is_changed())
+        .map(|(&slot, value)| StorageChange { slot, before: value.original_value, after: value.present_value })
+        .collect();
+    changes.sort_by_key(|c| c.slot);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+
+    #[test]
+    fn test_account_overrides_combine() {
+        let address: Address = "0x00000000000000000000000000000000000000aa".parse().unwrap();
+        let overrides = [
+            parse_code_override("0x00000000000000000000000000000000000000aa:0x6000").unwrap(),
+            parse_balance_override("0x00000000000000000000000000000000000000aa:1000").unwrap(),
+        ];
+        let mut db = CacheDB::new(Backend::Empty);
+        apply_account_overrides(&mut db, &overrides);
+        let info = &db.cache.accounts[&address].info;
+        assert_eq!(info.balance, U256::from(1000));
+        assert_eq!(info.code_hash, keccak256([0x60, 0x00]));
+        assert!(parse_balance_override("0xaa").is_err());
+        assert!(parse_code_override("0x00000000000000000000000000000000000000aa:zz").is_err());
+    }
+
+    #[test]
+    fn test_parse_storage_override() {
+        let word = format!("0x{}01", "00".repeat(31));
+        assert_eq!(parse_storage_override(&format!("0x5:{word}")).unwrap(), (U256::from(5), U256::from(1)));
+        assert!(parse_storage_override("0x5").is_err());
+    }
+
+    #[test]
+    fn test_prefill_addresses_are_distinct() {
+        let mut db = CacheDB::new(Backend::Empty);
+        prefill_accounts(&mut db, 3);
+        assert_eq!(db.cache.accounts.len(), 3);
+        assert_eq!(
+            prefill_address(2),
+            "0xac00000000000000000000000000000000000002".parse::<Address>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_storage_snapshot_applied() {
+        let snapshot = parse_storage_snapshot(
+            r#"{"0x00000000000000000000000000000000000000aa": {"0x0": "0x2a", "0x1": "0x7"}}"#,
+        )
+        .unwrap();
+        let mut db = CacheDB::new(Backend::Empty);
+        apply_storage_snapshot(&mut db, &snapshot).unwrap();
+        let account = &db.cache.accounts[&Address::with_last_byte(0xaa)];
+        assert_eq!(account.storage[&U256::ZERO], U256::from(42u64));
+        assert_eq!(account.storage[&U256::from(1u64)], U256::from(7u64));
+    }
+
+    #[test]
+    fn test_impersonation_keeps_account_state() {
+        let whale = Address::with_last_byte(0x77);
+        let mut db = CacheDB::new(Backend::Empty);
+        db.insert_account_info(whale, AccountInfo { balance: U256::from(5u64), nonce: 9, ..Default::default() });
+        prepare_impersonation(&mut db, whale).unwrap();
+        let info = &db.cache.accounts[&whale].info;
+        assert_eq!((info.balance, info.nonce), (U256::from(5u64), 9));
+    }
+
+    #[test]
+    fn test_impersonation_keeps_fork_balance() {
+        let whale = Address::with_last_byte(0x77);
+        let params = serde_json::json!([whale, "0x10"]);
+        let fork = crate::fork::Fork::seeded(
+            16,
+            &[
+                ("eth_getBalance", params.clone(), serde_json::json!("0xde0b6b3a7640000")),
+                ("eth_getTransactionCount", params.clone(), serde_json::json!("0x3")),
+                ("eth_getCode", params, serde_json::json!("0x")),
+            ],
+        );
+        let mut db = CacheDB::new(Backend::Fork(fork));
+        prepare_impersonation(&mut db, whale).unwrap();
+        let info = &db.cache.accounts[&whale].info;
+        assert_eq!((info.balance, info.nonce), (U256::from(10u64).pow(U256::from(18u64)), 3));
+    }
+
+    #[test]
+    fn test_genesis_alloc_applied() {
+        let alloc = parse_genesis(
+            r#"{
+                "config": {"chainId": 10},
+                "alloc": {
+                    "4200000000000000000000000000000000000015": {
+                        "balance": "0x0",
+                        "code": "0x6001",
+                        "storage": {"0x0000000000000000000000000000000000000000000000000000000000000001": "0x05"}
+                    },
+                    "0x00000000000000000000000000000000000000bb": {"balance": "0xde0b6b3a7640000", "nonce": "0x2"}
+                }
+            }"#,
+        )
+        .unwrap();
+        let mut db = CacheDB::new(Backend::Empty);
+        apply_genesis(&mut db, &alloc).unwrap();
+        let predeploy: Address = "0x4200000000000000000000000000000000000015".parse().unwrap();
+        let account = &db.cache.accounts[&predeploy];
+        assert_eq!(account.info.code.as_ref().unwrap().original_bytes().as_ref(), &[0x60, 0x01]);
+        assert_eq!(account.storage[&U256::from(1u64)], U256::from(5u64));
+        let eoa = &db.cache.accounts[&Address::with_last_byte(0xbb)].info;
+        assert_eq!((eoa.balance, eoa.nonce), (U256::from(10u64).pow(U256::from(18u64)), 2));
+    }
+
+    #[test]
+    fn test_storage_changes_skip_unchanged_slots() {
+        use revm::state::{Account, EvmStorageSlot};
+        let address = Address::repeat_byte(0x20);
+        let mut account = Account::default();
+        let slot = |original: u64, present: u64| EvmStorageSlot {
+            present_value: U256::from(present),
+            ..EvmStorageSlot::new(U256::from(original), 0)
+        };
+        account.storage.insert(U256::from(2), slot(0, 7));
+        account.storage.insert(U256::from(1), slot(5, 5));
+        account.storage.insert(U256::from(0), slot(3, 0));
+        let mut state = EvmState::default();
+        state.insert(address, account);
+
+        let changes = storage_changes(&state, address);
+        let flat: Vec<(u64, u64, u64)> =
+            changes.iter().map(|c| (c.slot.to(), c.before.to(), c.after.to())).collect();
+        assert_eq!(flat, vec![(0, 3, 0), (2, 0, 7)]);
+        assert!(storage_changes(&state, Address::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_access_list_skips_warm_accounts_without_enough_slots() {
+        use revm::state::{Account, EvmStorageSlot};
+        let with_slots = |n: u64| {
+            let mut account = Account::default();
+            for slot in (0..n).rev() {
+                account.storage.insert(U256::from(slot), EvmStorageSlot::new(U256::ZERO, 0));
+            }
+            account
+        };
+        let (caller, target, other) = (Address::repeat_byte(0x10), Address::repeat_byte(0x20), Address::repeat_byte(0x30));
+        let mut state = EvmState::default();
+        state.insert(caller, with_slots(0));
+        state.insert(target, with_slots(2));
+        state.insert(other, with_slots(2));
+        state.insert(Address::with_last_byte(1), with_slots(0));
+
+        let list = access_list(&state, &[caller, target]);
+        assert_eq!(list, vec![AccessListEntry { address: other, storage_keys: vec![B256::ZERO, B256::with_last_byte(1)] }]);
+
+        state.insert(target, with_slots(25));
+        let list = access_list(&state, &[caller, target]);
+        assert_eq!(list.iter().map(|e| e.address).collect::<Vec<_>>(), vec![target, other]);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::types::{TokenBaseline, TokenStandard};
+use alloy_dyn_abi::DynSolValue;
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{Address, U256};
+
+/// Recipient/spender/operator used in standard calls: a plain address that is
+/// neither the caller nor zero, so "transfer to self/zero" guards pass.
+const RECIPIENT: Address = Address::new([
+    0x70, 0x6b, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01,
+]);
+
+/// A standard function with its typical gas range as a standalone transaction
+/// (intrinsic cost included), spanning the common OpenZeppelin/Solmate
+/// implementations from warm, existing balances to first-time writes.
+struct StandardFunction {
+    signature: &'static str,
+    typical: Option<(u64, u64)>,
+}
+
+const fn f(signature: &'static str, min: u64, max: u64) -> StandardFunction {
+    StandardFunction { signature, typical: Some((min, max)) }
+}
+
+/// Required by the standard, but without a meaningful baseline.
+const fn untracked(signature: &'static str) -> StandardFunction {
+    StandardFunction { signature, typical: None }
+}
+
+const ERC20: &[StandardFunction] = &[
+    f("totalSupply()", 21_000, 24_500),
+    f("balanceOf(address)", 21_500, 25_000),
+    f("allowance(address,address)", 21_500, 25_500),
+    f("transfer(address,uint256)", 34_000, 65_000),
+    f("approve(address,uint256)", 24_000, 47_000),
+    f("transferFrom(address,address,uint256)", 40_000, 75_000),
+];
+
+const ERC721: &[StandardFunction] = &[
+    f("balanceOf(address)", 21_500, 25_500),
+    f("ownerOf(uint256)", 21_500, 25_500),
+    f("getApproved(uint256)", 21_500, 26_000),
+    f("isApprovedForAll(address,address)", 21_500, 25_500),
+    f("approve(address,uint256)", 26_000, 50_000),
+    f("setApprovalForAll(address,bool)", 24_000, 47_000),
+    f("transferFrom(address,address,uint256)", 35_000, 85_000),
+    f("safeTransferFrom(address,address,uint256)", 36_000, 90_000),
+    untracked("safeTransferFrom(address,address,uint256,bytes)"),
+];
+
+const ERC1155: &[StandardFunction] = &[
+    f("balanceOf(address,uint256)", 21_500, 25_500),
+    untracked("balanceOfBatch(address[],uint256[])"),
+    f("isApprovedForAll(address,address)", 21_500, 25_500),
+    f("setApprovalForAll(address,bool)", 24_000, 47_000),
+    f("safeTransferFrom(address,address,uint256,uint256,bytes)", 30_000, 70_000),
+    untracked("safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)"),
+];
+
+fn functions(standard: TokenStandard) -> &'static [StandardFunction] {
+    match standard {
+        TokenStandard::Erc20 => ERC20,
+        TokenStandard::Erc721 => ERC721,
+        TokenStandard::Erc1155 => ERC1155,
+    }
+}
+
+/// Standards whose whole required function set the ABI declares. Optional
+/// extensions (metadata, enumeration) don't affect detection.
+pub fn detect(abi: &JsonAbi) -> Vec<TokenStandard> {
+    let declared: Vec<String> = abi.functions().map(|f| f.signature()).collect();
+    [TokenStandard::Erc20, TokenStandard::Erc721, TokenStandard::Erc1155]
+        .into_iter()
+        .filter(|&standard| {
+            functions(standard).iter().all(|f| declared.iter().any(|d| d == f.signature))
+        })
+        .collect()
+}
+
+/// Compare `gas` for `signature` against the typical range of the first of
+/// `standards` that defines one.
+pub fn baseline(standards: &[TokenStandard], signature: &str, gas: u64) -> Option<TokenBaseline> {
+    standards.iter().find_map(|&standard| {
+        let (min, max) = functions(standard).iter().find(|f| f.signature == signature)?.typical?;
+        let verdict = if gas < min {
+            "below"
+        } else if gas > max {
+            "above"
+        } else {
+            "within"
+        };
+        Some(TokenBaseline { standard, typical_min: min, typical_max: max, verdict: verdict.into() })
+    })
+}
+
+/// Calldata for a standard token function with arguments a real holder would
+/// send: the caller as `from`/owner, a distinct recipient, amount 1, and
+/// token id 1 then 0 (collections start counting at either). Empty for
+/// functions outside the ERC-20/721/1155 sets.
+pub fn encode_token_calls(func: &Function, caller: Address) -> Vec<(String, Vec<u8>)> {
+    let signature = func.signature();
+    let is_standard = [TokenStandard::Erc20, TokenStandard::Erc721, TokenStandard::Erc1155]
+        .into_iter()
+        .any(|s| functions(s).iter().any(|f| f.signature == signature));
+    if !is_standard {
+        return Vec::new();
+    }
+    let address_params = func.inputs.iter().filter(|p| p.ty == "address").count();
+    [1u64, 0]
+        .into_iter()
+        .filter_map(|n| {
+            let mut seen_address = false;
+            let values = func
+                .inputs
+                .iter()
+                .map(|p| {
+                    Some(match p.ty.as_str() {
+                        "address" => {
+                            // `from`/`owner` leads whenever a second party follows.
+                            let is_from = !seen_address && address_params >= 2 && !func.name.starts_with("is");
+                            seen_address = true;
+                            DynSolValue::Address(if is_from { caller } else { RECIPIENT })
+                        }
+                        "address[]" => DynSolValue::Array(vec![DynSolValue::Address(caller)]),
+                        "uint256" => DynSolValue::Uint(U256::from(n), 256),
+                        "uint256[]" => DynSolValue::Array(vec![DynSolValue::Uint(U256::from(n), 256)]),
+                        "bool" => DynSolValue::Bool(true),
+                        "bytes" => DynSolValue::Bytes(Vec::new()),
+                        _ => return None,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            let mut calldata = func.selector().to_vec();
+            calldata.extend_from_slice(&DynSolValue::Tuple(values).abi_encode_params());
+            Some((format!("token_standard_{n}"), calldata))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abi(signatures: &[&str]) -> JsonAbi {
+        let items: Vec<String> = signatures.iter().map(|s| format!("function {s}")).collect();
+        JsonAbi::parse(items.iter().map(String::as_str)).unwrap()
+    }
+
+    #[test]
+    fn test_detects_erc20_only() {
+        let mut sigs: Vec<&str> = ERC20.iter().map(|f| f.signature).collect();
+        sigs.push("mint(address,uint256)");
+        assert_eq!(detect(&abi(&sigs)), vec![TokenStandard::Erc20]);
+        assert!(detect(&abi(&sigs[..3])).is_empty());
+    }
+
+    #[test]
+    fn test_detects_erc721_and_erc1155() {
+        let erc721: Vec<&str> = ERC721.iter().map(|f| f.signature).collect();
+        assert_eq!(detect(&abi(&erc721)), vec![TokenStandard::Erc721]);
+        let erc1155: Vec<&str> = ERC1155.iter().map(|f| f.signature).collect();
+        assert_eq!(detect(&abi(&erc1155)), vec![TokenStandard::Erc1155]);
+    }
+
+    #[test]
+    fn test_baseline_verdicts() {
+        let standards = [TokenStandard::Erc20];
+        let within = baseline(&standards, "transfer(address,uint256)", 51_234).unwrap();
+        assert_eq!((within.typical_min, within.typical_max, within.verdict.as_str()), (34_000, 65_000, "within"));
+        assert_eq!(baseline(&standards, "transfer(address,uint256)", 90_000).unwrap().verdict, "above");
+        assert!(baseline(&standards, "mint(address,uint256)", 50_000).is_none());
+        // Shared signatures take the range of the contract's own standard.
+        let nft = baseline(&[TokenStandard::Erc721], "transferFrom(address,address,uint256)", 80_000).unwrap();
+        assert_eq!(nft.verdict, "within");
+    }
+}
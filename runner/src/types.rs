@@ -1,25 +1,503 @@
-use alloy_json_abi::JsonAbi;
+use alloy_json_abi::{JsonAbi, StateMutability};
+use alloy_primitives::{Address, Bytes, B256, U256};
 use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Top-level output for one contract.
 #[derive(Debug, Serialize)]
 pub struct ContractReport {
+    /// Contract name as declared in the source.
     pub contract: String,
+    /// `.sol` file the contract was compiled from, as given on the command
+    /// line; with `contract`, a unique key across multi-file runs.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub source: String,
+    /// One report per measured function, in ABI order.
     pub functions: Vec<FunctionReport>,
+    /// Dummy accounts seeded before deployment (`--prefill-accounts`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefilled_accounts: Option<u64>,
+    /// Init bytecode as deployed, constructor args excluded (`--include-bytecode`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytecode: Option<String>,
+    /// Runtime bytecode returned by the constructor (`--include-bytecode`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_bytecode: Option<String>,
+    /// Gas of the deployment transaction, constructor included, for the
+    /// deployer and constructor arguments that succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deploy_gas: Option<u64>,
+    /// Solc version recorded in the artifact's metadata, e.g.
+    /// `0.8.26+commit.8a97fa7a`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compiler_version: Option<String>,
+    /// Hardfork the contract was deployed and measured under, by its solc
+    /// EVM version name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_version: Option<String>,
+    /// How the deployed constructor's arguments were chosen: a calldata
+    /// strategy label, `args_file` or `constructor_args`. Omitted when the
+    /// constructor takes none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constructor_strategy: Option<String>,
+    /// Why the contract could not be deployed and measured; `functions` is
+    /// then empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deploy_error: Option<String>,
+    /// Deployed code size in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_size: Option<usize>,
+    /// `runtime_size` is over the EIP-170 limit, so mainnet would reject the
+    /// deployment.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub exceeds_size_limit: bool,
+    /// Bytes of `runtime_size` taken by the CBOR metadata trailer. Not
+    /// serialized; see `code_size`.
+    #[serde(skip)]
+    pub metadata_size: Option<usize>,
+    /// Runtime size with and without the metadata trailer (`--code-size`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_size: Option<CodeSize>,
+    /// Deployer used when the configured caller could not deploy, e.g. a
+    /// constructor requiring a hardcoded `msg.sender`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployer: Option<Address>,
+    /// Functions were called through a forwarder contract
+    /// (`--caller-is-contract`), so `msg.sender` had code.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub caller_is_contract: bool,
+    /// Not measured because `--max-runtime` ran out first; `functions` is
+    /// empty and the report as a whole is incomplete.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Token standards the ABI implements (`--token-mode`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub token_standards: Vec<TokenStandard>,
+    /// Account the functions were called from via `--impersonate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonated: Option<Address>,
+    /// Functions skipped over parameter types the encoder cannot handle.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unsupported_types: Vec<UnsupportedFunction>,
+    /// `initialize` call committed after deployment (`--initialize`); it is
+    /// left out of `functions`, which were measured on the initialized state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initializer: Option<FunctionReport>,
+    /// `--sequence` steps in call order, each measured against the state the
+    /// previous steps left behind. Empty when the contract lacks a step's function.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<SequenceStepReport>,
+    /// `--setup` calls committed after deployment, before any function was
+    /// measured.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub setup: Vec<SequenceStepReport>,
+}
+
+impl ContractReport {
+    /// A report with only the always-present fields set.
+    pub fn new(contract: String, functions: Vec<FunctionReport>) -> Self {
+        ContractReport {
+            contract,
+            source: String::new(),
+            functions,
+            prefilled_accounts: None,
+            bytecode: None,
+            runtime_bytecode: None,
+            deploy_gas: None,
+            compiler_version: None,
+            evm_version: None,
+            constructor_strategy: None,
+            deploy_error: None,
+            runtime_size: None,
+            exceeds_size_limit: false,
+            metadata_size: None,
+            code_size: None,
+            deployer: None,
+            caller_is_contract: false,
+            truncated: false,
+            token_standards: Vec::new(),
+            impersonated: None,
+            unsupported_types: Vec::new(),
+            initializer: None,
+            sequence: Vec::new(),
+            setup: Vec::new(),
+        }
+    }
+}
+
+/// Deployed code size in bytes, with the compiler's metadata trailer and
+/// without it. EIP-170 applies to the former.
+#[derive(Debug, Serialize)]
+pub struct CodeSize {
+    pub runtime: usize,
+    pub runtime_without_metadata: usize,
+}
+
+/// A function that could not be called because sigScan cannot encode some
+/// of its parameter types.
+#[derive(Debug, Serialize)]
+pub struct UnsupportedFunction {
+    pub signature: String,
+    pub types: Vec<String>,
+}
+
+/// One measured step of a `--sequence` or `--setup`.
+#[derive(Debug, Serialize)]
+pub struct SequenceStepReport {
+    pub signature: String,
+    pub gas: u64,
+    pub status: ExecutionStatus,
+}
+
+/// One calldata strategy's call of a function (`--all-strategies`).
+#[derive(Debug, Serialize)]
+pub struct StrategyResult {
+    pub strategy: String,
+    pub gas: u64,
+    pub status: ExecutionStatus,
+    pub calldata: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub halt_reason: Option<String>,
+}
+
+/// A storage slot a function's call changed (`--storage-diff`).
+#[derive(Debug, Serialize)]
+pub struct StorageChange {
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+/// A log the reported call emitted (`--include-events`). Logs matching an
+/// event in the ABI carry its name and arguments; others keep the raw topics
+/// and data.
+#[derive(Debug, Serialize)]
+pub struct DecodedEvent {
+    /// Contract that emitted the log.
+    pub address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<EventArg>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Bytes>,
+}
+
+/// One decoded event argument. Indexed arguments of dynamic type only carry
+/// the keccak hash of the value.
+#[derive(Debug, Serialize)]
+pub struct EventArg {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub indexed: bool,
+}
+
+/// One account of an EIP-2930 access list, in the JSON shape
+/// `eth_createAccessList` returns (`--access-list-out`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListEntry {
+    pub address: Address,
+    pub storage_keys: Vec<B256>,
 }
 
 /// Per-function gas execution report.
 #[derive(Debug, Serialize)]
 pub struct FunctionReport {
     pub name: String,
+    /// 4-byte selector as `0x`-prefixed hex.
     pub selector: String,
+    /// Canonical signature, e.g. `transfer(address,uint256)`.
     pub signature: String,
+    /// Position among functions overloading `name`, in ABI order; omitted
+    /// when the name is not overloaded. Key reports by `signature` instead of
+    /// `name` to tell overloads apart.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overload_index: Option<usize>,
+    /// Gas used by the call as a standalone transaction, intrinsic cost included.
     pub gas: u64,
     pub status: ExecutionStatus,
+    /// Declared state mutability: `pure`, `view`, `nonpayable` or `payable`.
+    /// Omitted for fallback calls, which have no ABI function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mutability: Option<StateMutability>,
     /// Which calldata strategy produced this result.
     /// Omitted from JSON when None for backward compatibility.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub strategy: Option<String>,
+    /// Structured input parameters (`--include-signature-detail`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<ParamDetail>>,
+    /// Structured output parameters (`--include-signature-detail`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<ParamDetail>>,
+    /// Budget from a `@custom:gas-budget` natspec tag, if annotated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_budget: Option<u64>,
+    /// Whether `gas` exceeded `gas_budget`. Present only when a budget is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub over_budget: Option<bool>,
+    /// Compiler-generated getter for a public state variable, so `gas` is
+    /// essentially the cost of the storage read.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub auto_getter: bool,
+    /// Whether gas grows per element of dynamic inputs (`--check-unbounded`).
+    /// Omitted when not checked or the function has no such inputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unbounded_gas_growth: Option<bool>,
+    /// Whether execution gas was identical for every input tried
+    /// (`--constant-gas`). Omitted when fewer than two inputs succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constant_gas: Option<bool>,
+    /// Internal function measured through a generated harness
+    /// (`--expose-internal`); `gas` includes the forwarder's overhead.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub internal: bool,
+    /// SLOAD opcodes executed by the reported call (`--storage-ops`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sloads: Option<u64>,
+    /// SSTORE opcodes executed by the reported call (`--storage-ops`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sstores: Option<u64>,
+    /// Times each opcode ran in the reported call, by mnemonic (`--trace`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opcode_counts: Option<BTreeMap<String, u64>>,
+    /// Part of `gas` charged for memory growth, summed over all call frames
+    /// (`--memory-gas`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_expansion_gas: Option<u64>,
+    /// Whether every `--repeat` run of the reported call used the same gas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_stable: Option<bool>,
+    /// Lowest gas across `--repeat` or `--runs` runs; present only when runs
+    /// differed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_min: Option<u64>,
+    /// Highest gas across `--repeat` or `--runs` runs; present only when runs
+    /// differed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_max: Option<u64>,
+    /// Median gas over `--runs` consecutive runs after an uncounted warm-up
+    /// run, each against the state the previous one committed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_median: Option<u64>,
+    /// Gas of the `--exclude-opcodes` opcodes, already subtracted from `gas`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded_opcode_gas: Option<u64>,
+    /// Function missing from the ABI, found in the dispatch table and named
+    /// from the `--selectors` database.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub named_from_selector_db: bool,
+    /// Typical gas for this standard token function (`--token-mode`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_baseline: Option<TokenBaseline>,
+    /// Transaction gas limit set for this function in the `--args` file;
+    /// omitted when measured under the default limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<u64>,
+    /// Input that produced `gas`, when `--fuzz` searched for the most
+    /// expensive one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<Bytes>,
+    /// Wei sent with the call, for payable functions (`--value`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    /// Hash of gas, status, return data and logs (`--fingerprint`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_fingerprint: Option<B256>,
+    /// Contract storage slots the call changed, before and after
+    /// (`--storage-diff`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_diff: Option<Vec<StorageChange>>,
+    /// Why the reported call reverted, decoded from its revert data: the
+    /// `require` message, the panic, a custom error the ABI declares, or the
+    /// raw data. Not captured for calls
+    /// made through `--atomic-step` helpers or the forwarder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// Why the reported call halted, as revm names it, e.g. `OutOfGas(Basic)`,
+    /// `InvalidJump` or `StackUnderflow`. Not captured for calls made through
+    /// `--atomic-step` helpers or the forwarder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub halt_reason: Option<String>,
+    /// Data the reported call returned, when it succeeded with any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<Bytes>,
+    /// `return_data` decoded against the function's ABI outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_values: Option<Vec<DecodedValue>>,
+    /// Raw revert data behind `revert_reason`, for decoding custom errors
+    /// against the contract's ABI. Not serialized.
+    #[serde(skip)]
+    pub revert_data: Option<Bytes>,
+    /// Corrections `--adaptive` applied before the reported call, in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remedies: Option<Vec<String>>,
+    /// Access list that pre-warms what the call touches. Not serialized;
+    /// written to the `--access-list-out` file.
+    #[serde(skip)]
+    pub access_list: Option<Vec<AccessListEntry>>,
+    /// Gas the call saves when sent with `access_list`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_list_gas_saved: Option<i64>,
+    /// Events the reported call emitted, in order (`--include-events`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<DecodedEvent>,
+    /// Every strategy's call in the order tried, the reported one included
+    /// (`--all-strategies`). Fuzz inputs are left out.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub strategies: Vec<StrategyResult>,
+}
+
+impl FunctionReport {
+    /// A report with only the always-present fields set.
+    pub fn new(
+        name: String,
+        selector: String,
+        signature: String,
+        gas: u64,
+        status: ExecutionStatus,
+    ) -> Self {
+        FunctionReport {
+            name,
+            selector,
+            signature,
+            overload_index: None,
+            gas,
+            status,
+            mutability: None,
+            strategy: None,
+            inputs: None,
+            outputs: None,
+            gas_budget: None,
+            over_budget: None,
+            auto_getter: false,
+            unbounded_gas_growth: None,
+            constant_gas: None,
+            internal: false,
+            sloads: None,
+            sstores: None,
+            opcode_counts: None,
+            memory_expansion_gas: None,
+            gas_stable: None,
+            gas_min: None,
+            gas_max: None,
+            gas_median: None,
+            excluded_opcode_gas: None,
+            named_from_selector_db: false,
+            token_baseline: None,
+            gas_limit: None,
+            calldata: None,
+            value: None,
+            execution_fingerprint: None,
+            storage_diff: None,
+            return_data: None,
+            return_values: None,
+            revert_reason: None,
+            halt_reason: None,
+            revert_data: None,
+            remedies: None,
+            access_list: None,
+            access_list_gas_saved: None,
+            events: Vec::new(),
+            strategies: Vec::new(),
+        }
+    }
+}
+
+/// One ABI parameter, with tuple members expanded into `components`.
+#[derive(Debug, Serialize)]
+pub struct ParamDetail {
+    pub name: String,
+    /// Canonical Solidity type, e.g. `uint256[]` or `(address,uint256)[]`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ParamDetail>,
+}
+
+impl ParamDetail {
+    pub fn from_param(param: &alloy_json_abi::Param) -> Self {
+        ParamDetail {
+            name: param.name.clone(),
+            ty: param.selector_type().into_owned(),
+            components: param.components.iter().map(ParamDetail::from_param).collect(),
+        }
+    }
+}
+
+/// Token interface recognized by `--token-mode`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+impl TokenStandard {
+    /// Display name, e.g. `ERC-20`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TokenStandard::Erc20 => "ERC-20",
+            TokenStandard::Erc721 => "ERC-721",
+            TokenStandard::Erc1155 => "ERC-1155",
+        }
+    }
+}
+
+/// A standard token function's gas next to the typical range for that
+/// standard (`--token-mode`).
+#[derive(Debug, Serialize)]
+pub struct TokenBaseline {
+    pub standard: TokenStandard,
+    pub typical_min: u64,
+    pub typical_max: u64,
+    /// `below`, `within` or `above` the typical range.
+    pub verdict: String,
+}
+
+/// Rollup of a whole run (`--summary`): totals and outcome counts across
+/// every measured function, then the same per contract.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct RunSummary {
+    pub total_gas: u64,
+    pub functions: usize,
+    pub succeeded: usize,
+    pub reverted: usize,
+    pub halted: usize,
+    pub contracts: Vec<ContractSummary>,
+}
+
+/// One contract's line in `RunSummary`. The most and least expensive
+/// functions are picked among successful calls only, since a revert's gas
+/// says little about the function; both are omitted when none succeeded.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ContractSummary {
+    pub contract: String,
+    pub total_gas: u64,
+    pub functions: usize,
+    pub succeeded: usize,
+    pub reverted: usize,
+    pub halted: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub most_expensive: Option<FunctionGas>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub least_expensive: Option<FunctionGas>,
+}
+
+/// A function signature and its measured gas.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FunctionGas {
+    pub signature: String,
+    pub gas: u64,
 }
 
 /// Whether the function call succeeded or reverted.
@@ -31,12 +509,55 @@ pub enum ExecutionStatus {
     Halt,
 }
 
+impl ExecutionStatus {
+    /// Lowercase label, matching the JSON serialization.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionStatus::Success => "success",
+            ExecutionStatus::Revert => "revert",
+            ExecutionStatus::Halt => "halt",
+        }
+    }
+}
+
 /// Intermediate representation of a compiled contract.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CompiledContract {
     pub name: String,
     pub abi: JsonAbi,
+    /// Init code, constructor arguments excluded.
     pub bytecode: Vec<u8>,
+    /// `@custom:gas-budget` natspec values, keyed by function signature.
+    pub gas_budgets: HashMap<String, u64>,
+    /// Storage variable names from the artifact's storage layout.
+    pub storage_variables: HashSet<String>,
+    /// Compiler version and settings recorded in the artifact's metadata.
+    pub compiler: CompilerSettings,
+    /// Library references still zeroed in `bytecode`; linking removes them.
+    pub libraries: Vec<LibraryPlaceholder>,
+}
+
+/// An unlinked library's address slot in [`CompiledContract::bytecode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryPlaceholder {
+    /// The 34 hex chars the compiler wrote instead of the address: the start
+    /// of `keccak256("<source path>:<Library>")`.
+    pub hash: String,
+    /// `<source path>:<Library>`, when the artifact's link references name it.
+    pub library: Option<String>,
+    /// Byte offset of the 20-byte slot.
+    pub offset: usize,
+}
+
+/// Resolved compiler settings a contract was built with. Fields are `None`
+/// when the artifact carries no metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompilerSettings {
+    pub solc: Option<String>,
+    pub optimizer: Option<bool>,
+    pub optimizer_runs: Option<u64>,
+    pub evm_version: Option<String>,
+    pub via_ir: Option<bool>,
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,145 @@
+use crate::calldata::smart_param_value;
+use alloy_dyn_abi::DynSolValue;
+use alloy_json_abi::{Function, Param};
+use alloy_primitives::{keccak256, Address, B256, U256};
+
+/// Canonical tuple shape of the EntryPoint v0.6 `UserOperation` struct.
+const USER_OP_V06: &str =
+    "(address,uint256,bytes,bytes,uint256,uint256,uint256,uint256,uint256,bytes,bytes)";
+/// Canonical tuple shape of the EntryPoint v0.7 `PackedUserOperation` struct.
+const USER_OP_V07: &str = "(address,uint256,bytes,bytes,bytes32,uint256,bytes32,bytes,bytes)";
+
+const CALL_GAS_LIMIT: u64 = 100_000;
+const VERIFICATION_GAS_LIMIT: u64 = 150_000;
+const PRE_VERIFICATION_GAS: u64 = 21_000;
+const MAX_FEE_PER_GAS: u64 = 1_000_000_000;
+const MAX_PRIORITY_FEE_PER_GAS: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UserOpVersion {
+    V06,
+    V07,
+}
+
+/// Encode a call to an ERC-4337 validation-style function (`validateUserOp`,
+/// `validatePaymasterUserOp`, `handleOps`, ...) with a structurally valid
+/// UserOperation whose `sender` is the account under test.
+///
+/// Returns `None` when the function takes no UserOperation-shaped parameter,
+/// so callers can fall through to the generic strategies.
+pub fn encode_user_op_call(func: &Function, account: Address, caller: Address) -> Option<Vec<u8>> {
+    if !func.inputs.iter().any(|p| user_op_version(p).is_some()) {
+        return None;
+    }
+    let values = func
+        .inputs
+        .iter()
+        .map(|p| match user_op_version(p) {
+            Some(version) if p.ty == "tuple" => Some(user_op_value(version, account)),
+            Some(version) => Some(DynSolValue::Array(vec![user_op_value(version, account)])),
+            // userOpHash — any non-zero digest; signature checks fail softly
+            // (SIG_VALIDATION_FAILED) rather than reverting.
+            None if p.ty == "bytes32" => Some(DynSolValue::FixedBytes(user_op_hash(), 32)),
+            // missingAccountFunds — nothing to prefund the EntryPoint with.
+            None if p.ty == "uint256" => Some(DynSolValue::Uint(U256::ZERO, 256)),
+            None => smart_param_value(p, caller).ok(),
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let encoded = DynSolValue::Tuple(values).abi_encode_params();
+    let mut calldata = func.selector().to_vec();
+    calldata.extend_from_slice(&encoded);
+    Some(calldata)
+}
+
+/// Match a `tuple` or `tuple[]` parameter against the known UserOperation layouts.
+fn user_op_version(param: &Param) -> Option<UserOpVersion> {
+    if param.ty != "tuple" && param.ty != "tuple[]" {
+        return None;
+    }
+    let shape = param.selector_type();
+    let shape = shape.strip_suffix("[]").unwrap_or(&shape);
+    match shape {
+        USER_OP_V06 => Some(UserOpVersion::V06),
+        USER_OP_V07 => Some(UserOpVersion::V07),
+        _ => None,
+    }
+}
+
+fn user_op_value(version: UserOpVersion, sender: Address) -> DynSolValue {
+    let uint = |v: u64| DynSolValue::Uint(U256::from(v), 256);
+    let head = [
+        DynSolValue::Address(sender),
+        uint(0),                        // nonce
+        DynSolValue::Bytes(Vec::new()), // initCode
+        DynSolValue::Bytes(Vec::new()), // callData
+    ];
+    let tail = [
+        DynSolValue::Bytes(Vec::new()), // paymasterAndData
+        DynSolValue::Bytes(dummy_signature()),
+    ];
+    let gas_fields = match version {
+        UserOpVersion::V06 => vec![
+            uint(CALL_GAS_LIMIT),
+            uint(VERIFICATION_GAS_LIMIT),
+            uint(PRE_VERIFICATION_GAS),
+            uint(MAX_FEE_PER_GAS),
+            uint(MAX_PRIORITY_FEE_PER_GAS),
+        ],
+        UserOpVersion::V07 => vec![
+            DynSolValue::FixedBytes(pack_u128s(VERIFICATION_GAS_LIMIT, CALL_GAS_LIMIT), 32),
+            uint(PRE_VERIFICATION_GAS),
+            DynSolValue::FixedBytes(pack_u128s(MAX_PRIORITY_FEE_PER_GAS, MAX_FEE_PER_GAS), 32),
+        ],
+    };
+    DynSolValue::Tuple(head.into_iter().chain(gas_fields).chain(tail).collect())
+}
+
+/// Pack two values into a bytes32 as `high << 128 | low` (v0.7 gas fields).
+fn pack_u128s(high: u64, low: u64) -> B256 {
+    B256::from((U256::from(high) << 128) | U256::from(low))
+}
+
+fn user_op_hash() -> B256 {
+    keccak256(b"sigscan.userOpHash")
+}
+
+/// 65-byte `r || s || v` blob with the shape of an ECDSA signature.
+fn dummy_signature() -> Vec<u8> {
+    let mut sig = vec![0x11; 64];
+    sig.push(27);
+    sig
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_packed_user_op() {
+        let func = Function::parse(
+            "function validateUserOp((address,uint256,bytes,bytes,bytes32,uint256,bytes32,bytes,bytes) userOp, \
+             bytes32 userOpHash, uint256 missingAccountFunds)",
+        )
+        .unwrap();
+        assert_eq!(user_op_version(&func.inputs[0]), Some(UserOpVersion::V07));
+        assert_eq!(user_op_version(&func.inputs[1]), None);
+    }
+
+    #[test]
+    fn test_detects_user_op_array() {
+        let func = Function::parse(
+            "function handleOps((address,uint256,bytes,bytes,uint256,uint256,uint256,uint256,uint256,bytes,bytes)[] ops, \
+             address beneficiary)",
+        )
+        .unwrap();
+        assert_eq!(user_op_version(&func.inputs[0]), Some(UserOpVersion::V06));
+    }
+
+    #[test]
+    fn test_pack_u128s() {
+        let packed = pack_u128s(1, 2);
+        assert_eq!(packed[15], 1);
+        assert_eq!(packed[31], 2);
+    }
+}
@@ -54,6 +54,30 @@ interface RunnerContractReport {
   functions: RunnerFunctionReport[];
 }
 
+interface RunnerGasOutput {
+  schema_version: number;
+  contracts: RunnerContractReport[];
+}
+
+/** Highest `schema_version` of the runner's JSON output this parser understands. */
+const RUNNER_SCHEMA_VERSION = 1;
+
+/**
+ * Extract the contract reports from `gas` output. Runners that predate
+ * `schema_version` print a bare array; newer schemas are rejected so an
+ * incompatible runner falls back instead of being misread.
+ */
+function parseRunnerReports(stdout: string): RunnerContractReport[] {
+  const output: RunnerGasOutput | RunnerContractReport[] = JSON.parse(stdout);
+  if (Array.isArray(output)) {
+    return output;
+  }
+  if (output.schema_version > RUNNER_SCHEMA_VERSION) {
+    throw new Error(`Unsupported sigscan-runner output schema v${output.schema_version}`);
+  }
+  return output.contracts;
+}
+
 // ---------------------------------------------------------------------------
 // New subcommand types (matches runner/src/types.rs)
 // ---------------------------------------------------------------------------
@@ -309,7 +333,7 @@ export async function compileWithRunner(
     const { stdout, stderr } = await spawnRunner(runnerPath, filePath);
 
     // Parse runner JSON output
-    const reports: RunnerContractReport[] = JSON.parse(stdout);
+    const reports = parseRunnerReports(stdout);
 
     // Regex-parse the source for line locations, visibility, state mutability
     const lineOffsets = buildLineOffsets(source);